@@ -1183,6 +1183,27 @@ impl Builder {
         })
     }
 
+    /// Build a range expression, `lo..hi` or `lo..=hi`
+    pub fn range_expr<L, H>(self, lo: L, hi: H, inclusive: bool) -> P<Expr>
+    where
+        L: Make<P<Expr>>,
+        H: Make<P<Expr>>,
+    {
+        let lo = lo.make(&self);
+        let hi = hi.make(&self);
+        let limits = if inclusive {
+            RangeLimits::Closed
+        } else {
+            RangeLimits::HalfOpen
+        };
+        P(Expr {
+            id: self.id,
+            kind: ExprKind::Range(Some(lo), Some(hi), limits),
+            span: self.span,
+            attrs: self.attrs.into(),
+        })
+    }
+
     // Patterns
 
     pub fn ident_pat<I>(self, name: I) -> P<Pat>
@@ -1209,6 +1230,20 @@ impl Builder {
         })
     }
 
+    pub fn tuple_struct_pat<Pa, P1>(self, path: Pa, pats: Vec<P1>) -> P<Pat>
+    where
+        Pa: Make<Path>,
+        P1: Make<P<Pat>>,
+    {
+        let path = path.make(&self);
+        let pats: Vec<P<Pat>> = pats.into_iter().map(|x| x.make(&self)).collect();
+        P(Pat {
+            id: self.id,
+            kind: PatKind::TupleStruct(path, pats),
+            span: self.span,
+        })
+    }
+
     pub fn qpath_pat<Pa>(self, qself: Option<QSelf>, path: Pa) -> P<Pat>
     where
         Pa: Make<Path>,
@@ -1241,6 +1276,22 @@ impl Builder {
         })
     }
 
+    /// Build an inclusive range pattern, `lo..=hi`
+    pub fn range_pat<L, H>(self, lo: L, hi: H) -> P<Pat>
+    where
+        L: Make<P<Expr>>,
+        H: Make<P<Expr>>,
+    {
+        let lo = lo.make(&self);
+        let hi = hi.make(&self);
+        let end = dummy_spanned(RangeEnd::Included(RangeSyntax::DotDotEq));
+        P(Pat {
+            id: self.id,
+            kind: PatKind::Range(lo, hi, end),
+            span: self.span,
+        })
+    }
+
     pub fn mac_pat<M>(self, mac: M) -> P<Pat>
     where
         M: Make<Mac>,
@@ -1759,6 +1810,34 @@ impl Builder {
         )
     }
 
+    pub fn trait_impl_item<Pa, T>(self, trait_path: Pa, ty: T, items: Vec<ImplItem>) -> P<Item>
+    where
+        Pa: Make<Path>,
+        T: Make<P<Ty>>,
+    {
+        let trait_ref = TraitRef {
+            path: trait_path.make(&self),
+            ref_id: DUMMY_NODE_ID,
+        };
+        let ty = ty.make(&self);
+        Self::item(
+            Ident::invalid(),
+            self.attrs,
+            self.vis,
+            self.span,
+            self.id,
+            ItemKind::Impl(
+                self.unsafety,
+                ImplPolarity::Positive,
+                Defaultness::Final,
+                self.generics,
+                Some(trait_ref),
+                ty,
+                items,
+            ),
+        )
+    }
+
     pub fn extern_crate_item<I>(self, name: I, rename: Option<I>) -> P<Item>
     where
         I: Make<Ident>,
@@ -1929,6 +2008,48 @@ impl Builder {
         )
     }
 
+    pub fn fn_impl_item<I, S, B>(self, name: I, sig: S, block: B) -> ImplItem
+    where
+        I: Make<Ident>,
+        S: Make<FnSig>,
+        B: Make<P<Block>>,
+    {
+        let name = name.make(&self);
+        let sig = sig.make(&self);
+        let block = block.make(&self);
+        let kind = ImplItemKind::Method(sig, block);
+        Self::impl_item_(
+            name,
+            self.attrs,
+            self.vis,
+            Defaultness::Final,
+            self.generics,
+            self.span,
+            self.id,
+            kind,
+        )
+    }
+
+    pub fn ty_alias_impl_item<I, T>(self, name: I, ty: T) -> ImplItem
+    where
+        I: Make<Ident>,
+        T: Make<P<Ty>>,
+    {
+        let name = name.make(&self);
+        let ty = ty.make(&self);
+        let kind = ImplItemKind::TyAlias(ty);
+        Self::impl_item_(
+            name,
+            self.attrs,
+            self.vis,
+            Defaultness::Final,
+            self.generics,
+            self.span,
+            self.id,
+            kind,
+        )
+    }
+
     // Trait Items
 
     /// Called `trait_item_` because `trait_item` is already used for "Item, of ItemKind::Trait".
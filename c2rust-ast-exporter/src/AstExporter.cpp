@@ -3,6 +3,7 @@
 #include <fstream>
 #include <iostream>
 #include <iterator>
+#include <mutex>
 #include <set>
 #include <unordered_map>
 #include <unordered_set>
@@ -27,6 +28,9 @@
 #include "clang/Frontend/CompilerInstance.h"
 #include "clang/Frontend/LangStandard.h"
 #include "clang/Tooling/Tooling.h"
+#if CLANG_VERSION_MAJOR >= 8
+#include "llvm/Support/VirtualFileSystem.h"
+#endif // CLANG_VERSION_MAJOR
 
 #include "AstExporter.hpp"
 #include "ExportResult.hpp"
@@ -276,6 +280,16 @@ class TypeEncoder final : public TypeVisitor<TypeEncoder> {
         VisitQualType(t);
     }
 
+    void VisitAtomicType(const AtomicType *T) {
+        auto t = T->getValueType();
+        auto qt = encodeQualType(t);
+
+        encodeType(T, TagAtomicType,
+                   [qt](CborEncoder *local) { cbor_encode_uint(local, qt); });
+
+        VisitQualType(t);
+    }
+
     void VisitComplexType(const ComplexType *T) {
         auto t = T->getElementType();
         auto qt = encodeQualType(t);
@@ -445,6 +459,11 @@ class TranslateASTVisitor final
 
         /// Expressions that we have seen this macro expand to
         SmallPtrSet<Expr*, 10> Expressions;
+
+        /// For function-like macros, the subexpressions of each expansion
+        /// that were spelled as macro arguments, paired with the index of the
+        /// parameter they were substituted for.
+        DenseMap<Expr*, std::vector<std::pair<unsigned, Expr*>>> Arguments;
     };
 
     ASTContext *Context;
@@ -636,9 +655,73 @@ class TranslateASTVisitor final
 
         info.Expressions.insert(E);
         typeEncoder.VisitQualType(E->getType());
+
+        if (mac->isFunctionLike()) {
+            auto &args = info.Arguments[E];
+            collectMacroArguments(mac, E, args);
+            for (auto &arg : args)
+                typeEncoder.VisitQualType(arg.second->getType());
+        }
         return true;
     }
 
+    // Find the outermost subexpressions of `E` that were spelled as arguments
+    // to the function-like macro `mac`.
+    void collectMacroArguments(MacroInfo *mac, Expr *E,
+                               std::vector<std::pair<unsigned, Expr *>> &args) {
+        for (auto *Child : E->children()) {
+            auto *SubExpr = dyn_cast_or_null<Expr>(Child);
+            if (!SubExpr)
+                continue;
+            int param = getMacroParamIndex(mac, SubExpr->getSourceRange());
+            if (param >= 0)
+                args.emplace_back(param, SubExpr);
+            else
+                collectMacroArguments(mac, SubExpr, args);
+        }
+    }
+
+    // Returns the index of the parameter of `mac` that the whole of `Range`
+    // was substituted for, or -1 if `Range` is not a complete macro argument.
+    int getMacroParamIndex(MacroInfo *mac, SourceRange Range) {
+        auto &Mgr = Context->getSourceManager();
+        auto Begin = Range.getBegin();
+        auto End = Range.getEnd();
+        if (!Mgr.isMacroArgExpansion(Begin) || !Mgr.isMacroArgExpansion(End))
+            return -1;
+
+        // The immediate expansion location of a macro argument is the use of
+        // the parameter in the expanded macro body.
+#if CLANG_VERSION_MAJOR < 7
+        auto ParamLoc = Mgr.getImmediateExpansionRange(Begin).first;
+        auto ParamEndLoc = Mgr.getImmediateExpansionRange(End).first;
+#else // CLANG_VERSION_MAJOR >= 7
+        auto ParamLoc = Mgr.getImmediateExpansionRange(Begin).getBegin();
+        auto ParamEndLoc = Mgr.getImmediateExpansionRange(End).getBegin();
+#endif
+        if (ParamLoc != ParamEndLoc)
+            return -1;
+
+        auto ParamSpelling = Mgr.getSpellingLoc(ParamLoc);
+        if (Mgr.isBeforeInTranslationUnit(ParamSpelling, mac->getDefinitionLoc()) ||
+            Mgr.isBeforeInTranslationUnit(mac->getDefinitionEndLoc(), ParamSpelling))
+            return -1;
+
+        Token Tok;
+        if (Lexer::getRawToken(ParamSpelling, Tok, Mgr, Context->getLangOpts(),
+                               false) ||
+            !Tok.is(tok::raw_identifier))
+            return -1;
+
+        int i = 0;
+        for (auto *Param : mac->params()) {
+            if (Param->getName() == Tok.getRawIdentifier())
+                return i;
+            ++i;
+        }
+        return -1;
+    }
+
     static bool isScalarAsmType(QualType ty) {
         ty = ty.getCanonicalType();
         switch (ty->getTypeClass()) {
@@ -709,13 +792,44 @@ class TranslateASTVisitor final
 
             auto range = SourceRange(Mac->getDefinitionLoc(), Mac->getDefinitionEndLoc());
             encode_entry_raw(Mac, tag, range, QualType(), false,
-                             false, false, childIds, [Name](CborEncoder *local) {
+                             false, false, childIds, [&](CborEncoder *local) {
                                  cbor_encode_string(local, Name.str());
+                                 if (Mac->isFunctionLike())
+                                     encodeMacroArguments(local, Mac, Info, childIds);
                              });
 
         }
     }
 
+    // Encode the parameter names of a function-like macro followed by, for
+    // each expansion in `childIds`, the list of `[param index, argument]`
+    // pairs found in that expansion.
+    void encodeMacroArguments(CborEncoder *local, MacroInfo *Mac,
+                              MacroExpansionInfo &Info,
+                              const std::vector<void *> &childIds) {
+        std::vector<std::string> params;
+        for (auto *Param : Mac->params())
+            params.push_back(Param->getName().str());
+        cbor_encode_string_array(local, params);
+
+        CborEncoder expansions;
+        cbor_encoder_create_array(local, &expansions, childIds.size());
+        for (auto *child : childIds) {
+            auto &args = Info.Arguments[static_cast<Expr *>(child)];
+            CborEncoder argArray;
+            cbor_encoder_create_array(&expansions, &argArray, args.size());
+            for (auto &arg : args) {
+                CborEncoder entry;
+                cbor_encoder_create_array(&argArray, &entry, 2);
+                cbor_encode_uint(&entry, arg.first);
+                cbor_encode_uint(&entry, uintptr_t(arg.second));
+                cbor_encoder_close_container(&argArray, &entry);
+            }
+            cbor_encoder_close_container(&expansions, &argArray);
+        }
+        cbor_encoder_close_container(local, &expansions);
+    }
+
     void encodeSourcePos(CborEncoder *enc, SourceLocation loc,
                          bool isVaList = false) {
         auto &manager = Context->getSourceManager();
@@ -830,11 +944,9 @@ class TranslateASTVisitor final
     }
 
     bool VisitIndirectGotoStmt(IndirectGotoStmt *IGS) {
-        std::string msg =
-            "the GNU C labels-as-values extension is not supported. Aborting.";
-
-        printError(msg, IGS);
-        abort();
+        std::vector<void *> childIds = {IGS->getTarget()};
+        encode_entry(IGS, TagIndirectGotoStmt, childIds);
+        return true;
     }
 
     bool VisitLabelStmt(LabelStmt *LS) {
@@ -909,9 +1021,8 @@ class TranslateASTVisitor final
         return true;
     }
 
-    bool VisitCaseStmt(CaseStmt *CS) {
-        auto expr = CS->getLHS();
-
+    // Evaluate the constant expression of a case label
+    APSInt evaluateCaseValue(CaseStmt *CS, Expr *expr) {
         APSInt value;
         if (!expr->isIntegerConstantExpr(value, *Context)) {
 #if CLANG_VERSION_MAJOR < 8
@@ -931,15 +1042,36 @@ class TranslateASTVisitor final
             value = eval_result.Val.getInt();
 #endif // CLANG_VERSION_MAJOR
         }
+        return value;
+    }
+
+    bool VisitCaseStmt(CaseStmt *CS) {
+        auto expr = CS->getLHS();
+        APSInt value = evaluateCaseValue(CS, expr);
+
+        // GNU case ranges, `case lo ... hi:`, have an upper bound
+        auto rhs = CS->getRHS();
+        APSInt rhs_value;
+        if (rhs)
+            rhs_value = evaluateCaseValue(CS, rhs);
 
         std::vector<void *> childIds{expr, CS->getSubStmt()};
-        encode_entry(CS, TagCaseStmt, childIds, [value](CborEncoder *extra) {
+        if (rhs)
+            childIds.push_back(rhs);
+        encode_entry(CS, TagCaseStmt, childIds, [value, rhs, rhs_value](CborEncoder *extra) {
             cbor_encode_boolean(extra, value.isSigned());
             if (value.isSigned()) {
                 cbor_encode_int(extra, value.getSExtValue());
             } else {
                 cbor_encode_uint(extra, value.getZExtValue());
             }
+            if (rhs) {
+                if (rhs_value.isSigned()) {
+                    cbor_encode_int(extra, rhs_value.getSExtValue());
+                } else {
+                    cbor_encode_uint(extra, rhs_value.getZExtValue());
+                }
+            }
         });
         return true;
     }
@@ -964,6 +1096,8 @@ class TranslateASTVisitor final
     //   List of input constraints
     //   List of output constraints
     //   List of clobbers
+    //   List of input names, empty for unnamed operands
+    //   List of output names, empty for unnamed operands
     //
     // The number of input and output expressions in the child id list will
     // match the length of the corresponding constraint arrays.
@@ -979,6 +1113,7 @@ class TranslateASTVisitor final
             cbor_encode_string(local, E->generateAsmString(*Context));
 
             std::vector<std::string> outputs, inputs, clobbers;
+            std::vector<std::string> output_names, input_names;
             std::vector<TargetInfo::ConstraintInfo> output_infos;
             for (unsigned i = 0, num = E->getNumOutputs(); i < num; ++i) {
                 auto constraint = E->getOutputConstraint(i);
@@ -999,6 +1134,7 @@ class TranslateASTVisitor final
                 }
                 convertedConstraint += SimplifyConstraint(constraint.str());
                 outputs.push_back(convertedConstraint);
+                output_names.push_back(E->getOutputName(i).str());
                 output_infos.push_back(std::move(info));
             }
             for (unsigned i = 0, num = E->getNumInputs(); i < num; ++i) {
@@ -1013,6 +1149,7 @@ class TranslateASTVisitor final
                 }
                 convertedConstraint += SimplifyConstraint(constraint.str());
                 inputs.emplace_back(convertedConstraint);
+                input_names.push_back(E->getInputName(i).str());
             }
             for (unsigned i = 0, num = E->getNumClobbers(); i < num; ++i) {
                 auto clobber = E->getClobber(i);
@@ -1023,6 +1160,8 @@ class TranslateASTVisitor final
             cbor_encode_string_array(local, ArrayRef<std::string>(inputs));
             cbor_encode_string_array(local, ArrayRef<std::string>(outputs));
             cbor_encode_string_array(local, ArrayRef<std::string>(clobbers));
+            cbor_encode_string_array(local, ArrayRef<std::string>(input_names));
+            cbor_encode_string_array(local, ArrayRef<std::string>(output_names));
         });
         return true;
     }
@@ -1063,10 +1202,10 @@ class TranslateASTVisitor final
     bool VisitExpr(Expr *E) {
         curMacroExpansionStack.clear();
 
-        // We only translate constant macro objects to Rust consts, so this
-        // expression must be constant.
-        if (!E->isConstantInitializer(*Context, false))
-            return true;
+        // We only translate constant macro objects to Rust consts, so object
+        // macro expansions must be constant. Function-like macros become
+        // functions and may expand to arbitrary expressions.
+        bool isConstant = E->isConstantInitializer(*Context, false);
 
         auto &Mgr = Context->getSourceManager();
         auto Range = E->getSourceRange();
@@ -1113,7 +1252,8 @@ class TranslateASTVisitor final
             Begin = ExpansionBegin;
             End = ExpansionEnd;
 
-            if (mac->isObjectLike() && VisitMacro(name, Begin, mac, E)) {
+            if ((mac->isFunctionLike() || isConstant) &&
+                VisitMacro(name, Begin, mac, E)) {
                 curMacroExpansionStack.push_back(mac);
             }
         }
@@ -1128,7 +1268,62 @@ class TranslateASTVisitor final
     }
 
     bool VisitGenericSelectionExpr(GenericSelectionExpr *E) {
-        printWarning("Encountered unsupported generic selection expression", E);
+        // The controlling expression, followed by the expression of each
+        // association
+        std::vector<void *> childIds{E->getControllingExpr()};
+        std::vector<QualType> assocTypes;
+        for (unsigned i = 0; i < E->getNumAssocs(); i++) {
+#if CLANG_VERSION_MAJOR >= 9
+            auto assoc = E->getAssociation(i);
+            childIds.push_back(assoc.getAssociationExpr());
+            assocTypes.push_back(assoc.getType());
+#else
+            childIds.push_back(E->getAssocExpr(i));
+            assocTypes.push_back(E->getAssocType(i));
+#endif // CLANG_VERSION_MAJOR
+        }
+
+        // The index of the selected association, followed by the type of
+        // each association, which is null for the `default` association
+        encode_entry(E, TagGenericSelectionExpr, childIds,
+                     [E, &assocTypes, this](CborEncoder *extras) {
+                         cbor_encode_uint(extras, E->getResultIndex());
+                         for (auto t : assocTypes) {
+                             if (t.isNull())
+                                 cbor_encode_null(extras);
+                             else
+                                 cbor_encode_uint(
+                                     extras, typeEncoder.encodeQualType(t));
+                         }
+                     });
+        for (auto t : assocTypes) {
+            if (!t.isNull())
+                typeEncoder.VisitQualType(t);
+        }
+        return true;
+    }
+
+    bool VisitTypeTraitExpr(TypeTraitExpr *E) {
+        if (E->getTrait() != BTT_TypeCompatible) {
+            printWarning("Encountered unsupported type trait expression", E);
+            return true;
+        }
+
+        // The two types compared by `__builtin_types_compatible_p`, followed
+        // by the result of the comparison
+        auto t1 = E->getArg(0)->getType();
+        auto t2 = E->getArg(1)->getType();
+        std::vector<void *> childIds;
+        encode_entry(E, TagTypeTraitExpr, childIds,
+                     [E, t1, t2, this](CborEncoder *extras) {
+                         cbor_encode_uint(extras,
+                                          typeEncoder.encodeQualType(t1));
+                         cbor_encode_uint(extras,
+                                          typeEncoder.encodeQualType(t2));
+                         cbor_encode_boolean(extras, E->getValue());
+                     });
+        typeEncoder.VisitQualType(t1);
+        typeEncoder.VisitQualType(t2);
         return true;
     }
 
@@ -1602,7 +1797,8 @@ class TranslateASTVisitor final
     }
 
     bool VisitAddrLabelExpr(AddrLabelExpr *E) {
-        printWarning("Cannot translate GNU address of label expression", E);
+        std::vector<void *> childIds = {E->getLabel()->getStmt()};
+        encode_entry(E, TagAddrLabelExpr, childIds);
         return true;
     }
 
@@ -1771,10 +1967,6 @@ class TranslateASTVisitor final
         // Use the type from the definition in case the extern was an incomplete
         // type
         auto T = def->getType();
-        if(isa<AtomicType>(T)) {
-            printC11AtomicError(def);
-            abort();
-        }
 
         auto loc = is_defn ? def->getLocation() : VD->getLocation();
 
@@ -1846,12 +2038,6 @@ class TranslateASTVisitor final
         auto recordAlignment = 0;
         auto byteSize = 0;
 
-        auto t = D->getTypeForDecl();
-        if(isa<AtomicType>(t)) {
-            printC11AtomicError(D);
-            abort();
-        }
-
         auto loc = D->getLocation();
         std::vector<void *> childIds;
         if (def) {
@@ -1926,12 +2112,6 @@ class TranslateASTVisitor final
         if (!D->isCompleteDefinition())
             return true;
 
-        auto t = D->getTypeForDecl();
-        if(isa<AtomicType>(t)) {
-            printC11AtomicError(D);
-            abort();
-        }
-
         std::vector<void *> childIds;
         for (auto x : D->enumerators()) {
             childIds.push_back(x->getCanonicalDecl());
@@ -1990,10 +2170,6 @@ class TranslateASTVisitor final
 
         std::vector<void *> childIds;
         auto t = D->getType();
-        if(isa<AtomicType>(t)) {
-            printC11AtomicError(D);
-            abort();
-        }
 
         auto record = D->getParent();
         const ASTRecordLayout &layout =
@@ -2084,7 +2260,8 @@ class TranslateASTVisitor final
 #endif // CLANG_VERSION_MAJOR
 
     bool VisitImaginaryLiteral(ImaginaryLiteral *L) {
-        printWarning("Encountered unsupported imaginary literal", L);
+        std::vector<void *> childIds{L->getSubExpr()};
+        encode_entry(L, TagImaginaryLiteral, childIds);
         return true;
     }
 
@@ -2204,11 +2381,6 @@ class TranslateASTVisitor final
             CharSourceRange::getCharRange(E->getSourceRange()));
     }
 
-    void printC11AtomicError(Decl *D) {
-        std::string msg = "C11 Atomics are not supported. Aborting.";
-        printError(msg, D);
-    }
-
     void printError(std::string Message, Decl *D) {
         auto DiagBuilder =
                 getDiagBuilder(D->getLocation(), DiagnosticsEngine::Error);
@@ -2285,15 +2457,6 @@ void TypeEncoder::VisitVariableArrayType(const VariableArrayType *T) {
 
     VisitQualType(t);
 }
-//
-//void TypeEncoder::VisitAtomicType(const AtomicType *AT) {
-//    std::string msg =
-//            "C11 Atomic types are not supported. Aborting.";
-////    auto horse = AT->get
-////    astEncoder->printError(msg, AT);
-//    AT->getValueType()->dump();
-//    abort();
-//}
 
 class TranslateConsumer : public clang::ASTConsumer {
     Outputs *outputs;
@@ -2319,7 +2482,7 @@ class TranslateConsumer : public clang::ASTConsumer {
             cbor_encoder_init(&encoder, buffer, len, 0);
 
             CborEncoder outer;
-            cbor_encoder_create_array(&encoder, &outer, 5);
+            cbor_encoder_create_array(&encoder, &outer, 6);
 
             CborEncoder array;
 
@@ -2399,6 +2562,12 @@ class TranslateConsumer : public clang::ASTConsumer {
             // 5. Target VaList type as BuiltiVaListKind
             cbor_encode_uint(&outer, static_cast<std::uintptr_t>(Context.getTargetInfo().getBuiltinVaListKind()));
 
+            // 6. Target integer layout as [char is signed, width of long]
+            cbor_encoder_create_array(&outer, &array, 2);
+            cbor_encode_boolean(&array, Context.getTargetInfo().isCharSigned());
+            cbor_encode_uint(&array, Context.getTargetInfo().getLongWidth());
+            cbor_encoder_close_container(&outer, &array);
+
             cbor_encoder_close_container(&encoder, &outer);
         };
 
@@ -2526,6 +2695,11 @@ ExportResult *make_export_result(const Outputs &outputs) {
 // Note: The arguments should only reference one source file at a time.
 Outputs process(int argc, const char *argv[], int *result) {
     static uint64_t source_path_count = 0;
+    // Command line options are global, so only one thread at a time may parse
+    // them. The parsing is cheap; the tool itself runs concurrently where it
+    // can have a working directory of its own.
+    static std::mutex options_mutex;
+    std::unique_lock<std::mutex> options_lock(options_mutex);
     auto argv_ = augment_argv(argc, argv);
     int argc_ = argv_.size() - 1; // ignore the extra nullptr
     CommonOptionsParser OptionsParser(argc_, argv_.data(), MyToolCategory);
@@ -2540,7 +2714,21 @@ Outputs process(int argc, const char *argv[], int *result) {
     std::string sourcePath = OptionsParser.getSourcePathList().back();
     // Make a new list with just the file we're currently translating
     std::vector<std::string> sourcePathList(1, sourcePath);
+#if CLANG_VERSION_MAJOR >= 8
+    // ClangTool changes the working directory of its file system to the
+    // directory of the compile command. The real file system shares the
+    // working directory of the process, so each tool gets a physical file
+    // system with a working directory of its own.
+    IntrusiveRefCntPtr<llvm::vfs::FileSystem> BaseFS(
+        llvm::vfs::createPhysicalFileSystem().release());
+    ClangTool Tool(OptionsParser.getCompilations(), sourcePathList,
+                   std::make_shared<PCHContainerOperations>(), BaseFS);
+    options_lock.unlock();
+#else
+    // Older versions of ClangTool can only change the working directory of
+    // the process, so tools run one at a time.
     ClangTool Tool(OptionsParser.getCompilations(), sourcePathList);
+#endif // CLANG_VERSION_MAJOR
 
     Outputs outputs;
     MyFrontendActionFactory myFrontendActionFactory(&outputs);
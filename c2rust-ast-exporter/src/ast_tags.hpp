@@ -46,6 +46,7 @@ enum ASTEntryTag {
 
     TagAsmStmt,
     TagAttributedStmt,
+    TagIndirectGotoStmt,
 
     TagBinaryOperator = 200,
     TagUnaryOperator,
@@ -83,10 +84,16 @@ enum ASTEntryTag {
 
     TagAtomicExpr,
 
+    TagAddrLabelExpr,
+
+    TagGenericSelectionExpr,
+    TagTypeTraitExpr,
+
     TagIntegerLiteral = 300,
     TagStringLiteral,
     TagCharacterLiteral,
     TagFloatingLiteral,
+    TagImaginaryLiteral,
 };
 
 enum TypeTag {
@@ -139,6 +146,7 @@ enum TypeTag {
     TagBlockPointer,
     TagComplexType,
     TagHalf,
+    TagAtomicType,
 };
 
 enum StringTypeTag {
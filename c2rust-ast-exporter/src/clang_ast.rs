@@ -118,6 +118,8 @@ pub struct AstContext {
     pub comments: Vec<CommentNode>,
     pub files: Vec<SrcFile>,
     pub va_list_kind: BuiltinVaListKind,
+    pub char_is_signed: bool,
+    pub long_width: u64,
 }
 
 pub fn expect_opt_str(val: &Value) -> Option<Option<&str>> {
@@ -159,12 +161,13 @@ pub fn process(items: Value) -> error::Result<AstContext> {
     let mut types: HashMap<u64, TypeNode> = HashMap::new();
     let mut comments: Vec<CommentNode> = vec![];
 
-    let (all_nodes, top_nodes, files, raw_comments, va_list_kind): (
+    let (all_nodes, top_nodes, files, raw_comments, va_list_kind, (char_is_signed, long_width)): (
         Vec<VecDeque<Value>>,
         Vec<u64>,
         Vec<(String, Option<(u64, u64, u64)>)>,
         Vec<(u64, u64, u64, ByteBuf)>,
         u64,
+        (bool, u64),
     ) = from_value(items)?;
 
     let va_list_kind = import_va_list_kind(va_list_kind);
@@ -254,5 +257,7 @@ pub fn process(items: Value) -> error::Result<AstContext> {
         comments,
         files,
         va_list_kind,
+        char_is_signed,
+        long_width,
     })
 }
@@ -29,8 +29,19 @@ pub fn get_untyped_ast(
     extra_args: &[&str],
     debug: bool,
 ) -> Result<clang_ast::AstContext, Error> {
-    let cbors = get_ast_cbors(file_path, cc_db, extra_args, debug);
-    let buffer = cbors.values().next().ok_or(Error::new(
+    let buffer = get_ast_cbor(file_path, cc_db, extra_args, debug)?;
+    parse_ast_cbor(&buffer)
+}
+
+/// Run clang on `file_path` and get the serialized AST, without decoding it
+pub fn get_ast_cbor(
+    file_path: &Path,
+    cc_db: &Path,
+    extra_args: &[&str],
+    debug: bool,
+) -> Result<Vec<u8>, Error> {
+    let mut cbors = get_ast_cbors(file_path, cc_db, extra_args, debug);
+    let key = cbors.keys().next().cloned().ok_or(Error::new(
         ErrorKind::InvalidData,
         "Could not parse input file",
     ))?;
@@ -40,7 +51,12 @@ pub fn get_untyped_ast(
     // cbor_file.write_all(&buffer[..])?;
     // eprintln!("Dumped CBOR to {}", cbor_path.to_string_lossy());
 
-    let items: Value = from_slice(&buffer[..]).unwrap();
+    Ok(cbors.remove(&key).unwrap())
+}
+
+/// Decode an AST serialized by `get_ast_cbor`
+pub fn parse_ast_cbor(buffer: &[u8]) -> Result<clang_ast::AstContext, Error> {
+    let items: Value = from_slice(buffer).unwrap();
 
     match clang_ast::process(items) {
         Ok(cxt) => Ok(cxt),
@@ -0,0 +1,5 @@
+fn main() {
+    cc::Build::new()
+        .file("src/long_double.c")
+        .compile("c2rust_long_double");
+}
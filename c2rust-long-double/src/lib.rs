@@ -0,0 +1,56 @@
+//! Runtime support for passing C2Rust's translation of `long double` through variadic arguments.
+//!
+//! C2Rust translates `long double` to the quadruple precision `f128` type of the `f128` crate,
+//! but on x86_64 a C `long double` is an x87 extended precision value, which the SysV ABI passes
+//! to variadic functions on the stack. Variadic `long double` arguments are translated into a
+//! `VaLongDouble`, which is passed the same way, and `va_arg(ap, long double)` into `va_arg(ap)`.
+//! Only x86_64 is supported.
+
+#![feature(c_variadic)]
+
+#[cfg(not(target_arch = "x86_64"))]
+compile_error!("c2rust-long-double only supports x86_64");
+
+use ::f128::f128;
+use std::ffi::VaList;
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+struct Unaligned {
+    first: u8,
+    middle: u64,
+    last: [u8; 7],
+}
+
+/// An x87 extended precision `long double`, to pass as a variadic argument
+///
+/// Rust has no type that the ABI classifies like `long double`, but aggregates with misaligned
+/// fields are also passed on the stack, so this type has one, along with the size and
+/// alignment of a `long double`. A plain 16-byte struct would be passed in registers.
+#[repr(C, align(16))]
+#[derive(Copy, Clone)]
+pub struct VaLongDouble(Unaligned);
+
+extern "C" {
+    fn c2rust_f128_to_long_double(x: *const f128, out: *mut VaLongDouble);
+    fn c2rust_va_arg_long_double(ap: VaList, out: *mut f128);
+}
+
+impl From<f128> for VaLongDouble {
+    fn from(x: f128) -> Self {
+        let mut out = VaLongDouble(Unaligned {
+            first: 0,
+            middle: 0,
+            last: [0; 7],
+        });
+        unsafe { c2rust_f128_to_long_double(&x, &mut out) };
+        out
+    }
+}
+
+/// Read the next variadic argument as a `long double`, like `va_arg(ap, long double)`
+pub unsafe fn va_arg(ap: VaList) -> f128 {
+    let mut out = f128::ZERO;
+    c2rust_va_arg_long_double(ap, &mut out);
+    out
+}
@@ -0,0 +1,22 @@
+#include <stdarg.h>
+#include <string.h>
+
+/* `f128` values are only byte-aligned, so they are copied in and out through `memcpy` */
+
+/* Convert a quadruple precision value to an x87 extended precision `long double`, stored in the
+ * 16 bytes at `out` */
+void c2rust_f128_to_long_double(const void *x, void *out) {
+    __float128 q;
+    long double ld;
+
+    memcpy(&q, x, sizeof(q));
+    ld = (long double)q;
+    memcpy(out, &ld, sizeof(ld));
+}
+
+/* `va_arg(ap, long double)`, converted to quadruple precision */
+void c2rust_va_arg_long_double(va_list ap, void *out) {
+    __float128 q = (__float128)va_arg(ap, long double);
+
+    memcpy(out, &q, sizeof(q));
+}
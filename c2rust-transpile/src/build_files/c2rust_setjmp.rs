@@ -0,0 +1,44 @@
+//! Runtime support for C2Rust's translation of `setjmp` and `longjmp`, emitted into translated
+//! crates as the `c2rust_setjmp` module.
+//!
+//! C2Rust translates `if (setjmp(env) == 0) { A } else { B }` into
+//! `if catch_longjmp(env, || { A }) != 0 { B }`. A `longjmp(env, val)` does not unwind: it records
+//! a pending jump and returns, and the translated code returns early after every call which may
+//! have started a jump, until control is back in the `catch_longjmp` for `env`. Every frame
+//! between the `setjmp` and the `longjmp` therefore has to be translated code.
+
+use std::cell::Cell;
+use std::os::raw::c_int;
+
+thread_local! {
+    /// The jump in progress, if any, as the address of its `jmp_buf` and the value `setjmp`
+    /// returns when the jump completes
+    static PENDING: Cell<Option<(usize, c_int)>> = Cell::new(None);
+}
+
+/// Start a jump to the innermost active `catch_longjmp` for `env`, which then returns `val`. The
+/// caller has to return immediately, as do its callers until the jump completes. As in C, a `val`
+/// of 0 is replaced by 1.
+pub fn longjmp<T>(env: *mut T, val: c_int) {
+    let val = if val == 0 { 1 } else { val };
+    PENDING.with(|pending| pending.set(Some((env as usize, val))));
+}
+
+/// Is a jump in progress? If so, the caller has to return immediately.
+pub fn jumping() -> bool {
+    PENDING.with(|pending| pending.get().is_some())
+}
+
+/// Run `body`, the code executed after `setjmp(env)` returns 0. Returns 0 if `body` completes
+/// normally, or the value passed to a `longjmp` on the same `env` from within `body`. A jump to
+/// any other `jmp_buf` stays in progress, so the caller has to return after checking `jumping`.
+pub fn catch_longjmp<T, F: FnOnce()>(env: *mut T, body: F) -> c_int {
+    body();
+    PENDING.with(|pending| match pending.get() {
+        Some((jmp_env, val)) if jmp_env == env as usize => {
+            pending.set(None);
+            val
+        }
+        _ => 0,
+    })
+}
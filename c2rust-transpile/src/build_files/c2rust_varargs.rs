@@ -0,0 +1,119 @@
+//! Runtime support for C2Rust's translation of variadic functions on stable Rust, emitted into
+//! translated crates as the `c2rust_varargs` module.
+//!
+//! Defining a C-variadic function in Rust requires the unstable `c_variadic` feature. With
+//! `--stable-variadics`, C2Rust instead translates a `static` variadic function which is only
+//! ever called directly into a function taking its variadic arguments as a `&[VarArg]` slice,
+//! and each call site builds that slice: `f(fmt, &[VarArg::from(x), VarArg::from(p)])`. The
+//! function reads its arguments through a `VaList`, just as it would through
+//! `std::ffi::VaListImpl`.
+
+use core::ffi::c_void;
+
+/// A variadic argument, after the C default argument promotions
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VarArg {
+    Int(i64),
+    UInt(u64),
+    Double(f64),
+    Ptr(*mut c_void),
+}
+
+macro_rules! impl_int_var_arg {
+    ($variant:ident($repr:ty): $($ty:ty),*) => {$(
+        impl From<$ty> for VarArg {
+            fn from(x: $ty) -> Self {
+                VarArg::$variant(x as $repr)
+            }
+        }
+
+        impl FromVarArg for $ty {
+            fn from_var_arg(arg: VarArg) -> Option<Self> {
+                // As in C, an argument may be read with the other signedness
+                match arg {
+                    VarArg::Int(x) => Some(x as $ty),
+                    VarArg::UInt(x) => Some(x as $ty),
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+impl_int_var_arg!(Int(i64): i8, i16, i32, i64, isize);
+impl_int_var_arg!(UInt(u64): u8, u16, u32, u64, usize);
+
+impl From<f64> for VarArg {
+    fn from(x: f64) -> Self {
+        VarArg::Double(x)
+    }
+}
+
+impl<T> From<*mut T> for VarArg {
+    fn from(p: *mut T) -> Self {
+        VarArg::Ptr(p as *mut c_void)
+    }
+}
+
+impl<T> From<*const T> for VarArg {
+    fn from(p: *const T) -> Self {
+        VarArg::Ptr(p as *mut c_void)
+    }
+}
+
+/// Types which can be read from a variadic argument with `VaList::arg`
+pub trait FromVarArg: Sized {
+    fn from_var_arg(arg: VarArg) -> Option<Self>;
+}
+
+impl FromVarArg for f64 {
+    fn from_var_arg(arg: VarArg) -> Option<Self> {
+        match arg {
+            VarArg::Double(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl<T> FromVarArg for *mut T {
+    fn from_var_arg(arg: VarArg) -> Option<Self> {
+        match arg {
+            VarArg::Ptr(p) => Some(p as *mut T),
+            _ => None,
+        }
+    }
+}
+
+impl<T> FromVarArg for *const T {
+    fn from_var_arg(arg: VarArg) -> Option<Self> {
+        match arg {
+            VarArg::Ptr(p) => Some(p as *const T),
+            _ => None,
+        }
+    }
+}
+
+/// Cursor over the variadic arguments of a call, the translation of a C `va_list`
+#[derive(Copy, Clone, Debug)]
+pub struct VaList<'a> {
+    args: &'a [VarArg],
+}
+
+impl<'a> VaList<'a> {
+    /// Start reading `args` from the first one, like `va_start`
+    pub fn new(args: &'a [VarArg]) -> Self {
+        VaList { args }
+    }
+
+    /// Read the next argument, like `va_arg`. Unlike in C, reading past the last argument or
+    /// reading an argument as a type of the wrong kind panics.
+    pub fn arg<T: FromVarArg>(&mut self) -> T {
+        let (&arg, rest) = self
+            .args
+            .split_first()
+            .expect("va_arg: no variadic arguments left");
+        self.args = rest;
+        T::from_var_arg(arg)
+            .unwrap_or_else(|| panic!("va_arg: argument {:?} read as the wrong type", arg))
+    }
+}
@@ -184,8 +184,44 @@ fn convert_module_list(
     res
 }
 
+/// Get the path from the crate root to the module `lib.rs` declares for the library file
+/// `module`, e.g., `crate::src::foo` for `build_dir/src/foo.rs`
+pub fn module_path(tcfg: &TranspilerConfig, build_dir: &Path, module: &Path) -> String {
+    let modules = convert_module_list(
+        tcfg,
+        build_dir,
+        vec![module.to_path_buf()],
+        ModuleSubset::Libraries,
+    );
+    let mut path = vec!["crate".to_string()];
+    path.extend(modules.into_iter().filter(|m| !m.close).map(|m| m.name));
+    path.join("::")
+}
+
 fn convert_dependencies_list(crates: CrateSet) -> Vec<ExternCrateDetails> {
-    crates.into_iter().map(|dep| dep.into()).collect()
+    crates
+        .into_iter()
+        .map(ExternCrateDetails::from)
+        .filter(|dep| dep.runtime.is_none())
+        .collect()
+}
+
+/// Emit the runtime support modules the translated code uses next to `lib.rs`, and return
+/// their names
+fn emit_runtime_modules(
+    tcfg: &TranspilerConfig,
+    build_dir: &Path,
+    crates: &CrateSet,
+) -> Vec<String> {
+    let mut modules = vec![];
+    for dep in crates.iter().cloned().map(ExternCrateDetails::from) {
+        if let Some(source) = dep.runtime {
+            let output_path = build_dir.join(format!("{}.rs", dep.ident));
+            maybe_write_to_file(&output_path, source.to_string(), tcfg.overwrite_existing);
+            modules.push(dep.ident);
+        }
+    }
+    modules
 }
 
 fn get_lib_rs_file_name(tcfg: &TranspilerConfig) -> &str {
@@ -229,6 +265,7 @@ fn emit_lib_rs(
         .join(", ");
 
     let modules = convert_module_list(tcfg, build_dir, modules, ModuleSubset::Libraries);
+    let runtime_modules = emit_runtime_modules(tcfg, build_dir, crates);
     let crates = convert_dependencies_list(crates.clone());
     let file_name = get_lib_rs_file_name(tcfg);
     let rs_xcheck_backend = tcfg.cross_check_backend.replace("-", "_");
@@ -240,6 +277,7 @@ fn emit_lib_rs(
         "cross_check_backend": rs_xcheck_backend,
         "plugin_args": plugin_args,
         "modules": modules,
+        "runtime_modules": runtime_modules,
         "pragmas": pragmas,
         "crates": crates,
     });
@@ -285,6 +323,7 @@ fn emit_cargo_toml<'lcmd>(
             "cross_checks": tcfg.cross_checks,
             "cross_check_backend": tcfg.cross_check_backend,
             "dependencies": dependencies,
+            "features": tcfg.cfg_variants.iter().map(|v| &v.feature).collect::<Vec<_>>(),
         });
         json.as_object_mut()
             .unwrap()
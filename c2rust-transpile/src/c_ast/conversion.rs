@@ -156,9 +156,12 @@ fn parse_cast_kind(kind: &str) -> CastKind {
         "IntegralComplexToBoolean" => CastKind::IntegralComplexToBoolean,
         "IntegralComplexCast" => CastKind::IntegralComplexCast,
         "IntegralComplexToFloatingComplex" => CastKind::IntegralComplexToFloatingComplex,
+        "FloatingComplexToBoolean" => CastKind::FloatingComplexToBoolean,
         "BuiltinFnToFnPtr" => CastKind::BuiltinFnToFnPtr,
         "ConstCast" => CastKind::ConstCast,
         "VectorSplat" => CastKind::VectorSplat,
+        "AtomicToNonAtomic" => CastKind::AtomicToNonAtomic,
+        "NonAtomicToAtomic" => CastKind::NonAtomicToAtomic,
         k => panic!("Unsupported implicit cast: {}", k),
     }
 }
@@ -462,6 +465,8 @@ impl ConversionContext {
         }
 
         self.typed_context.va_list_kind = untyped_context.va_list_kind;
+        self.typed_context.char_is_signed = untyped_context.char_is_signed;
+        self.typed_context.long_width = untyped_context.long_width;
     }
 
     /// Visit one node.
@@ -734,6 +739,16 @@ impl ConversionContext {
                     self.processed_nodes.insert(new_id, TYPE);
                 }
 
+                TypeTag::TagAtomicType => {
+                    let value_id = from_value(ty_node.extras[0].clone())
+                        .expect("Atomic value type not found");
+                    let value = self.visit_qualified_type(value_id);
+
+                    let atomic_ty = CTypeKind::Atomic(value);
+                    self.add_type(new_id, not_located(atomic_ty));
+                    self.processed_nodes.insert(new_id, TYPE);
+                }
+
                 TypeTag::TagConstantArrayType => {
                     let element_id = from_value(ty_node.extras[0].clone()).expect("element id");
                     let element = self.visit_type(element_id);
@@ -885,6 +900,16 @@ impl ConversionContext {
                     self.processed_nodes.insert(new_id, OTHER_STMT);
                 }
 
+                ASTEntryTag::TagIndirectGotoStmt if expected_ty & OTHER_STMT != 0 => {
+                    let target_old = node.children[0].expect("Indirect goto target not found");
+                    let target = self.visit_expr(target_old);
+
+                    let goto_stmt = CStmtKind::IndirectGoto(target);
+
+                    self.add_stmt(new_id, located(node, goto_stmt));
+                    self.processed_nodes.insert(new_id, OTHER_STMT);
+                }
+
                 ASTEntryTag::TagNullStmt if expected_ty & OTHER_STMT != 0 => {
                     let null_stmt = CStmtKind::Empty;
 
@@ -971,18 +996,28 @@ impl ConversionContext {
 
                     let is_signed = from_value(node.extras[0].clone())
                         .expect("Case constant is_signed not found");
-                    let cie = match is_signed {
+                    let const_int_expr = |extra: &Value| match is_signed {
                         false => ConstIntExpr::U(
-                            from_value(node.extras[1].clone())
+                            from_value(extra.clone())
                                 .expect("Case constant not found")
                         ),
                         true => ConstIntExpr::I(
-                            from_value(node.extras[1].clone())
+                            from_value(extra.clone())
                                 .expect("Case constant not found")
                         ),
                     };
-
-                    let case_stmt = CStmtKind::Case(expr, substmt, cie);
+                    let cie = const_int_expr(&node.extras[1]);
+
+                    // GNU case range, `case lo ... hi:`
+                    let case_stmt = match node.children.get(2) {
+                        Some(&rhs_old) => {
+                            let rhs_old = rhs_old.expect("Case range end not found");
+                            let rhs = self.visit_expr(rhs_old);
+                            let rhs_cie = const_int_expr(&node.extras[2]);
+                            CStmtKind::CaseRange(expr, rhs, substmt, cie, rhs_cie)
+                        }
+                        None => CStmtKind::Case(expr, substmt, cie),
+                    };
 
                     self.add_stmt(new_id, located(node, case_stmt));
                     self.processed_nodes.insert(new_id, OTHER_STMT);
@@ -1005,6 +1040,9 @@ impl ConversionContext {
                     let raw_inputs = from_value::<Vec<Value>>(node.extras[2].clone()).expect("input constraints array");
                     let raw_outputs = from_value::<Vec<Value>>(node.extras[3].clone()).expect("output constraints array");
                     let raw_clobbers = from_value::<Vec<Value>>(node.extras[4].clone()).expect("clobber array");
+                    let input_names = from_value::<Vec<String>>(node.extras[5].clone()).expect("input names array");
+                    let output_names = from_value::<Vec<String>>(node.extras[6].clone()).expect("output names array");
+                    let operand_name = |name: String| if name.is_empty() { None } else { Some(name) };
 
                     let (input_children, output_children) =
                         node.children.split_at(raw_inputs.len());
@@ -1012,12 +1050,14 @@ impl ConversionContext {
                     let inputs: Vec<AsmOperand> = raw_inputs
                         .into_iter()
                         .zip(input_children)
-                        .map(|(c, e)| {
+                        .zip(input_names)
+                        .map(|((c, e), name)| {
                             let constraints = from_value(c).expect("constraint string");
                             let expression = self.visit_expr(e.expect("expression"));
                             AsmOperand {
                                 constraints,
                                 expression,
+                                name: operand_name(name),
                             }
                         })
                         .collect();
@@ -1025,12 +1065,14 @@ impl ConversionContext {
                     let outputs: Vec<AsmOperand> = raw_outputs
                         .into_iter()
                         .zip(output_children)
-                        .map(|(c, e)| {
+                        .zip(output_names)
+                        .map(|((c, e), name)| {
                             let constraints = from_value(c).expect("constraint string");
                             let expression = self.visit_expr(e.expect("expression"));
                             AsmOperand {
                                 constraints,
                                 expression,
+                                name: operand_name(name),
                             }
                         })
                         .collect();
@@ -1460,6 +1502,21 @@ impl ConversionContext {
                     )
                 }
 
+                ASTEntryTag::TagImaginaryLiteral => {
+                    let ty_old = node.type_id.expect("Expected imaginary literal to have type");
+                    let ty = self.visit_qualified_type(ty_old);
+
+                    let val_old = node.children[0].expect("Expected child on imaginary literal");
+                    let val = self.visit_expr(val_old);
+
+                    self.expr_possibly_as_stmt(
+                        expected_ty,
+                        new_id,
+                        node,
+                        CExprKind::ImaginaryLiteral(ty, val),
+                    )
+                }
+
                 ASTEntryTag::TagImplicitValueInitExpr => {
                     let ty_old = node.type_id.expect("Expected expression to have type");
                     let ty = self.visit_qualified_type(ty_old);
@@ -1640,6 +1697,66 @@ impl ConversionContext {
                     self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
                 }
 
+                ASTEntryTag::TagAddrLabelExpr => {
+                    let label_old = node.children[0].expect("Expected label on address of label");
+                    let label = CStmtId(self.visit_node_type(label_old, LABEL_STMT));
+
+                    let ty_old = node.type_id.expect("Expected expression to have type");
+                    let ty = self.visit_qualified_type(ty_old);
+
+                    self.expr_possibly_as_stmt(
+                        expected_ty,
+                        new_id,
+                        node,
+                        CExprKind::AddrLabel(ty, label),
+                    )
+                }
+
+                ASTEntryTag::TagGenericSelectionExpr => {
+                    let ty = node.type_id.expect("Expected expression to have type");
+                    let ty = self.visit_qualified_type(ty);
+
+                    let controlling =
+                        node.children[0].expect("Expected controlling expression");
+                    let controlling = self.visit_expr(controlling);
+
+                    let selected = from_value::<usize>(node.extras[0].clone())
+                        .expect("Expected index of selected association");
+
+                    let assocs = node.children[1..]
+                        .iter()
+                        .zip(&node.extras[1..])
+                        .map(|(&expr, assoc_ty)| {
+                            let assoc_ty = from_value::<Option<ClangId>>(assoc_ty.clone())
+                                .expect("Expected association type")
+                                .map(|assoc_ty| self.visit_qualified_type(assoc_ty));
+                            let expr = expr.expect("Generic selection association not found");
+                            (assoc_ty, self.visit_expr(expr))
+                        })
+                        .collect();
+
+                    let e = CExprKind::GenericSelection(ty, controlling, assocs, selected);
+
+                    self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
+                }
+
+                ASTEntryTag::TagTypeTraitExpr => {
+                    let ty = node.type_id.expect("Expected expression to have type");
+                    let ty = self.visit_qualified_type(ty);
+
+                    let ty1 = from_value(node.extras[0].clone()).expect("Expected first type");
+                    let ty1 = self.visit_qualified_type(ty1);
+                    let ty2 = from_value(node.extras[1].clone()).expect("Expected second type");
+                    let ty2 = self.visit_qualified_type(ty2);
+
+                    let compatible = from_value(node.extras[2].clone())
+                        .expect("Expected result of type comparison");
+
+                    let e = CExprKind::TypesCompatible(ty, ty1, ty2, compatible);
+
+                    self.expr_possibly_as_stmt(expected_ty, new_id, node, e)
+                }
+
                 ASTEntryTag::TagAtomicExpr => {
                     let name = from_value::<String>(node.extras[0].clone())
                         .expect("Expected to find builtin operator name");
@@ -1981,6 +2098,45 @@ impl ConversionContext {
                     self.typed_context.c_decls_top.push(CDeclId(new_id));
                 }
 
+                ASTEntryTag::TagMacroFunctionDef if expected_ty & MACRO_DECL != 0 => {
+                    let name = from_value::<String>(node.extras[0].clone())
+                        .expect("Macros must have a name");
+
+                    let parameters = from_value::<Vec<String>>(node.extras[1].clone())
+                        .expect("Function-like macros must have parameters");
+
+                    let replacements = node
+                        .children
+                        .iter()
+                        .map(|id| {
+                            let expr_id = id.expect("Macro replacement expr not found");
+                            self.visit_expr(expr_id)
+                        })
+                        .collect();
+
+                    let arguments = from_value::<Vec<Vec<(usize, ClangId)>>>(node.extras[2].clone())
+                        .expect("Function-like macros must have argument lists")
+                        .into_iter()
+                        .map(|args| {
+                            args.into_iter()
+                                .map(|(param, arg)| (param, self.visit_expr(arg)))
+                                .collect()
+                        })
+                        .collect();
+
+                    let mac_function = CDeclKind::MacroFunction {
+                        name,
+                        parameters,
+                        replacements,
+                        arguments,
+                    };
+                    self.add_decl(new_id, located(node, mac_function));
+                    self.processed_nodes.insert(new_id, MACRO_DECL);
+
+                    // See the note on object-like macros above
+                    self.typed_context.c_decls_top.push(CDeclId(new_id));
+                }
+
                 ASTEntryTag::TagNonCanonicalDecl if expected_ty & DECL != 0 => {
                     let canonical_decl = node.children[0]
                         .expect("NonCanonicalDecl must point to a canonical decl");
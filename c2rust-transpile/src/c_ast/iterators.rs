@@ -44,6 +44,7 @@ fn immediate_expr_children(kind: &CExprKind) -> Vec<SomeId> {
         ShuffleVector(..) | ConvertVector(..) => vec![],
         OffsetOf(..) | Literal(..) | ImplicitValueInit(..) => vec![],
         DeclRef(..) => vec![], // don't follow references back!
+        AddrLabel(..) => vec![], // don't follow the reference to the label
         Unary(_ty, _op, subexpr, _) => intos![subexpr],
         UnaryType(_ty, _op, opt_expr_id, _) => opt_expr_id.iter().map(|&x| x.into()).collect(),
         Binary(_ty, _op, lhs, rhs, _, _) => intos![lhs, rhs],
@@ -55,8 +56,11 @@ fn immediate_expr_children(kind: &CExprKind) -> Vec<SomeId> {
             res
         }
         ArraySubscript(_, l, r, _) => intos![l, r],
-        Conditional(_, c, t, e)
-        | Choose(_, c, t, e, _) => intos![c, t, e],
+        Conditional(_, c, t, e) => intos![c, t, e],
+        // Only the selected operand is evaluated
+        Choose(_, _, t, e, is_cond_true) => intos![if is_cond_true { t } else { e }],
+        GenericSelection(_, _, ref assocs, selected) => intos![assocs[selected].1],
+        TypesCompatible(..) => vec![],
         BinaryConditional(_, c, t) => intos![c, t],
         InitList(_, ref xs, _, _) => xs.iter().map(|&x| x.into()).collect(),
         Atomic { ptr, order, val1, order_fail, val2, weak, ..} => {
@@ -72,6 +76,7 @@ fn immediate_expr_children(kind: &CExprKind) -> Vec<SomeId> {
         | Paren(_, e)
         | CompoundLiteral(_, e)
         | Predefined(_, e)
+        | ImaginaryLiteral(_, e)
         | VAArg(_, e) => intos![e],
         Statements(_, s) => vec![s.into()],
     }
@@ -90,6 +95,7 @@ fn immediate_expr_children_all_types(kind: &CExprKind) -> Vec<SomeId> {
         OffsetOf(_, OffsetOfKind::Variable(qty, _, _)) => intos![qty.ctype],
         OffsetOf(..) | Literal(..) | ImplicitValueInit(..) => vec![],
         DeclRef(..) => vec![], // don't follow references back!
+        AddrLabel(..) => vec![], // don't follow the reference to the label
         Unary(_ty, _op, subexpr, _) => intos![subexpr],
         UnaryType(_ty, _op, opt_expr_id, qty) => {
             let mut res = intos![qty.ctype];
@@ -109,6 +115,17 @@ fn immediate_expr_children_all_types(kind: &CExprKind) -> Vec<SomeId> {
         ArraySubscript(_, l, r, _) => intos![l, r],
         Conditional(_, c, t, e)
         | Choose(_, c, t, e, _) => intos![c, t, e],
+        GenericSelection(_, c, ref assocs, _) => {
+            let mut res = intos![c];
+            for &(qty, e) in assocs {
+                if let Some(qty) = qty {
+                    res.push(qty.ctype.into());
+                }
+                res.push(e.into());
+            }
+            res
+        }
+        TypesCompatible(_, qty1, qty2, _) => intos![qty1.ctype, qty2.ctype],
         BinaryConditional(_, c, t) => intos![c, t],
         InitList(_, ref xs, _, _) => xs.iter().map(|&x| x.into()).collect(),
         Atomic { ptr, order, val1, order_fail, val2, weak, ..} => {
@@ -118,7 +135,7 @@ fn immediate_expr_children_all_types(kind: &CExprKind) -> Vec<SomeId> {
                 .map(|&x| x.into())
                 .collect()
         }
-        Member(_, e, _, _, _) | Predefined(_, e) => intos![e],
+        Member(_, e, _, _, _) | Predefined(_, e) | ImaginaryLiteral(_, e) => intos![e],
         // Normally we don't step into the result type annotation field, because it's not really
         // part of the expression.  But for `ExplicitCast`, the result type is actually the cast's
         // target type as written by the user.  The other expr kinds here work similarly.
@@ -175,6 +192,9 @@ fn immediate_decl_children(kind: &CDeclKind) -> Vec<SomeId> {
         MacroObject {
             ref replacements, ..
         } => replacements.iter().map(|&x| x.into()).collect(),
+        MacroFunction {
+            ref replacements, ..
+        } => replacements.iter().map(|&x| x.into()).collect(),
         NonCanonicalDecl { canonical_decl } => intos![canonical_decl],
     }
 }
@@ -185,6 +205,7 @@ fn immediate_stmt_children(kind: &CStmtKind) -> Vec<SomeId> {
         Expr(e) => intos![e],
         Label(s) => intos![s],
         Case(e, s, _) => intos![e, s],
+        CaseRange(lo, hi, s, _, _) => intos![lo, hi, s],
         Default(s) => intos![s],
 
         // Compound statements (6.8.2)
@@ -234,6 +255,7 @@ fn immediate_stmt_children(kind: &CStmtKind) -> Vec<SomeId> {
             res
         }
         Goto(_) => vec![], // Don't follow the reference to the label
+        IndirectGoto(e) => intos![e],
         Break => vec![],
         Continue => vec![],
         Return(ref opt_e) => opt_e.iter().map(|&x| x.into()).collect(),
@@ -266,7 +288,8 @@ fn immediate_type_children(kind: &CTypeKind) -> Vec<SomeId> {
             vec![]
         }
 
-        Pointer(qtype) | Reference(qtype) | Attributed(qtype, _) | BlockPointer(qtype) | Vector(qtype, _) => {
+        Pointer(qtype) | Reference(qtype) | Attributed(qtype, _) | BlockPointer(qtype) | Vector(qtype, _)
+        | Atomic(qtype) => {
             intos![qtype.ctype]
         }
 
@@ -76,6 +76,10 @@ pub struct TypedAstContext {
     pub prenamed_decls: IndexMap<CDeclId, CDeclId>,
 
     pub va_list_kind: BuiltinVaListKind,
+
+    // Whether plain `char` is signed on the target, and the width of `long` in bits
+    pub char_is_signed: bool,
+    pub long_width: u64,
 }
 
 /// Comments associated with a typed AST context
@@ -166,6 +170,8 @@ impl TypedAstContext {
             comments: vec![],
             prenamed_decls: IndexMap::new(),
             va_list_kind: BuiltinVaListKind::CharPtrBuiltinVaList,
+            char_is_signed: true,
+            long_width: 64,
         }
     }
 
@@ -346,6 +352,17 @@ impl TypedAstContext {
         }
     }
 
+    /// Is the translation targeting x86 or x86-64? The only target information exported by the
+    /// AST exporter is the kind of `va_list`. i386 uses a `char *` `va_list`, which a few other
+    /// targets share.
+    pub fn targets_x86(&self) -> bool {
+        match self.va_list_kind {
+            BuiltinVaListKind::CharPtrBuiltinVaList
+            | BuiltinVaListKind::X86_64ABIBuiltinVaList => true,
+            _ => false,
+        }
+    }
+
     /// Predicate for function pointers
     pub fn is_function_pointer(&self, typ: CTypeId) -> bool {
         let resolved_ctype = self.resolve_type(typ);
@@ -360,6 +377,29 @@ impl TypedAstContext {
         }
     }
 
+    /// Does a value of this type contain an `_Atomic` object, either directly or through array
+    /// elements and struct/union fields? Such values cannot be `Copy` in Rust.
+    pub fn contains_atomic(&self, typ: CTypeId) -> bool {
+        match self.resolve_type(typ).kind {
+            CTypeKind::Atomic(_) => true,
+            CTypeKind::ConstantArray(elt, _)
+            | CTypeKind::IncompleteArray(elt) => self.contains_atomic(elt),
+            CTypeKind::Struct(decl_id) | CTypeKind::Union(decl_id) => {
+                match self.index(decl_id).kind {
+                    CDeclKind::Struct { fields: Some(ref fields), .. }
+                    | CDeclKind::Union { fields: Some(ref fields), .. } => {
+                        fields.iter().any(|&field_id| match self.index(field_id).kind {
+                            CDeclKind::Field { typ, .. } => self.contains_atomic(typ.ctype),
+                            _ => false,
+                        })
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
     /// Can the given field decl be a flexible array member?
     pub fn maybe_flexible_array(&self, typ: CTypeId) -> bool {
         let field_ty = self.resolve_type(typ);
@@ -466,6 +506,8 @@ impl TypedAstContext {
             CExprKind::Literal(_, _) |
             CExprKind::DeclRef(_, _, _) |
             CExprKind::UnaryType(_, _, _, _) |
+            CExprKind::AddrLabel(..) |
+            CExprKind::TypesCompatible(..) |
             CExprKind::OffsetOf(..) => true,
 
             CExprKind::DesignatedInitExpr(_,_,e) |
@@ -474,6 +516,7 @@ impl TypedAstContext {
             CExprKind::Member(_, e, _, _, _) |
             CExprKind::Paren(_, e) |
             CExprKind::CompoundLiteral(_, e) |
+            CExprKind::ImaginaryLiteral(_, e) |
             CExprKind::Unary(_, _, e, _) => self.is_expr_pure(e),
 
             CExprKind::Binary(_, op, _, _, _, _) if op.underlying_assignment().is_some() => false,
@@ -482,7 +525,8 @@ impl TypedAstContext {
             CExprKind::ArraySubscript(_, lhs, rhs, _) => self.is_expr_pure(lhs) && self.is_expr_pure(rhs),
             CExprKind::Conditional(_, c, lhs, rhs) => self.is_expr_pure(c) && self.is_expr_pure(lhs) && self.is_expr_pure(rhs),
             CExprKind::BinaryConditional(_, c, rhs) => self.is_expr_pure(c) && self.is_expr_pure(rhs),
-            CExprKind::Choose(_, c, lhs, rhs, _) => self.is_expr_pure(c) && self.is_expr_pure(lhs) && self.is_expr_pure(rhs),
+            CExprKind::Choose(_, _, lhs, rhs, is_cond_true) => self.is_expr_pure(if is_cond_true { lhs } else { rhs }),
+            CExprKind::GenericSelection(_, _, ref assocs, selected) => self.is_expr_pure(assocs[selected].1),
         }
     }
 
@@ -664,6 +708,38 @@ impl TypedAstContext {
         }
     }
 
+    /// Check whether an lvalue expression refers to (part of) a field of a packed struct. Rust
+    /// does not allow taking references to such places since they may be misaligned.
+    pub fn is_packed_place(&self, expr: CExprId) -> bool {
+        match self.index(expr).kind {
+            CExprKind::Paren(_, e) => self.is_packed_place(e),
+            CExprKind::Member(_, base, field, kind, _) => {
+                let in_packed = self
+                    .parents
+                    .get(&field)
+                    .map_or(false, |&record| self.is_packed_struct_decl(record));
+                // Through `->` the base is a separate object, which we can borrow normally
+                in_packed
+                    || match kind {
+                        MemberKind::Dot => self.is_packed_place(base),
+                        MemberKind::Arrow => false,
+                    }
+            }
+            CExprKind::ArraySubscript(_, lhs, rhs, _) => {
+                let is_array_decay = |e: CExprId| match self.index(e).kind {
+                    CExprKind::ImplicitCast(_, arr, CastKind::ArrayToPointerDecay, _, _) => {
+                        Some(arr)
+                    }
+                    _ => None,
+                };
+                is_array_decay(lhs)
+                    .or_else(|| is_array_decay(rhs))
+                    .map_or(false, |arr| self.is_packed_place(arr))
+            }
+            _ => false,
+        }
+    }
+
     pub fn is_aligned_struct_type(&self, typ: CTypeId) -> bool {
         if let Some(decl_id) = self
             .resolve_type(typ)
@@ -926,6 +1002,15 @@ pub enum CDeclKind {
         replacements: Vec<CExprId>,
     },
 
+    MacroFunction {
+        name: String,
+        parameters: Vec<String>,
+        replacements: Vec<CExprId>,
+        // For each replacement, the argument subexpressions paired with the
+        // index of the parameter they were substituted for
+        arguments: Vec<Vec<(usize, CExprId)>>,
+    },
+
     NonCanonicalDecl {
         canonical_decl: CDeclId,
     }
@@ -949,6 +1034,7 @@ impl CDeclKind {
             } => Some(i),
             &CDeclKind::Field { name: ref i, .. } => Some(i),
             &CDeclKind::MacroObject { ref name, .. } => Some(name),
+            &CDeclKind::MacroFunction { ref name, .. } => Some(name),
             _ => None,
         }
     }
@@ -1037,6 +1123,9 @@ pub enum CExprKind {
     // Predefined expr
     Predefined(CQualTypeId, CExprId),
 
+    // Imaginary literal (`2.0i`) - type, real-valued literal
+    ImaginaryLiteral(CQualTypeId, CExprId),
+
     // Statement expression
     Statements(CQualTypeId, CStmtId),
 
@@ -1053,6 +1142,16 @@ pub enum CExprKind {
     // GNU choose expr. Condition, true expr, false expr, was condition true?
     Choose(CQualTypeId, CExprId, CExprId, CExprId, bool),
 
+    // GNU address of label (`&&label`)
+    AddrLabel(CQualTypeId, CLabelId),
+
+    // C11 generic selection. Controlling expr, associations with their type (`None` for
+    // `default`), index of the selected association
+    GenericSelection(CQualTypeId, CExprId, Vec<(Option<CQualTypeId>, CExprId)>, usize),
+
+    // GNU `__builtin_types_compatible_p`. Compared types, are they compatible?
+    TypesCompatible(CQualTypeId, CQualTypeId, CQualTypeId, bool),
+
     // GNU/C11 atomic expr
     Atomic {
         typ: CQualTypeId,
@@ -1108,12 +1207,16 @@ impl CExprKind {
             | CExprKind::Paren(ty, _)
             | CExprKind::CompoundLiteral(ty, _)
             | CExprKind::Predefined(ty, _)
+            | CExprKind::ImaginaryLiteral(ty, _)
             | CExprKind::Statements(ty, _)
             | CExprKind::VAArg(ty, _)
             | CExprKind::ShuffleVector(ty, _)
             | CExprKind::ConvertVector(ty, _)
             | CExprKind::DesignatedInitExpr(ty, _, _) => Some(ty),
             | CExprKind::Choose(ty, _, _, _, _)
+            | CExprKind::AddrLabel(ty, _)
+            | CExprKind::GenericSelection(ty, _, _, _)
+            | CExprKind::TypesCompatible(ty, _, _, _)
             | CExprKind::Atomic{typ: ty, ..} => Some(ty),
         }
     }
@@ -1161,9 +1264,12 @@ pub enum CastKind {
     IntegralComplexToBoolean,
     IntegralComplexCast,
     IntegralComplexToFloatingComplex,
+    FloatingComplexToBoolean,
     BuiltinFnToFnPtr,
     ConstCast,
     VectorSplat,
+    AtomicToNonAtomic,
+    NonAtomicToAtomic,
 }
 
 /// Represents a unary operator in C (6.5.3 Unary operators) and GNU C extensions
@@ -1325,6 +1431,8 @@ pub enum CStmtKind {
     // All of these have a `CStmtId` to represent the substatement that comes after them
     Label(CStmtId),
     Case(CExprId, CStmtId, ConstIntExpr),
+    /// GNU case range, `case lo ... hi:`
+    CaseRange(CExprId, CExprId, CStmtId, ConstIntExpr, ConstIntExpr),
     Default(CStmtId),
 
     // Compound statements (6.8.2)
@@ -1363,6 +1471,8 @@ pub enum CStmtKind {
 
     // Jump statements (6.8.6)
     Goto(CLabelId),
+    // GNU computed goto (`goto *ptr`)
+    IndirectGoto(CExprId),
     Break,
     Continue,
     Return(Option<CExprId>),
@@ -1384,6 +1494,8 @@ pub enum CStmtKind {
 pub struct AsmOperand {
     pub constraints: String,
     pub expression: CExprId,
+    /// The symbolic name of the operand, as in `[name] "r" (x)`
+    pub name: Option<String>,
 }
 
 /// Type qualifiers (6.7.3)
@@ -1537,6 +1649,9 @@ pub enum CTypeKind {
     Vector(CQualTypeId, usize),
 
     Half,
+
+    // Atomic type (6.7.2.4), wrapping the underlying value type
+    Atomic(CQualTypeId),
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1657,6 +1772,20 @@ impl CTypeKind {
         }
     }
 
+    pub fn is_atomic(&self) -> bool {
+        match *self {
+            CTypeKind::Atomic(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_complex(&self) -> bool {
+        match *self {
+            CTypeKind::Complex(_) => true,
+            _ => false,
+        }
+    }
+
     /// Choose the smaller, simpler of the two types if they are cast-compatible.
     pub fn smaller_compatible_type(ty1: CTypeKind, ty2: CTypeKind) -> Option<CTypeKind> {
         match (&ty1, &ty2) {
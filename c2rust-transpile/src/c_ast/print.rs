@@ -213,6 +213,10 @@ impl<W: Write> Printer<W> {
                 self.print_expr(val, context)
             }
             Some(&CExprKind::Predefined(_, val)) => self.print_expr(val, context),
+            Some(&CExprKind::ImaginaryLiteral(_, val)) => {
+                self.print_expr(val, context)?;
+                self.writer.write_all(b"i")
+            }
 
             Some(&CExprKind::VAArg(_, val)) => self.print_expr(val, context),
 
@@ -226,6 +230,33 @@ impl<W: Write> Printer<W> {
                 self.writer.write_all(b")")
             }
 
+            Some(&CExprKind::GenericSelection(_, ctrl, ref assocs, _)) => {
+                self.writer.write_all(b"_Generic(")?;
+                self.print_expr(ctrl, context)?;
+                for &(qty, expr) in assocs {
+                    self.writer.write_all(b", ")?;
+                    match qty {
+                        Some(qty) => self.print_qtype(qty, None, context)?,
+                        None => self.writer.write_all(b"default")?,
+                    }
+                    self.writer.write_all(b": ")?;
+                    self.print_expr(expr, context)?;
+                }
+                self.writer.write_all(b")")
+            }
+
+            Some(&CExprKind::TypesCompatible(_, qty1, qty2, _)) => {
+                self.writer.write_all(b"__builtin_types_compatible_p(")?;
+                self.print_qtype(qty1, None, context)?;
+                self.writer.write_all(b", ")?;
+                self.print_qtype(qty2, None, context)?;
+                self.writer.write_all(b")")
+            }
+
+            Some(&CExprKind::AddrLabel(_, CStmtId(label_id))) => {
+                self.writer.write_fmt(format_args!("&&label_{}", label_id))
+            }
+
             Some(&CExprKind::Atomic{ref name, ptr, order, val1, order_fail, val2, weak, ..}) => {
                 self.writer.write_fmt(format_args!("{}(", name))?;
 
@@ -728,6 +759,20 @@ impl<W: Write> Printer<W> {
                 Ok(())
             }
 
+            Some(&CDeclKind::MacroFunction {
+                ref name,
+                ref parameters,
+                ref replacements,
+                ..
+            }) => {
+                self.writer.write_fmt(format_args!("#define {}({}) ", name, parameters.join(", ")))?;
+                for replacement in replacements {
+                    self.print_expr(*replacement, context)?;
+                }
+
+                Ok(())
+            }
+
             Some(&CDeclKind::NonCanonicalDecl {
                 ref canonical_decl,
             }) => {
@@ -0,0 +1,161 @@
+//! Cache of translated files for `--cache-dir`, so that re-running the transpiler only
+//! re-translates the translation units whose input changed.
+//!
+//! Each entry is keyed on a hash of everything a translation depends on: the transpiler
+//! version, the translation options, the compile command, and the AST clang exported for
+//! the unit, which covers every header it includes and every macro defined when it was
+//! preprocessed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json;
+
+use crate::compile_cmds::CompileCmd;
+use crate::dedup::HeaderType;
+use crate::report::DeclReport;
+use crate::{CrateSet, ExternCrate, PragmaVec, TranspilerConfig};
+
+/// A translation along with the pragmas and crates it requires, its report, and the header
+/// types set aside for `--dedup-types`
+pub type Translation = (
+    String,
+    PragmaVec,
+    CrateSet,
+    Vec<DeclReport>,
+    Vec<HeaderType>,
+);
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    translation: String,
+    pragmas: Vec<(String, Vec<String>)>,
+    crates: Vec<ExternCrate>,
+    decls: Vec<DeclReport>,
+    header_types: Vec<HeaderType>,
+}
+
+pub struct TranslationCache {
+    dir: PathBuf,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl TranslationCache {
+    pub fn new(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(TranslationCache {
+            dir: dir.to_path_buf(),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    /// Compute the key of the translation of `cmd`, whose AST was exported by clang as
+    /// `cbors`, one per preprocessor configuration.
+    pub fn key(
+        &self,
+        tcfg: &TranspilerConfig,
+        cmd: &CompileCmd,
+        extra_clang_args: &[&str],
+        cbors: &[Vec<u8>],
+    ) -> String {
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        tcfg.translation_options().hash(&mut hasher);
+        format!("{:?}", cmd).hash(&mut hasher);
+        extra_clang_args.hash(&mut hasher);
+        cbors.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(key).with_extension("json")
+    }
+
+    /// Look up a translation, counting the lookup as a hit or a miss
+    pub fn get(&self, key: &str) -> Option<Translation> {
+        let entry = File::open(self.entry_path(key))
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, CacheEntry>(file).ok());
+        let entry = match entry {
+            Some(entry) => entry,
+            None => {
+                self.misses.fetch_add(1, Ordering::SeqCst);
+                return None;
+            }
+        };
+        self.hits.fetch_add(1, Ordering::SeqCst);
+
+        let pragmas = entry
+            .pragmas
+            .into_iter()
+            .map(|(key, vals)| (intern(key), vals.into_iter().map(intern).collect()))
+            .collect();
+        let crates = entry.crates.into_iter().collect();
+        Some((
+            entry.translation,
+            pragmas,
+            crates,
+            entry.decls,
+            entry.header_types,
+        ))
+    }
+
+    pub fn insert(&self, key: &str, translation: &Translation) {
+        let (ref translated_string, ref pragmas, ref crates, ref decls, ref header_types) =
+            *translation;
+        let entry = CacheEntry {
+            translation: translated_string.clone(),
+            pragmas: pragmas
+                .iter()
+                .map(|&(key, ref vals)| {
+                    (
+                        key.to_string(),
+                        vals.iter().map(|val| val.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            crates: crates.iter().cloned().collect(),
+            decls: decls.clone(),
+            header_types: header_types.clone(),
+        };
+
+        // Write to a temporary file first so that an interrupted run can't leave a
+        // truncated entry behind
+        let path = self.entry_path(key);
+        let tmp_path = path.with_extension("json.tmp");
+        let res = File::create(&tmp_path)
+            .and_then(|file| serde_json::to_writer(file, &entry).map_err(io::Error::from))
+            .and_then(|()| fs::rename(&tmp_path, &path));
+        if let Err(e) = res {
+            warn!(
+                "Unable to write translation cache entry {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// Print how many translation units were found in the cache
+    pub fn print_stats(&self) {
+        let hits = self.hits.load(Ordering::SeqCst);
+        let misses = self.misses.load(Ordering::SeqCst);
+        println!(
+            "Translation cache: {} reused, {} translated ({})",
+            hits,
+            misses,
+            self.dir.display()
+        );
+    }
+}
+
+/// Pragma names are `&'static str`s, so the ones read back from the cache are leaked. That is
+/// a few bytes per cached translation unit.
+fn intern(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
@@ -0,0 +1,269 @@
+//! This module recognizes C `for` loops that count an induction variable up to a loop-invariant
+//! bound, so that they can be translated into a Rust `for` loop over a range instead of going
+//! through the relooper.
+use super::*;
+use syntax::ast::Ident;
+use syntax::visit::Visitor;
+
+/// A C loop of the form `for (T i = start; i < end; i++) body`. The loop also uses `i <= end`,
+/// `++i`, or `i += 1`.
+#[derive(Copy, Clone, Debug)]
+pub struct ForRange {
+    /// The induction variable
+    pub var: CDeclId,
+    pub start: CExprId,
+    pub end: CExprId,
+    /// Does the loop run for `i == end`?
+    pub inclusive: bool,
+}
+
+impl ForRange {
+    /// Recognize a `for` loop that can be translated into a Rust `for` loop over a range. This
+    /// requires:
+    ///
+    ///   * the induction variable is declared by the loop, so it is dead after the loop
+    ///   * the loop body does not modify the induction variable or the bound
+    ///   * the bound does not have side effects, so evaluating it once is enough
+    ///   * control only leaves the body through `break`, `continue`, and `return`
+    ///
+    /// `addr_taken` are the variables of the function whose address is taken, which may be
+    /// modified by the body through a pointer.
+    pub fn recognize(
+        ast_context: &TypedAstContext,
+        addr_taken: &IndexSet<CDeclId>,
+        stmt_id: CStmtId,
+    ) -> Option<ForRange> {
+        let (init, condition, increment, body) = match ast_context[stmt_id].kind {
+            CStmtKind::ForLoop {
+                init: Some(init),
+                condition: Some(condition),
+                increment: Some(increment),
+                body,
+            } => (init, condition, increment, body),
+            _ => return None,
+        };
+
+        // Init: `T i = start`
+        let (var, start, var_ty) = match ast_context[init].kind {
+            CStmtKind::Decls(ref decls) if decls.len() == 1 => {
+                match ast_context.index(decls[0]).kind {
+                    CDeclKind::Variable {
+                        has_static_duration: false,
+                        has_thread_duration: false,
+                        initializer: Some(start),
+                        typ,
+                        ..
+                    } => (decls[0], start, typ),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        let ty = &ast_context.resolve_type(var_ty.ctype).kind;
+        if var_ty.qualifiers.is_volatile
+            || !ty.is_integral_type()
+            || ty.is_bool()
+            || ty.is_enum()
+            || addr_taken.contains(&var)
+        {
+            return None;
+        }
+
+        // Condition: `i < end` or `i <= end`. The variable must be compared without conversion,
+        // so that the bound has the type of the variable. `i <= end` is only recognized for
+        // signed variables, since the C loop never ends if `end` is the maximum value of an
+        // unsigned type.
+        let (end, inclusive) = match ast_context[condition].kind {
+            CExprKind::Binary(_, op, lhs, rhs, _, _) if is_var_value(ast_context, lhs, var) => {
+                match op {
+                    BinOp::Less => (rhs, false),
+                    BinOp::LessEqual if ty.is_signed_integral_type() => (rhs, true),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        // Increment: `i++`, `++i`, or `i += 1`
+        match ast_context[increment].kind {
+            CExprKind::Unary(_, UnOp::PostIncrement, e, _)
+            | CExprKind::Unary(_, UnOp::PreIncrement, e, _)
+                if is_var(ast_context, e, var) => {}
+            CExprKind::Binary(_, BinOp::AssignAdd, lhs, rhs, _, _)
+                if is_var(ast_context, lhs, var) && is_one(ast_context, rhs) => {}
+            _ => return None,
+        }
+
+        let modified = modified_vars(ast_context, body)?;
+        if modified.contains(&var) {
+            return None;
+        }
+        if !is_invariant(ast_context, addr_taken, &modified, var, end) {
+            return None;
+        }
+
+        Some(ForRange {
+            var,
+            start,
+            end,
+            inclusive,
+        })
+    }
+}
+
+/// Is this expression a reference to `var`?
+fn is_var(ast_context: &TypedAstContext, expr: CExprId, var: CDeclId) -> bool {
+    match ast_context[expr].kind {
+        CExprKind::DeclRef(_, decl, _) => decl == var,
+        CExprKind::Paren(_, e) => is_var(ast_context, e, var),
+        _ => false,
+    }
+}
+
+/// Is this expression the value of `var`, without any conversion?
+fn is_var_value(ast_context: &TypedAstContext, expr: CExprId, var: CDeclId) -> bool {
+    match ast_context[expr].kind {
+        CExprKind::ImplicitCast(_, e, CastKind::LValueToRValue, _, _) => {
+            is_var(ast_context, e, var)
+        }
+        CExprKind::Paren(_, e) => is_var_value(ast_context, e, var),
+        _ => false,
+    }
+}
+
+fn is_one(ast_context: &TypedAstContext, expr: CExprId) -> bool {
+    match *ast_context.resolve_expr_value(expr) {
+        CExprKind::Literal(_, CLiteral::Integer(1, _)) => true,
+        _ => false,
+    }
+}
+
+/// Collect the variables a loop body assigns, increments, decrements, takes the address of, or
+/// passes as an assembly output. Returns `None` if control can leave the body in a way that a
+/// Rust `for` loop can't express: through a `goto`, or a `case` of an enclosing `switch`.
+fn modified_vars(ast_context: &TypedAstContext, body: CStmtId) -> Option<IndexSet<CDeclId>> {
+    let mut modified = IndexSet::new();
+    let mut modify = |expr: CExprId| {
+        if let CExprKind::DeclRef(_, decl, _) = *ast_context.resolve_expr_value(expr) {
+            modified.insert(decl);
+        }
+    };
+
+    let mut cases: IndexSet<CStmtId> = IndexSet::new();
+    let mut switch_cases: IndexSet<CStmtId> = IndexSet::new();
+
+    for id in DFExpr::new(ast_context, body.into()) {
+        match id {
+            SomeId::Stmt(stmt_id) => match ast_context[stmt_id].kind {
+                CStmtKind::Goto(_) | CStmtKind::IndirectGoto(_) | CStmtKind::Label(_) => {
+                    return None
+                }
+                CStmtKind::Case(..) | CStmtKind::CaseRange(..) | CStmtKind::Default(_) => {
+                    cases.insert(stmt_id);
+                }
+                CStmtKind::Switch { body, .. } => {
+                    switch_cases.extend(
+                        DFExpr::new(ast_context, body.into())
+                            .flat_map(SomeId::stmt)
+                            .filter(|&s| match ast_context[s].kind {
+                                CStmtKind::Case(..)
+                                | CStmtKind::CaseRange(..)
+                                | CStmtKind::Default(_) => true,
+                                _ => false,
+                            }),
+                    );
+                }
+                CStmtKind::Asm { ref outputs, .. } => {
+                    for output in outputs {
+                        modify(output.expression);
+                    }
+                }
+                _ => {}
+            },
+            SomeId::Expr(expr_id) => match ast_context[expr_id].kind {
+                CExprKind::Unary(_, op, e, _) => match op {
+                    UnOp::AddressOf
+                    | UnOp::PreIncrement
+                    | UnOp::PostIncrement
+                    | UnOp::PreDecrement
+                    | UnOp::PostDecrement => modify(e),
+                    _ => {}
+                },
+                CExprKind::Binary(_, op, lhs, _, _, _) if op.is_assignment() => modify(lhs),
+                CExprKind::AddrLabel(..) => return None,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    if cases.iter().any(|case| !switch_cases.contains(case)) {
+        return None;
+    }
+
+    Some(modified)
+}
+
+/// Does this expression evaluate to the same value on every iteration of a loop whose body
+/// modifies `modified`? `var` is the induction variable.
+fn is_invariant(
+    ast_context: &TypedAstContext,
+    addr_taken: &IndexSet<CDeclId>,
+    modified: &IndexSet<CDeclId>,
+    var: CDeclId,
+    expr: CExprId,
+) -> bool {
+    let invariant = |e| is_invariant(ast_context, addr_taken, modified, var, e);
+    match ast_context[expr].kind {
+        CExprKind::Literal(..) | CExprKind::UnaryType(_, _, None, _) => true,
+        CExprKind::ImplicitCast(_, e, _, _, _)
+        | CExprKind::ExplicitCast(_, e, _, _, _)
+        | CExprKind::Paren(_, e) => invariant(e),
+        CExprKind::Unary(_, op, e, _) => match op {
+            UnOp::Plus | UnOp::Negate | UnOp::Complement | UnOp::Not => invariant(e),
+            _ => false,
+        },
+        CExprKind::Binary(_, op, lhs, rhs, _, _) => {
+            !op.is_assignment() && op != BinOp::Comma && invariant(lhs) && invariant(rhs)
+        }
+        CExprKind::DeclRef(_, decl, _) => match ast_context.index(decl).kind {
+            CDeclKind::EnumConstant { .. } => true,
+            CDeclKind::Variable {
+                has_static_duration: false,
+                has_thread_duration: false,
+                typ,
+                ..
+            } => {
+                decl != var
+                    && !typ.qualifiers.is_volatile
+                    && !addr_taken.contains(&decl)
+                    && !modified.contains(&decl)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Do these statements `break` or `continue` to `label`?
+pub fn uses_label(stmts: &[Stmt], label: Label) -> bool {
+    struct LabelUses {
+        label: Ident,
+        used: bool,
+    }
+
+    impl<'ast> Visitor<'ast> for LabelUses {
+        fn visit_label(&mut self, label: &'ast syntax::ast::Label) {
+            self.used |= label.ident == self.label;
+        }
+    }
+
+    let mut uses = LabelUses {
+        label: mk().label(label.pretty_print()).ident,
+        used: false,
+    };
+    for stmt in stmts {
+        uses.visit_stmt(stmt);
+    }
+    uses.used
+}
@@ -1,5 +1,6 @@
 //! This module provides support for removing the extraneous break statements
-//! generated by the incremental relooper.
+//! generated by the incremental relooper, and the extraneous continue statements
+//! ending the bodies of `for` loops over a range.
 use super::*;
 
 pub struct IncCleanup {
@@ -115,16 +116,13 @@ impl IncCleanup {
             }
 
             _ => {
-                if let Expr {
-                    kind: ExprKind::Break(Some(ref blbl), None),
-                    ..
-                } = **tail_expr
-                {
-                    if blbl.ident == mk().label(self.brk_lbl.pretty_print()).ident {
-                        return true;
+                // A `continue` to the label is idempotent at the end of the body of a loop
+                match tail_expr.kind {
+                    ExprKind::Break(Some(ref blbl), None) | ExprKind::Continue(Some(ref blbl)) => {
+                        blbl.ident == mk().label(self.brk_lbl.pretty_print()).ident
                     }
+                    _ => false,
                 }
-                false
             }
         }
     }
@@ -24,6 +24,7 @@ use std::hash::Hash;
 use std::hash::Hasher;
 use std::io;
 use std::io::Write;
+use std::mem;
 use std::ops::Deref;
 use std::ops::Index;
 use syntax;
@@ -44,12 +45,15 @@ use crate::translator::*;
 use crate::with_stmts::WithStmts;
 use c2rust_ast_builder::mk;
 
+mod for_range;
 mod inc_cleanup;
 pub mod loops;
 pub mod multiples;
 pub mod relooper;
 pub mod structures;
+pub mod stmt_expr;
 
+use crate::cfg::for_range::ForRange;
 use crate::cfg::inc_cleanup::IncCleanup;
 use crate::cfg::loops::*;
 use crate::cfg::multiples::*;
@@ -91,6 +95,37 @@ impl Label {
     }
 }
 
+/// The integer standing in for the address of a C label (`&&label`). A computed goto on that value
+/// dispatches back to the label. We offset the ID by one so that no label's address is null.
+pub fn label_address(CStmtId(label_id): CLabelId) -> u128 {
+    label_id as u128 + 1
+}
+
+/// The literal for the value of a `case` label
+fn case_value_expr(cie: ConstIntExpr) -> P<Expr> {
+    match cie {
+        ConstIntExpr::U(n) => mk().lit_expr(mk().int_lit(n as u128, LitIntType::Unsuffixed)),
+
+        ConstIntExpr::I(n) if n >= 0 => {
+            mk().lit_expr(mk().int_lit(n as u128, LitIntType::Unsuffixed))
+        }
+
+        ConstIntExpr::I(n) => mk().unary_expr(
+            syntax::ast::UnOp::Neg,
+            mk().lit_expr(mk().int_lit((-n) as u128, LitIntType::Unsuffixed)),
+        ),
+    }
+}
+
+/// Is the GNU case range `case lo ... hi:` empty, i.e., is `lo > hi`?
+fn case_range_is_empty(lo: ConstIntExpr, hi: ConstIntExpr) -> bool {
+    match (lo, hi) {
+        (ConstIntExpr::U(lo), ConstIntExpr::U(hi)) => lo > hi,
+        (ConstIntExpr::I(lo), ConstIntExpr::I(hi)) => lo > hi,
+        _ => false,
+    }
+}
+
 impl Serialize for Label {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_str(&self.debug_print())
@@ -315,7 +350,7 @@ pub enum GenTerminator<Lbl> {
     /// Multi-way branch. The patterns are expected to match the type of the expression.
     Switch {
         expr: P<Expr>,
-        cases: Vec<(P<Pat>, Lbl)>, // TODO: support ranges of expressions
+        cases: Vec<(P<Pat>, Lbl)>,
     },
 }
 
@@ -436,6 +471,8 @@ impl GenTerminator<StructureLabel<StmtOrDecl>> {
 pub struct SwitchCases {
     cases: Vec<(P<Pat>, Label)>,
     default: Option<Label>,
+    /// The closed enum matched on, whose variants the cases are
+    closed_enum: Option<CEnumId>,
 }
 
 /// A Rust statement, or a C declaration, or a comment
@@ -564,14 +601,46 @@ impl Cfg<Label, StmtOrDecl> {
         stmt_ids: &[CStmtId],
         ret: ImplicitReturnType,
     ) -> Result<(Self, DeclStmtStore), TranslationError> {
+        // Labels whose address is taken with `&&label` are potential targets of every computed
+        // goto in these statements.
+        let addr_taken_labels: IndexSet<CLabelId> = stmt_ids
+            .iter()
+            .flat_map(|&stmt_id| DFExpr::new(&translator.ast_context, stmt_id.into()))
+            .flat_map(SomeId::expr)
+            .flat_map(|x| match translator.ast_context[x].kind {
+                CExprKind::AddrLabel(_, label) => Some(label),
+                _ => None,
+            })
+            .collect();
+
+        // Variables whose address is taken may be modified through a pointer anywhere in these
+        // statements.
+        let addr_taken_decls: IndexSet<CDeclId> = stmt_ids
+            .iter()
+            .flat_map(|&stmt_id| DFExpr::new(&translator.ast_context, stmt_id.into()))
+            .flat_map(SomeId::expr)
+            .flat_map(|x| match translator.ast_context[x].kind {
+                CExprKind::Unary(_, UnOp::AddressOf, e, _) => {
+                    match *translator.ast_context.resolve_expr_value(e) {
+                        CExprKind::DeclRef(_, decl, _) => Some(decl),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+
         let mut c_label_to_goto: IndexMap<CLabelId, IndexSet<CStmtId>> = IndexMap::new();
         for (target, x) in stmt_ids
             .iter()
             .flat_map(|&stmt_id| DFExpr::new(&translator.ast_context, stmt_id.into()))
             .flat_map(SomeId::stmt)
             .flat_map(|x| match translator.ast_context[x].kind {
-                CStmtKind::Goto(target) => Some((target, x)),
-                _ => None,
+                CStmtKind::Goto(target) => vec![(target, x)],
+                CStmtKind::IndirectGoto(_) => {
+                    addr_taken_labels.iter().map(|&target| (target, x)).collect()
+                }
+                _ => vec![],
             })
         {
             c_label_to_goto
@@ -580,7 +649,7 @@ impl Cfg<Label, StmtOrDecl> {
                 .insert(x);
         }
 
-        let mut cfg_builder = CfgBuilder::new(c_label_to_goto);
+        let mut cfg_builder = CfgBuilder::new(c_label_to_goto, addr_taken_labels, addr_taken_decls);
         let entry = cfg_builder.entry;
         cfg_builder.per_stmt_stack.push(PerStmt::new(
             stmt_ids.get(0).cloned(),
@@ -812,6 +881,13 @@ struct CfgBuilder {
     /// of the goto)
     c_label_to_goto: IndexMap<CLabelId, IndexSet<CStmtId>>,
 
+    /// Global (immutable) set of labels whose address is taken, in the order they appear. These
+    /// are the possible targets of a computed goto.
+    addr_taken_labels: IndexSet<CLabelId>,
+
+    /// Global (immutable) set of variables whose address is taken.
+    addr_taken_decls: IndexSet<CDeclId>,
+
     // Book-keeping information to build up the `loops` and `multiples` fields in `graph`.
     /// Loops we are currently in. Every time we enter a loop, we push a new vector onto this field.
     /// When we exit that loop, we pop the vector, add all the labels to the next entry in the
@@ -1215,6 +1291,101 @@ impl CfgBuilder {
         }
     }
 
+    /// Follow the chain of empty blocks starting at `lbl` to the first block that does something.
+    fn skip_empty_blocks(&mut self, mut lbl: Label) -> Label {
+        let nodes = &self.last_per_stmt_mut().nodes;
+        let mut seen: IndexSet<Label> = IndexSet::new();
+        while seen.insert(lbl) {
+            match nodes.get(&lbl).and_then(Cfg::empty_bb) {
+                Some(tgt) => lbl = tgt,
+                None => break,
+            }
+        }
+        lbl
+    }
+
+    /// Rewrite the cases of a `switch` whose cases fall through trivially, so that the relooper
+    /// can translate it into a single `match` rather than a `current_block` state machine:
+    ///
+    ///   * cases whose block is empty are retargeted to the block they fall through to, so that
+    ///     the relooper merges their patterns into one arm (`1 | 2 => ...`)
+    ///
+    ///   * a fallthrough into a case whose block only ends in a `break` gets its own copy of
+    ///     that block, so that the block is no longer shared between two arms
+    fn simplify_switch_cases(
+        &mut self,
+        cases: Vec<(P<Pat>, Label)>,
+        break_label: Label,
+    ) -> Vec<(P<Pat>, Label)> {
+        let cases: Vec<(P<Pat>, Label)> = cases
+            .into_iter()
+            .map(|(pat, lbl)| (pat, self.skip_empty_blocks(lbl)))
+            .collect();
+
+        let case_labels: IndexSet<Label> = cases.iter().map(|&(_, lbl)| lbl).collect();
+        for case_label in case_labels {
+            let copyable = match self.last_per_stmt_mut().nodes.get(&case_label) {
+                Some(bb) => {
+                    bb.defined.is_empty()
+                        && bb.body.iter().all(|s| match s {
+                            StmtOrDecl::Stmt(_) => true,
+                            StmtOrDecl::Decl(_) => false,
+                        })
+                        && match bb.terminator {
+                            Jump(_) => true,
+                            _ => false,
+                        }
+                }
+                None => false,
+            };
+            if !copyable {
+                continue;
+            }
+            let bb = self.last_per_stmt_mut().nodes[&case_label].clone();
+            let ends_in_break = match bb.terminator {
+                Jump(tgt) => self.skip_empty_blocks(tgt) == break_label,
+                _ => false,
+            };
+            if !ends_in_break {
+                continue;
+            }
+
+            // Find the jumps falling through into this case, skipping over empty blocks
+            let mut fallthroughs: Vec<(Label, usize)> = vec![];
+            let labels: Vec<Label> = self.last_per_stmt_mut().nodes.keys().cloned().collect();
+            for lbl in labels {
+                if Cfg::empty_bb(&self.last_per_stmt_mut().nodes[&lbl]).is_some() {
+                    continue;
+                }
+                let targets: Vec<Label> = self.last_per_stmt_mut().nodes[&lbl]
+                    .terminator
+                    .get_labels()
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                for (i, tgt) in targets.into_iter().enumerate() {
+                    if self.skip_empty_blocks(tgt) == case_label {
+                        fallthroughs.push((lbl, i));
+                    }
+                }
+            }
+
+            for (lbl, i) in fallthroughs {
+                let copy_label = self.fresh_label();
+                let copy = BasicBlock {
+                    terminator: Jump(break_label),
+                    span: DUMMY_SP,
+                    ..bb.clone()
+                };
+                self.add_block(copy_label, copy);
+                let bb = self.last_per_stmt_mut().nodes.get_mut(&lbl).unwrap();
+                *bb.terminator.get_labels_mut()[i] = copy_label;
+            }
+        }
+
+        cases
+    }
+
     /// Open a loop
     fn open_loop(&mut self) -> () {
         let loop_id: LoopId = self.fresh_loop_id();
@@ -1276,6 +1447,153 @@ impl CfgBuilder {
         b
     }
 
+    /// Translate the statements of the given statement expressions, which jump out of their
+    /// statement, ahead of the rest of it, binding their values for the translation of the
+    /// statement. Returns the block to continue translating the statement in.
+    fn lower_stmt_exprs(
+        &mut self,
+        translator: &Translation,
+        ctx: ExprContext,
+        compounds: Vec<CStmtId>,
+        mut wip: WipBlock,
+    ) -> Result<WipBlock, TranslationError> {
+        for compound in compounds {
+            // The value of the statement expression is that of its last statement, if it is an
+            // expression
+            let (stmts, result) = match translator.ast_context[compound].kind {
+                CStmtKind::Compound(ref stmts) => match stmts.split_last() {
+                    Some((&last, init)) => match translator.ast_context[last].kind {
+                        CStmtKind::Expr(expr) => (init.to_vec(), Some(expr)),
+                        _ => (stmts.clone(), None),
+                    },
+                    None => (vec![], None),
+                },
+                _ => (vec![compound], None),
+            };
+
+            let entry = self.fresh_label();
+            self.add_wip_block(wip, Jump(entry));
+
+            // The value may refer to variables declared by the statement expression, so it is
+            // translated in the same scope
+            wip = self.with_scope(
+                translator,
+                |slf| -> Result<WipBlock, TranslationError> {
+                    let mut lbl = entry;
+                    for &stmt in &stmts {
+                        lbl = match slf.convert_stmt_help(translator, ctx, stmt, None, lbl)? {
+                            Some(next) => next,
+                            // Control never reaches the value, so it goes in an unreachable block
+                            None => slf.fresh_label(),
+                        };
+                    }
+
+                    let mut wip = slf.new_wip_block(lbl);
+                    wip.extend(translator.convert_lowered_stmt_expr_value(ctx, compound, result)?);
+                    Ok(wip)
+                },
+            )?;
+        }
+
+        Ok(wip)
+    }
+
+    /// Lower the statement expressions of a loop condition or increment into the blocks which
+    /// evaluate it on each iteration, starting with `wip`. As in clang, `break` and `continue` in
+    /// a loop head apply to that loop.
+    fn lower_loop_head(
+        &mut self,
+        translator: &Translation,
+        ctx: ExprContext,
+        expr: CExprId,
+        wip: WipBlock,
+    ) -> Result<WipBlock, TranslationError> {
+        let stmt_exprs = stmt_expr::lowered_in_expr(&translator.ast_context, expr);
+        self.lower_stmt_exprs(translator, ctx, stmt_exprs, wip)
+    }
+
+    /// Translate a C `for` loop over a range into a Rust `for` loop. The body is relooped on its
+    /// own, with `break` and `continue` exiting to the Rust loop.
+    fn convert_for_range(
+        &mut self,
+        translator: &Translation,
+        ctx: ExprContext,
+        range: ForRange,
+        body: CStmtId,
+    ) -> Result<Vec<Stmt>, TranslationError> {
+        let loop_label = self.fresh_label();
+        let body_entry = self.fresh_label();
+        let brk_label = self.fresh_label();
+        let cont_label = self.fresh_label();
+
+        translator.with_scope(|| -> Result<Vec<Stmt>, TranslationError> {
+            let (mut stmts, (pat, range_expr)) = translator
+                .convert_for_range_head(ctx, range.var, range.start, range.end, range.inclusive)?
+                .discard_unsafe();
+
+            // The body gets a graph of its own, outside of the enclosing loops and branches
+            let loops = mem::replace(&mut self.loops, vec![]);
+            let multiples = mem::replace(&mut self.multiples, vec![]);
+            let live_in = self.current_variables();
+            self.per_stmt_stack.push(PerStmt::new(Some(body), body_entry, live_in));
+
+            self.with_scope(translator, |slf| -> Result<(), TranslationError> {
+                slf.break_labels.push(brk_label);
+                slf.continue_labels.push(cont_label);
+
+                let body_stuff =
+                    slf.convert_stmt_help(translator, ctx, body, None, body_entry)?;
+                if let Some(body_end) = body_stuff {
+                    let wip_body = slf.new_wip_block(body_end);
+                    slf.add_wip_block(wip_body, Jump(cont_label));
+                }
+
+                slf.break_labels.pop();
+                slf.continue_labels.pop();
+
+                let mut brk_wip = slf.new_wip_block(brk_label);
+                brk_wip.push_stmt(mk().semi_stmt(mk().break_expr(Some(loop_label.pretty_print()))));
+                slf.add_wip_block(brk_wip, End);
+
+                let mut cont_wip = slf.new_wip_block(cont_label);
+                cont_wip
+                    .push_stmt(mk().semi_stmt(mk().continue_expr(Some(loop_label.pretty_print()))));
+                slf.add_wip_block(cont_wip, End);
+
+                Ok(())
+            })?;
+
+            let body_per_stmt = self.per_stmt_stack.pop().unwrap();
+            self.loops = loops;
+            self.multiples = multiples;
+
+            let (graph, store, live_in) = body_per_stmt.into_cfg();
+            let mut body_stmts = translator.convert_cfg(
+                &format!("<for_body_{:?}>", body),
+                graph,
+                store,
+                live_in,
+                false,
+            )?;
+
+            // Falling off the end of the body continues the loop
+            IncCleanup::new(None, loop_label).remove_tail_expr(&mut body_stmts);
+            let label = if for_range::uses_label(&body_stmts, loop_label) {
+                Some(loop_label.pretty_print())
+            } else {
+                None
+            };
+
+            stmts.push(mk().expr_stmt(mk().for_expr(
+                pat,
+                range_expr,
+                mk().block(body_stmts),
+                label,
+            )));
+            Ok(stmts)
+        })
+    }
+
     fn current_variables(&self) -> IndexSet<CDeclId> {
         self.currently_live
             .last()
@@ -1307,7 +1625,11 @@ impl CfgBuilder {
     }
 
     /// Create a new `CfgBuilder` with a single entry label.
-    fn new(c_label_to_goto: IndexMap<CLabelId, IndexSet<CStmtId>>) -> CfgBuilder {
+    fn new(
+        c_label_to_goto: IndexMap<CLabelId, IndexSet<CStmtId>>,
+        addr_taken_labels: IndexSet<CLabelId>,
+        addr_taken_decls: IndexSet<CDeclId>,
+    ) -> CfgBuilder {
         let entry = Label::Synthetic(0);
 
         CfgBuilder {
@@ -1319,6 +1641,8 @@ impl CfgBuilder {
             prev_loop_id: 0,
 
             c_label_to_goto,
+            addr_taken_labels,
+            addr_taken_decls,
 
             break_labels: vec![],
             continue_labels: vec![],
@@ -1389,12 +1713,23 @@ impl CfgBuilder {
 
         wip.span = translator.get_span(SomeId::Stmt(stmt_id)).unwrap_or(DUMMY_SP);
 
+        // Statement expressions which jump out of this statement are part of this graph
+        let stmt_exprs = stmt_expr::lowered_stmt_exprs(&translator.ast_context, stmt_id);
+        let mut wip = self.lower_stmt_exprs(translator, ctx, stmt_exprs, wip)?;
+
+        // `for` loops over a range don't go through the relooper
+        let for_range =
+            ForRange::recognize(&translator.ast_context, &self.addr_taken_decls, stmt_id);
+
         let out_wip: Result<Option<WipBlock>, TranslationError> =
             match translator.ast_context.index(stmt_id).kind {
                 CStmtKind::Empty => Ok(Some(wip)),
 
                 CStmtKind::Decls(ref decls) => {
                     for decl in decls {
+                        let stmt_exprs = stmt_expr::lowered_in_decl(&translator.ast_context, *decl);
+                        wip = self.lower_stmt_exprs(translator, ctx, stmt_exprs, wip)?;
+
                         let info = translator.convert_decl_stmt_info(ctx, *decl)?;
                         self.last_per_stmt_mut()
                             .decls_seen
@@ -1427,6 +1762,18 @@ impl CfgBuilder {
                     true_variant,
                     false_variant,
                 } => {
+                    // An `if` conditioned on `setjmp` runs its direct branch inside the
+                    // condition, leaving only the branch taken after a `longjmp`
+                    let setjmp = if translator.tcfg.translate_setjmp {
+                        translator.match_setjmp_if(scrutinee, true_variant, false_variant)?
+                    } else {
+                        None
+                    };
+                    let (true_variant, false_variant) = match setjmp {
+                        Some(ref setjmp) => (setjmp.longjmp_branch, None),
+                        None => (Some(true_variant), false_variant),
+                    };
+
                     let next_entry = self.fresh_label();
                     let then_entry = self.fresh_label();
                     let else_entry = if false_variant.is_none() {
@@ -1436,10 +1783,17 @@ impl CfgBuilder {
                     };
 
                     // Condition
-                    let (stmts, val) = translator.convert_condition(ctx, true, scrutinee)?.discard_unsafe();
+                    let cond = match setjmp {
+                        Some(ref setjmp) => translator.convert_setjmp_condition(ctx, setjmp)?,
+                        None => translator.convert_condition(ctx, true, scrutinee)?,
+                    };
+                    let (stmts, val) = cond.discard_unsafe();
                     wip.extend(stmts);
 
-                    let cond_val = translator.ast_context[scrutinee].kind.get_bool();
+                    let cond_val = match setjmp {
+                        Some(_) => None,
+                        None => translator.ast_context[scrutinee].kind.get_bool(),
+                    };
                     self.add_wip_block(
                         wip,
                         match cond_val {
@@ -1451,10 +1805,15 @@ impl CfgBuilder {
 
                     // Then case
                     self.open_arm(then_entry);
-                    let then_stuff =
-                        self.convert_stmt_help(translator, ctx, true_variant, in_tail, then_entry)?;
-                    if let Some(then_end) = then_stuff {
-                        let wip_then = self.new_wip_block(then_end);
+                    if let Some(true_var) = true_variant {
+                        let then_stuff =
+                            self.convert_stmt_help(translator, ctx, true_var, in_tail, then_entry)?;
+                        if let Some(then_end) = then_stuff {
+                            let wip_then = self.new_wip_block(then_end);
+                            self.add_wip_block(wip_then, Jump(next_entry));
+                        }
+                    } else {
+                        let wip_then = self.new_wip_block(then_entry);
                         self.add_wip_block(wip_then, Jump(next_entry));
                     }
                     let then_arm = self.close_arm();
@@ -1490,10 +1849,17 @@ impl CfgBuilder {
                     self.add_wip_block(wip, Jump(cond_entry));
                     self.open_loop();
 
+                    let saw_unmatched_break = self.last_per_stmt_mut().saw_unmatched_break;
+                    let saw_unmatched_continue = self.last_per_stmt_mut().saw_unmatched_continue;
+                    self.break_labels.push(next_entry);
+                    self.continue_labels.push(cond_entry);
+
                     // Condition
+                    let cond_wip = self.new_wip_block(cond_entry);
+                    let mut cond_wip =
+                        self.lower_loop_head(translator, ctx, condition, cond_wip)?;
                     let (stmts, val) = translator.convert_condition(ctx, true, condition)?.discard_unsafe();
                     let cond_val = translator.ast_context[condition].kind.get_bool();
-                    let mut cond_wip = self.new_wip_block(cond_entry);
                     cond_wip.extend(stmts);
 
                     self.add_wip_block(
@@ -1506,11 +1872,6 @@ impl CfgBuilder {
                     );
 
                     // Body
-                    let saw_unmatched_break = self.last_per_stmt_mut().saw_unmatched_break;
-                    let saw_unmatched_continue = self.last_per_stmt_mut().saw_unmatched_continue;
-                    self.break_labels.push(next_entry);
-                    self.continue_labels.push(cond_entry);
-
                     let body_stuff =
                         self.convert_stmt_help(translator, ctx, body_stmt, None, body_entry)?;
                     if let Some(body_end) = body_stuff {
@@ -1552,15 +1913,12 @@ impl CfgBuilder {
                         self.add_wip_block(wip_body, Jump(cond_entry));
                     }
 
-                    self.last_per_stmt_mut().saw_unmatched_break = saw_unmatched_break;
-                    self.last_per_stmt_mut().saw_unmatched_continue = saw_unmatched_continue;
-                    self.break_labels.pop();
-                    self.continue_labels.pop();
-
                     // Condition
+                    let cond_wip = self.new_wip_block(cond_entry);
+                    let mut cond_wip =
+                        self.lower_loop_head(translator, ctx, condition, cond_wip)?;
                     let (stmts, val) = translator.convert_condition(ctx, true, condition)?.discard_unsafe();
                     let cond_val = translator.ast_context[condition].kind.get_bool();
-                    let mut cond_wip = self.new_wip_block(cond_entry);
                     cond_wip.extend(stmts);
                     self.add_wip_block(
                         cond_wip,
@@ -1571,12 +1929,23 @@ impl CfgBuilder {
                         },
                     );
 
+                    self.last_per_stmt_mut().saw_unmatched_break = saw_unmatched_break;
+                    self.last_per_stmt_mut().saw_unmatched_continue = saw_unmatched_continue;
+                    self.break_labels.pop();
+                    self.continue_labels.pop();
+
                     self.close_loop();
 
                     //Return
                     Ok(Some(self.new_wip_block(next_entry)))
                 }
 
+                CStmtKind::ForLoop { body, .. } if for_range.is_some() => {
+                    let range = for_range.unwrap();
+                    wip.extend(self.convert_for_range(translator, ctx, range, body)?);
+                    Ok(Some(wip))
+                }
+
                 CStmtKind::ForLoop {
                     init,
                     condition,
@@ -1605,13 +1974,20 @@ impl CfgBuilder {
 
                         slf.open_loop();
 
+                        let saw_unmatched_break = slf.last_per_stmt_mut().saw_unmatched_break;
+                        let saw_unmatched_continue = slf.last_per_stmt_mut().saw_unmatched_continue;
+                        slf.break_labels.push(next_label);
+                        slf.continue_labels.push(incr_entry);
+
                         // Condition
                         if let Some(cond) = condition {
+                            let cond_wip = slf.new_wip_block(cond_entry);
+                            let mut cond_wip =
+                                slf.lower_loop_head(translator, ctx, cond, cond_wip)?;
                             let (stmts, val) = translator
                                 .convert_condition(ctx, true, cond)?
                                 .discard_unsafe();
                             let cond_val = translator.ast_context[cond].kind.get_bool();
-                            let mut cond_wip = slf.new_wip_block(cond_entry);
                             cond_wip.extend(stmts);
                             slf.add_wip_block(
                                 cond_wip,
@@ -1626,11 +2002,6 @@ impl CfgBuilder {
                         }
 
                         // Body
-                        let saw_unmatched_break = slf.last_per_stmt_mut().saw_unmatched_break;
-                        let saw_unmatched_continue = slf.last_per_stmt_mut().saw_unmatched_continue;
-                        slf.break_labels.push(next_label);
-                        slf.continue_labels.push(incr_entry);
-
                         let body_stuff =
                             slf.convert_stmt_help(translator, ctx, body, None, body_entry)?;
 
@@ -1639,22 +2010,24 @@ impl CfgBuilder {
                             slf.add_wip_block(wip_body, Jump(incr_entry));
                         }
 
-                        slf.last_per_stmt_mut().saw_unmatched_break = saw_unmatched_break;
-                        slf.last_per_stmt_mut().saw_unmatched_continue = saw_unmatched_continue;
-                        slf.break_labels.pop();
-                        slf.continue_labels.pop();
-
                         // Increment
                         match increment {
                             None => slf.add_block(incr_entry, BasicBlock::new_jump(cond_entry)),
                             Some(incr) => {
+                                let incr_wip = slf.new_wip_block(incr_entry);
+                                let mut incr_wip =
+                                    slf.lower_loop_head(translator, ctx, incr, incr_wip)?;
                                 let incr_stmts = translator.convert_expr(ctx.unused(), incr)?.into_stmts();
-                                let mut incr_wip = slf.new_wip_block(incr_entry);
                                 incr_wip.extend(incr_stmts);
                                 slf.add_wip_block(incr_wip, Jump(cond_entry));
                             }
                         }
 
+                        slf.last_per_stmt_mut().saw_unmatched_break = saw_unmatched_break;
+                        slf.last_per_stmt_mut().saw_unmatched_continue = saw_unmatched_continue;
+                        slf.break_labels.pop();
+                        slf.continue_labels.pop();
+
                         slf.close_loop();
 
                         Ok(())
@@ -1687,6 +2060,39 @@ impl CfgBuilder {
                     Ok(None)
                 }
 
+                CStmtKind::IndirectGoto(target) => {
+                    // The target is one of the integers produced by `&&label`, so we dispatch on
+                    // it to every label whose address is taken.
+                    let (stmts, val) = translator
+                        .convert_expr(ctx.used(), target)?
+                        .discard_unsafe();
+                    wip.extend(stmts);
+                    let val = mk().cast_expr(val, mk().path_ty(vec!["usize"]));
+
+                    let mut cases = vec![];
+                    for label_id in self.addr_taken_labels.clone() {
+                        let addr = label_address(label_id);
+                        let pat = mk().lit_pat(mk().lit_expr(mk().int_lit(addr, LitIntType::Unsuffixed)));
+                        cases.push((pat, Label::FromC(label_id)));
+                        self.last_per_stmt_mut()
+                            .c_labels_used
+                            .entry(label_id)
+                            .or_insert(IndexSet::new())
+                            .insert(stmt_id);
+                    }
+
+                    // Jumping anywhere else is undefined behavior
+                    let bad_target = self.fresh_label();
+                    cases.push((mk().wild_pat(), bad_target));
+                    self.add_wip_block(wip, Switch { expr: val, cases });
+
+                    let mut bad_wip = self.new_wip_block(bad_target);
+                    bad_wip.push_stmt(mk().semi_stmt(translator.panic("Invalid computed goto target")));
+                    self.add_wip_block(bad_wip, End);
+
+                    Ok(None)
+                }
+
                 CStmtKind::Compound(ref comp_stmts) => {
                     let comp_entry = self.fresh_label();
                     self.add_wip_block(wip, Jump(comp_entry));
@@ -1704,19 +2110,29 @@ impl CfgBuilder {
                 CStmtKind::Expr(expr) => 'case_blk: {
                     // This case typically happens in macros from system headers.
                     // We simply inline the common statement at this point rather
-                    // than to try and create new control-flow blocks.
-                    if let CExprKind::Unary(_, UnOp::Extension, sube, _) =
-                        translator.ast_context[expr].kind
-                    {
-                        if let CExprKind::Statements(_, stmtid) = translator.ast_context[sube].kind
+                    // than to try and create new control-flow blocks. Statement
+                    // expressions which jump out of themselves have to be inlined.
+                    let inlined = match translator.ast_context[expr].kind {
+                        CExprKind::Unary(_, UnOp::Extension, sube, _) => {
+                            match translator.ast_context[sube].kind {
+                                CExprKind::Statements(_, stmtid) => Some(stmtid),
+                                _ => None,
+                            }
+                        }
+                        CExprKind::Statements(_, stmtid)
+                            if stmt_expr::jumps_out(&translator.ast_context, stmtid) =>
                         {
-                            let comp_entry = self.fresh_label();
-                            self.add_wip_block(wip, Jump(comp_entry));
-                            let next_lbl = self
-                                .convert_stmt_help(translator, ctx, stmtid, in_tail, comp_entry)?;
-
-                            break 'case_blk Ok(next_lbl.map(|l| self.new_wip_block(l)));
+                            Some(stmtid)
                         }
+                        _ => None,
+                    };
+                    if let Some(stmtid) = inlined {
+                        let comp_entry = self.fresh_label();
+                        self.add_wip_block(wip, Jump(comp_entry));
+                        let next_lbl =
+                            self.convert_stmt_help(translator, ctx, stmtid, in_tail, comp_entry)?;
+
+                        break 'case_blk Ok(next_lbl.map(|l| self.new_wip_block(l)));
                     }
 
                     wip.extend(translator.convert_expr(ctx.unused(), expr)?.into_stmts());
@@ -1755,34 +2171,42 @@ impl CfgBuilder {
                     Ok(None)
                 }
 
-                CStmtKind::Case(_case_expr, sub_stmt, cie) => {
+                CStmtKind::Case(_, sub_stmt, _) | CStmtKind::CaseRange(_, _, sub_stmt, _, _) => {
                     self.last_per_stmt_mut().saw_unmatched_case = true;
                     let this_label = Label::FromC(stmt_id);
                     self.add_wip_block(wip, Jump(this_label));
 
                     // Case
-                    let branch = match cie {
-                        ConstIntExpr::U(n) => {
-                            mk().lit_expr(mk().int_lit(n as u128, LitIntType::Unsuffixed))
+                    let switch_cases = self.switch_expr_cases.last_mut().ok_or(format_err!(
+                        "Cannot find the 'switch' wrapping this ({:?}) 'case' statement",
+                        stmt_id,
+                    ))?;
+                    let pat = match translator.ast_context[stmt_id].kind {
+                        // An empty range matches nothing, so its body is only reachable by
+                        // falling through from the previous case
+                        CStmtKind::CaseRange(_, _, _, lo, hi) if case_range_is_empty(lo, hi) => {
+                            let loc = &translator.ast_context[stmt_id].loc;
+                            warn!(
+                                "Empty case range will never match{}",
+                                translator
+                                    .ast_context
+                                    .display_loc(loc)
+                                    .map_or(String::new(), |loc| format!(" at {}", loc)),
+                            );
+                            None
                         }
-
-                        ConstIntExpr::I(n) if n >= 0 => {
-                            mk().lit_expr(mk().int_lit(n as u128, LitIntType::Unsuffixed))
+                        CStmtKind::CaseRange(_, _, _, lo, hi) => {
+                            Some(mk().range_pat(case_value_expr(lo), case_value_expr(hi)))
                         }
-
-                        ConstIntExpr::I(n) => mk().unary_expr(
-                            syntax::ast::UnOp::Neg,
-                            mk().lit_expr(mk().int_lit((-n) as u128, LitIntType::Unsuffixed)),
-                        ),
+                        CStmtKind::Case(_, _, cie) => Some(match switch_cases.closed_enum {
+                            Some(enum_id) => translator.closed_enum_case_pat(enum_id, cie)?,
+                            None => mk().lit_pat(case_value_expr(cie)),
+                        }),
+                        _ => unreachable!(),
                     };
-                    self.switch_expr_cases
-                        .last_mut()
-                        .ok_or(format_err!(
-                            "Cannot find the 'switch' wrapping this ({:?}) 'case' statement",
-                            stmt_id,
-                        ))?
-                        .cases
-                        .push((mk().lit_pat(branch), this_label));
+                    if let Some(pat) = pat {
+                        switch_cases.cases.push((pat, this_label));
+                    }
 
                     // Sub stmt
                     let sub_stmt_next =
@@ -1815,6 +2239,17 @@ impl CfgBuilder {
                     let next_label = self.fresh_label();
                     let body_label = self.fresh_label();
 
+                    // Switches over closed enums match on their variants
+                    let closed_enum_switch = if translator.tcfg.translate_closed_enums {
+                        translator.closed_enum_switch(scrutinee, switch_body)
+                    } else {
+                        None
+                    };
+                    let (scrutinee, closed_enum, exhaustive) = match closed_enum_switch {
+                        Some((value, enum_id, exhaustive)) => (value, Some(enum_id), exhaustive),
+                        None => (scrutinee, None, false),
+                    };
+
                     // Convert the condition
                     let (stmts, val) = translator
                         .convert_expr(ctx.used(), scrutinee)?
@@ -1829,7 +2264,10 @@ impl CfgBuilder {
                     let saw_unmatched_case = self.last_per_stmt_mut().saw_unmatched_case;
                     let saw_unmatched_default = self.last_per_stmt_mut().saw_unmatched_default;
                     self.break_labels.push(next_label);
-                    self.switch_expr_cases.push(SwitchCases::default());
+                    self.switch_expr_cases.push(SwitchCases {
+                        closed_enum,
+                        ..SwitchCases::default()
+                    });
 
                     let body_stuff =
                         self.convert_stmt_help(translator, ctx, switch_body, in_tail, body_label)?;
@@ -1848,10 +2286,14 @@ impl CfgBuilder {
                         .expect("No 'SwitchCases' to pop");
 
                     let mut cases: Vec<_> = switch_case.cases.clone();
-                    cases.push((
-                        mk().wild_pat(),
-                        switch_case.default.unwrap_or(next_label),
-                    ));
+                    // A match covering every variant of a closed enum needs no wildcard arm
+                    if !exhaustive || switch_case.default.is_some() {
+                        cases.push((
+                            mk().wild_pat(),
+                            switch_case.default.unwrap_or(next_label),
+                        ));
+                    }
+                    let cases = self.simplify_switch_cases(cases, next_label);
 
                     // Add the condition basic block terminator (we need the information built up during
                     // the conversion of the body to make the right terminator)
@@ -0,0 +1,141 @@
+//! GNU statement expressions, `({ ... })`, are normally translated into a Rust block expression
+//! with a control-flow graph of its own. That graph can't express control leaving the statement
+//! expression other than through `return`, like the `goto` in `({ if (err) goto out; val; })`.
+//!
+//! Such statement expressions are instead lowered into the graph of the enclosing function:
+//! their statements are translated right before the statement containing them, and their value
+//! is bound to a variable that the statement then uses. This is only possible when the
+//! statement expression is evaluated unconditionally, before the rest of its statement. The
+//! statement expressions of a loop condition or increment are lowered into the blocks that
+//! evaluate it on each iteration.
+use super::*;
+use crate::c_ast::iterators::immediate_children_all_types;
+
+/// Can control leave this statement expression through a `goto`, `break`, or `continue`?
+pub fn jumps_out(ast_context: &TypedAstContext, compound: CStmtId) -> bool {
+    let stmts: Vec<CStmtId> = DFExpr::new(ast_context, compound.into())
+        .flat_map(SomeId::stmt)
+        .collect();
+    let labels: IndexSet<CStmtId> = stmts
+        .iter()
+        .cloned()
+        .filter(|&s| match ast_context[s].kind {
+            CStmtKind::Label(_) => true,
+            _ => false,
+        })
+        .collect();
+
+    let gotos_out = stmts.iter().any(|&s| match ast_context[s].kind {
+        CStmtKind::Goto(target) => !labels.contains(&target),
+        CStmtKind::IndirectGoto(_) => true,
+        _ => false,
+    });
+
+    gotos_out || exits_loop(ast_context, compound.into(), false, false)
+}
+
+/// Is there a `break` or `continue` under this node which doesn't target a loop or `switch`
+/// under this node?
+fn exits_loop(ast_context: &TypedAstContext, id: SomeId, in_loop: bool, in_switch: bool) -> bool {
+    let (in_loop, in_switch) = match id {
+        SomeId::Stmt(stmt_id) => match ast_context[stmt_id].kind {
+            CStmtKind::Break => return !in_loop && !in_switch,
+            CStmtKind::Continue => return !in_loop,
+            CStmtKind::While { .. } | CStmtKind::DoWhile { .. } | CStmtKind::ForLoop { .. } => {
+                (true, in_switch)
+            }
+            CStmtKind::Switch { .. } => (in_loop, true),
+            _ => (in_loop, in_switch),
+        },
+        _ => (in_loop, in_switch),
+    };
+
+    immediate_children_all_types(ast_context, id)
+        .into_iter()
+        .any(|child| match child {
+            SomeId::Type(_) => false,
+            _ => exits_loop(ast_context, child, in_loop, in_switch),
+        })
+}
+
+/// Find the statement expressions of a statement which jump out of it and can be lowered into the
+/// graph of the enclosing function, in the order they are evaluated. A statement expression which
+/// is the whole of an expression statement isn't included: it is translated as a compound
+/// statement. Neither are those of declarations and loops, which are lowered one declarator or
+/// loop head at a time with `lowered_in_decl` and `lowered_in_expr`.
+pub fn lowered_stmt_exprs(ast_context: &TypedAstContext, stmt_id: CStmtId) -> Vec<CStmtId> {
+    match ast_context[stmt_id].kind {
+        CStmtKind::Expr(expr) => match ast_context[expr].kind {
+            CExprKind::Statements(..) => vec![],
+            CExprKind::Unary(_, UnOp::Extension, sube, _) => match ast_context[sube].kind {
+                CExprKind::Statements(..) => vec![],
+                _ => lowered_in_expr(ast_context, expr),
+            },
+            _ => lowered_in_expr(ast_context, expr),
+        },
+        CStmtKind::Return(Some(expr)) => lowered_in_expr(ast_context, expr),
+        CStmtKind::If { scrutinee, .. } | CStmtKind::Switch { scrutinee, .. } => {
+            lowered_in_expr(ast_context, scrutinee)
+        }
+        _ => vec![],
+    }
+}
+
+/// Find the statement expressions to lower in the initializer of a declarator. Each declarator
+/// of a declaration is in scope in the initializers of the following ones.
+pub fn lowered_in_decl(ast_context: &TypedAstContext, decl: CDeclId) -> Vec<CStmtId> {
+    match ast_context.index(decl).kind {
+        CDeclKind::Variable {
+            has_static_duration: false,
+            has_thread_duration: false,
+            initializer: Some(init),
+            ..
+        } => lowered_in_expr(ast_context, init),
+        _ => vec![],
+    }
+}
+
+/// Find the statement expressions to lower in an expression, in the order they are evaluated
+pub fn lowered_in_expr(ast_context: &TypedAstContext, expr: CExprId) -> Vec<CStmtId> {
+    let mut stmt_exprs = vec![];
+    collect_stmt_exprs(ast_context, expr, &mut stmt_exprs);
+    stmt_exprs
+}
+
+/// Collect the statement expressions to lower that are evaluated unconditionally and before the
+/// operands they are sequenced after.
+fn collect_stmt_exprs(ast_context: &TypedAstContext, expr: CExprId, stmt_exprs: &mut Vec<CStmtId>) {
+    let mut collect = |e| collect_stmt_exprs(ast_context, e, stmt_exprs);
+    match ast_context[expr].kind {
+        CExprKind::Statements(_, compound) => {
+            // Statement expressions nested in this one are lowered along with its statements
+            if jumps_out(ast_context, compound) {
+                stmt_exprs.push(compound);
+            }
+        }
+
+        // Only the first operand is evaluated unconditionally, or before the others
+        CExprKind::Binary(_, BinOp::And, lhs, _, _, _)
+        | CExprKind::Binary(_, BinOp::Or, lhs, _, _, _)
+        | CExprKind::Binary(_, BinOp::Comma, lhs, _, _, _)
+        | CExprKind::Conditional(_, lhs, _, _)
+        | CExprKind::BinaryConditional(_, lhs, _) => collect(lhs),
+
+        // Only the selected operand is evaluated
+        CExprKind::Choose(_, _, lhs, rhs, is_cond_true) => {
+            collect(if is_cond_true { lhs } else { rhs })
+        }
+        CExprKind::GenericSelection(_, _, ref assocs, selected) => collect(assocs[selected].1),
+
+        // Not evaluated
+        CExprKind::UnaryType(..) => {}
+
+        _ => {
+            for child in immediate_children_all_types(ast_context, expr.into()) {
+                if let SomeId::Expr(e) = child {
+                    collect(e);
+                }
+            }
+        }
+    }
+}
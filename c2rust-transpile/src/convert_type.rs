@@ -16,6 +16,8 @@ enum FieldKey {
 
 pub struct TypeConverter {
     pub translate_valist: bool,
+    pub translate_complex: bool,
+    pub translate_vector_lanes: bool,
     renamer: Renamer<CDeclId>,
     fields: HashMap<CDeclId, Renamer<FieldKey>>,
     suffix_names: HashMap<(CDeclId, &'static str), String>,
@@ -136,6 +138,8 @@ impl TypeConverter {
     pub fn new(emit_no_std: bool) -> TypeConverter {
         TypeConverter {
             translate_valist: false,
+            translate_complex: false,
+            translate_vector_lanes: false,
             renamer: Renamer::new(&RESERVED_NAMES),
             fields: HashMap::new(),
             suffix_names: HashMap::new(),
@@ -404,10 +408,102 @@ impl TypeConverter {
 
             CTypeKind::TypeOf(ty) => self.convert(ctxt, ty),
 
+            CTypeKind::Atomic(qtype) => self.convert_atomic(ctxt, qtype),
+
+            CTypeKind::Complex(elt) if self.translate_complex => self.convert_complex(ctxt, elt),
+
+            // Vectors of targets without Rust SIMD types are arrays of their lanes
+            CTypeKind::Vector(elt, len) if self.translate_vector_lanes => {
+                let ty = self.convert(ctxt, elt.ctype)?;
+                Ok(mk().array_ty(
+                    ty,
+                    mk().lit_expr(mk().int_lit(len as u128, LitIntType::Unsuffixed)),
+                ))
+            }
+
             ref t => Err(format_err!("Unsupported type {:?}", t).into()),
         }
     }
 
+    /// Name of the `core::sync::atomic` type used to represent `_Atomic` values of the given type
+    pub fn atomic_type_name(
+        ctxt: &TypedAstContext,
+        qtype: CQualTypeId,
+    ) -> Result<&'static str, TranslationError> {
+        let name = match ctxt.resolve_type(qtype.ctype).kind {
+            CTypeKind::Bool => "AtomicBool",
+            // `c_char` and `c_long` follow the target's signedness and width
+            CTypeKind::Char if ctxt.char_is_signed => "AtomicI8",
+            CTypeKind::Char => "AtomicU8",
+            CTypeKind::SChar => "AtomicI8",
+            CTypeKind::UChar => "AtomicU8",
+            CTypeKind::Short => "AtomicI16",
+            CTypeKind::UShort => "AtomicU16",
+            CTypeKind::Int => "AtomicI32",
+            CTypeKind::UInt => "AtomicU32",
+            CTypeKind::Long if ctxt.long_width == 32 => "AtomicI32",
+            CTypeKind::ULong if ctxt.long_width == 32 => "AtomicU32",
+            CTypeKind::Long | CTypeKind::LongLong => "AtomicI64",
+            CTypeKind::ULong | CTypeKind::ULongLong => "AtomicU64",
+            CTypeKind::Pointer(pointee) => match ctxt.resolve_type(pointee.ctype).kind {
+                CTypeKind::Function(..) => {
+                    return Err(format_err!("Unsupported atomic function pointer type").into())
+                }
+                _ => "AtomicPtr",
+            },
+            ref t => return Err(format_err!("Unsupported atomic type {:?}", t).into()),
+        };
+        Ok(name)
+    }
+
+    /// Convert a C11 `_Atomic` type to the matching `core::sync::atomic` type. Atomic pointers
+    /// always use `AtomicPtr<T>`, which stores a `*mut T` regardless of the pointee's constness.
+    pub fn convert_atomic(
+        &mut self,
+        ctxt: &TypedAstContext,
+        qtype: CQualTypeId,
+    ) -> Result<P<Ty>, TranslationError> {
+        let name = TypeConverter::atomic_type_name(ctxt, qtype)?;
+        let std_or_core = if self.emit_no_std { "core" } else { "std" };
+
+        let last = if let CTypeKind::Pointer(pointee) = ctxt.resolve_type(qtype.ctype).kind {
+            let pointee_ty = match ctxt.resolve_type(pointee.ctype).kind {
+                CTypeKind::Void => mk().path_ty(vec!["libc", "c_void"]),
+                _ => self.convert(ctxt, pointee.ctype)?,
+            };
+            mk().path_segment_with_args(name, mk().angle_bracketed_args(vec![pointee_ty]))
+        } else {
+            mk().path_segment(name)
+        };
+
+        Ok(mk().path_ty(vec![
+            mk().path_segment(""),
+            mk().path_segment(std_or_core),
+            mk().path_segment("sync"),
+            mk().path_segment("atomic"),
+            last,
+        ]))
+    }
+
+    /// Convert a C99 `_Complex` type to `num_complex::Complex`, which has the same layout as the
+    /// C type. Only `float` and `double` elements are supported.
+    pub fn convert_complex(
+        &mut self,
+        ctxt: &TypedAstContext,
+        elt: CTypeId,
+    ) -> Result<P<Ty>, TranslationError> {
+        match ctxt.resolve_type(elt).kind {
+            CTypeKind::Float | CTypeKind::Double => {
+                let elt_ty = self.convert(ctxt, elt)?;
+                Ok(mk().path_ty(vec![
+                    mk().path_segment("num_complex"),
+                    mk().path_segment_with_args("Complex", mk().angle_bracketed_args(vec![elt_ty])),
+                ]))
+            }
+            ref t => Err(format_err!("Unsupported complex element type {:?}", t).into()),
+        }
+    }
+
     /// Add the given parameters to a K&R function pointer type,
     /// returning a full signature or `None` if the function isn't K&R.
     pub fn knr_function_type_with_parameters(
@@ -0,0 +1,220 @@
+//! Deduplication of the type definitions every translation unit gets from the headers it
+//! includes, for `--dedup-types`.
+//!
+//! The translator sets the items translated from header records, enums and typedefs aside
+//! as `HeaderType`s instead of emitting them. Once every translation unit of a crate has been
+//! translated, the header types that all translation units translated identically, and that
+//! only refer to other such types, are emitted once in a shared `types` module and imported
+//! by the translation units; every other header type stays in the translation units that
+//! define it.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use syntax::ast::{Item, ItemKind, NodeId, Path as AstPath, UseTree, UseTreeKind};
+use syntax::ptr::P;
+use syntax::visit::{self, Visitor};
+
+use c2rust_ast_printer::pprust;
+
+use crate::build_files::module_path;
+use crate::{write_translation, CrateSet, ExternCrateDetails, TranspilerConfig};
+
+/// A type definition translated from a header
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HeaderType {
+    pub name: String,
+    /// The translated item
+    pub text: String,
+    /// Names of the other items of the translation unit this one refers to
+    pub deps: Vec<String>,
+}
+
+/// A translation whose header types are only emitted once the shared ones are known
+#[derive(Debug)]
+pub struct DeferredUnit {
+    pub output_path: PathBuf,
+    pub translation: String,
+    pub header_types: Vec<HeaderType>,
+}
+
+/// Collect the names of the items a header type refers to without a path, e.g., `foo` in
+/// `pub next: *mut foo`
+struct NameCollector(HashSet<String>);
+
+impl<'ast> Visitor<'ast> for NameCollector {
+    fn visit_path(&mut self, path: &'ast AstPath, _id: NodeId) {
+        if let [segment] = &path.segments[..] {
+            self.0.insert(segment.ident.to_string());
+        }
+        visit::walk_path(self, path);
+    }
+}
+
+/// Add the names a `use` item imports to `names`
+pub fn collect_imported_names(item: &Item, names: &mut HashSet<String>) {
+    fn collect(tree: &UseTree, names: &mut HashSet<String>) {
+        match tree.kind {
+            UseTreeKind::Simple(..) => {
+                names.insert(tree.ident().to_string());
+            }
+            UseTreeKind::Nested(ref trees) => {
+                for (tree, _) in trees {
+                    collect(tree, names);
+                }
+            }
+            UseTreeKind::Glob => {}
+        }
+    }
+
+    if let ItemKind::Use(ref tree) = item.kind {
+        collect(tree, names);
+    }
+}
+
+/// Print the header type items of a translation unit, given the names of all the items the
+/// translation unit defines
+pub fn header_types(items: Vec<P<Item>>, defined: &HashSet<String>) -> Vec<HeaderType> {
+    let mut header_types: Vec<HeaderType> = vec![];
+    for item in items {
+        let mut names = NameCollector(HashSet::new());
+        names.visit_item(&item);
+        let text = pprust::item_to_string(&item);
+
+        // Trait implementations are unnamed, and shared along with the type they follow
+        if let ItemKind::Impl(..) = item.kind {
+            if let Some(header_type) = header_types.last_mut() {
+                header_type.text.push('\n');
+                header_type.text.push_str(&text);
+                for dep in names.0 {
+                    if dep != header_type.name
+                        && defined.contains(&dep)
+                        && !header_type.deps.contains(&dep)
+                    {
+                        header_type.deps.push(dep);
+                    }
+                }
+                header_type.deps.sort();
+                continue;
+            }
+        }
+
+        let name = item.ident.to_string();
+        let mut deps = names
+            .0
+            .into_iter()
+            .filter(|dep| *dep != name && defined.contains(dep))
+            .collect::<Vec<_>>();
+        deps.sort();
+        header_types.push(HeaderType { name, text, deps });
+    }
+    header_types
+}
+
+/// Find the header types to share: those which every translation unit that defines them
+/// translated identically, and whose dependencies are all shared. Returns their definitions
+/// in the order the translation units define them.
+fn find_shared_types(units: &[&DeferredUnit]) -> IndexMap<String, String> {
+    // The definition of each header type, or `None` if it differs between translation units
+    let mut defs: IndexMap<&str, Option<&HeaderType>> = IndexMap::new();
+    for unit in units {
+        for header_type in &unit.header_types {
+            let def = defs
+                .entry(header_type.name.as_str())
+                .or_insert(Some(header_type));
+            if def.map_or(false, |def| def.text != header_type.text) {
+                *def = None;
+            }
+        }
+    }
+
+    // Dependencies resolve to the local items of each translation unit, so a header type
+    // can only be shared along with everything it refers to
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..defs.len() {
+            let unshared_dep = match defs.get_index(i) {
+                Some((_, Some(def))) => def.deps.iter().any(|dep| {
+                    defs.get(dep.as_str())
+                        .map_or(true, |dep_def| dep_def.is_none())
+                }),
+                _ => false,
+            };
+            if unshared_dep {
+                *defs.get_index_mut(i).unwrap().1 = None;
+                changed = true;
+            }
+        }
+    }
+
+    defs.into_iter()
+        .filter_map(|(name, def)| def.map(|def| (name.to_string(), def.text.clone())))
+        .collect()
+}
+
+/// Get the path of the shared `types` module, next to the translated files
+fn types_module_path(tcfg: &TranspilerConfig, build_dir: &Path) -> PathBuf {
+    if tcfg.output_dir.is_some() {
+        build_dir.join("src").join("types.rs")
+    } else {
+        build_dir.join("types.rs")
+    }
+}
+
+/// Emit the translation units of a crate, importing the header types they share from a
+/// `types` module. Returns the path of that module, if any header types were shared.
+pub fn emit_units(
+    tcfg: &TranspilerConfig,
+    build_dir: &Path,
+    units: Vec<DeferredUnit>,
+    crates: &CrateSet,
+) -> Option<PathBuf> {
+    let types_path = types_module_path(tcfg, build_dir);
+    let shared = if units.iter().any(|unit| unit.output_path == types_path) {
+        warn!(
+            "Can't emit shared types to {}, which is a translated file; not deduplicating types.",
+            types_path.display()
+        );
+        IndexMap::new()
+    } else {
+        // Binaries are separate crates, which can't import from the library
+        let lib_units = units
+            .iter()
+            .filter(|unit| !tcfg.is_binary(&unit.output_path))
+            .collect::<Vec<_>>();
+        find_shared_types(&lib_units)
+    };
+    let types_mod = module_path(tcfg, build_dir, &types_path);
+
+    for unit in units {
+        let is_binary = tcfg.is_binary(&unit.output_path);
+        let mut translation = unit.translation;
+        for header_type in unit.header_types {
+            if shared.contains_key(&header_type.name) && !is_binary {
+                translation.push_str(&format!("use {}::{};\n", types_mod, header_type.name));
+            } else {
+                translation.push_str(&header_type.text);
+                translation.push('\n');
+            }
+        }
+        write_translation(&unit.output_path, &translation);
+    }
+
+    if shared.is_empty() {
+        return None;
+    }
+
+    let mut types = String::new();
+    for c in crates {
+        let path = ExternCrateDetails::from(*c).use_path().join("::");
+        types.push_str(&format!("use {};\n", path));
+    }
+    for text in shared.values() {
+        types.push_str(text);
+        types.push('\n');
+    }
+    write_translation(&types_path, &types);
+    Some(types_path)
+}
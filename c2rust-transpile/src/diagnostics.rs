@@ -30,7 +30,9 @@ pub fn init(mut enabled_warnings: HashSet<Diagnostic>, log_level: log::LevelFilt
     enabled_warnings.extend(DEFAULT_WARNINGS.iter().cloned());
 
     let colors = ColoredLevelConfig::new();
-    fern::Dispatch::new()
+    // The logger can only be installed once per process, so when the transpiler runs more
+    // than once (e.g., in tests), the first configuration stays in effect
+    let _ = fern::Dispatch::new()
         .format(move |out, message, record| {
             let level_label = match record.level() {
                 Level::Error => "error",
@@ -63,8 +65,7 @@ pub fn init(mut enabled_warnings: HashSet<Diagnostic>, log_level: log::LevelFilt
                 .unwrap_or(true)
         })
         .chain(io::stderr())
-        .apply()
-        .expect("Could not set up diagnostics");
+        .apply();
 }
 
 
@@ -157,6 +158,11 @@ impl TranslationError {
         self.inner.get_context().clone()
     }
 
+    /// The innermost location the error was reported at
+    pub fn loc(&self) -> Option<&DisplaySrcSpan> {
+        self.loc.first()
+    }
+
     pub fn new(loc: Option<DisplaySrcSpan>, inner: Context<TranslationErrorKind>) -> Self {
         let mut loc_stack = vec![];
         if let Some(loc) = loc {
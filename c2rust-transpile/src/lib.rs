@@ -3,6 +3,7 @@
 #![feature(box_patterns)]
 
 extern crate colored;
+extern crate crossbeam_utils;
 extern crate dtoa;
 extern crate syntax;
 extern crate syntax_pos;
@@ -32,10 +33,13 @@ mod diagnostics;
 
 pub mod build_files;
 pub mod c_ast;
+mod cache;
 pub mod cfg;
 mod compile_cmds;
 pub mod convert_type;
+pub mod dedup;
 pub mod renamer;
+pub mod report;
 pub mod rust_ast;
 pub mod translator;
 pub mod with_stmts;
@@ -46,6 +50,8 @@ use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use failure::Error;
 use regex::Regex;
@@ -56,18 +62,22 @@ pub use crate::diagnostics::Diagnostic;
 use c2rust_ast_exporter as ast_exporter;
 
 use crate::build_files::{emit_build_files, get_build_dir, CrateConfig};
-use crate::compile_cmds::get_compile_commands;
+use crate::cache::TranslationCache;
+use crate::compile_cmds::{get_compile_commands, CompileCmd};
 use crate::convert_type::RESERVED_NAMES;
+use crate::dedup::DeferredUnit;
+use crate::report::{DeclReport, Report, UnitOutcome, UnitReport};
 pub use crate::translator::ReplaceMode;
 use std::prelude::v1::Vec;
 
 type PragmaVec = Vec<(&'static str, Vec<&'static str>)>;
 type PragmaSet = indexmap::IndexSet<(&'static str, &'static str)>;
 type CrateSet = indexmap::IndexSet<ExternCrate>;
-type TranspileResult = Result<(PathBuf, PragmaVec, CrateSet), ()>;
+type TranspileResult =
+    Result<(PathBuf, PragmaVec, CrateSet, Vec<DeclReport>, Option<DeferredUnit>), ()>;
 
 /// Configuration settings for the translation process
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TranspilerConfig {
     // Debug output options
     pub dump_untyped_context: bool,
@@ -90,6 +100,9 @@ pub struct TranspilerConfig {
     pub cross_check_configs: Vec<String>,
     pub prefix_function_names: Option<String>,
     pub translate_asm: bool,
+    /// Translate inline assembly into the `asm!` syntax of newer nightlies instead of the
+    /// LLVM-style one
+    pub new_asm_syntax: bool,
     pub use_c_loop_info: bool,
     pub use_c_multiple_info: bool,
     pub simplify_structures: bool,
@@ -104,8 +117,29 @@ pub struct TranspilerConfig {
     pub enabled_warnings: HashSet<Diagnostic>,
     pub emit_no_std: bool,
     pub output_dir: Option<PathBuf>,
+    pub report: Option<PathBuf>,
     pub translate_const_macros: bool,
+    pub translate_fn_macros: bool,
+    pub translate_complex: bool,
+    pub translate_setjmp: bool,
+    pub translate_restrict: bool,
+    pub stable_variadics: bool,
+    /// Translate SIMD operations without a Rust equivalent into scalar code on arrays of lanes
+    pub scalar_simd_fallback: bool,
+    /// Translate enums whose values only ever come from their own constants into Rust enums
+    pub translate_closed_enums: bool,
+    /// Emit the types translation units translate identically from headers once, in a
+    /// shared `types` module
+    pub dedup_types: bool,
+    /// Experimental: translate each file under each of these preprocessor configurations
+    /// and merge the results
+    pub cfg_variants: Vec<CfgVariant>,
     pub disable_refactoring: bool,
+    /// Number of translation units to translate concurrently
+    pub jobs: usize,
+    /// Directory to cache translations in, so that unchanged translation units are not
+    /// translated again
+    pub cache_dir: Option<PathBuf>,
     pub log_level: log::LevelFilter,
 
     // Options that control build files
@@ -116,6 +150,15 @@ pub struct TranspilerConfig {
     pub binaries: Vec<String>,
 }
 
+/// A preprocessor configuration to translate under, enabled in the generated crate by the
+/// Cargo feature of the same name
+#[derive(Debug, Clone)]
+pub struct CfgVariant {
+    pub feature: String,
+    /// Extra clang arguments, e.g., `-D` and `-U` options
+    pub clang_args: Vec<String>,
+}
+
 impl TranspilerConfig {
     fn is_binary(&self, file: &Path) -> bool {
         let file = Path::new(file.file_stem().unwrap());
@@ -123,6 +166,19 @@ impl TranspilerConfig {
         self.binaries.contains(&name)
     }
 
+    /// The options that may affect the contents of translated files, for the cache key
+    fn translation_options(&self) -> String {
+        let mut tcfg = self.clone();
+        tcfg.filter = None;
+        tcfg.jobs = 1;
+        tcfg.cache_dir = None;
+        tcfg.report = None;
+        tcfg.verbose = false;
+        tcfg.enabled_warnings.clear();
+        tcfg.log_level = log::LevelFilter::Off;
+        format!("{:?}", tcfg)
+    }
+
     fn crate_name(&self) -> String {
         self.output_dir.as_ref().and_then(
             |x| x.file_name().map(|x| x.to_string_lossy().into_owned())
@@ -130,7 +186,7 @@ impl TranspilerConfig {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ExternCrate {
     C2RustBitfields,
     C2RustAsmCasts,
@@ -138,6 +194,10 @@ pub enum ExternCrate {
     NumTraits,
     Memoffset,
     Libc,
+    NumComplex,
+    C2RustSetjmp,
+    C2RustVarargs,
+    C2RustLongDouble,
 }
 
 #[derive(Serialize)]
@@ -146,6 +206,10 @@ struct ExternCrateDetails {
     ident: String,
     macro_use: bool,
     version: &'static str,
+    /// Source of a runtime support module which is emitted into the translated crate
+    /// (see `build_files`) instead of being a dependency
+    #[serde(skip)]
+    runtime: Option<&'static str>,
 }
 
 impl ExternCrateDetails {
@@ -155,8 +219,22 @@ impl ExternCrateDetails {
             ident: name.replace("-", "_"),
             macro_use,
             version,
+            runtime: None,
         }
     }
+
+    fn runtime(name: &'static str, source: &'static str) -> Self {
+        Self {
+            runtime: Some(source),
+            ..Self::new(name, "", false)
+        }
+    }
+
+    /// Path to import the crate, or the runtime module, into a module of the translated crate
+    fn use_path(&self) -> Vec<String> {
+        let root = if self.runtime.is_some() { "crate" } else { "" };
+        vec![root.to_string(), self.ident.clone()]
+    }
 }
 
 impl From<ExternCrate> for ExternCrateDetails {
@@ -168,6 +246,14 @@ impl From<ExternCrate> for ExternCrateDetails {
             ExternCrate::NumTraits => Self::new("num-traits", "0.2", true),
             ExternCrate::Memoffset => Self::new("memoffset", "0.5", true),
             ExternCrate::Libc => Self::new("libc", "0.2", false),
+            ExternCrate::NumComplex => Self::new("num-complex", "0.2", false),
+            ExternCrate::C2RustSetjmp => {
+                Self::runtime("c2rust-setjmp", include_str!("build_files/c2rust_setjmp.rs"))
+            }
+            ExternCrate::C2RustVarargs => {
+                Self::runtime("c2rust-varargs", include_str!("build_files/c2rust_varargs.rs"))
+            }
+            ExternCrate::C2RustLongDouble => Self::new("c2rust-long-double", "0.1", false),
         }
     }
 }
@@ -235,6 +321,19 @@ pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]
     let mut clang_args: Vec<&str> = clang_args.iter().map(AsRef::as_ref).collect();
     clang_args.extend_from_slice(extra_clang_args);
 
+    let cache = tcfg.cache_dir.as_ref().and_then(|dir| match TranslationCache::new(dir) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            warn!(
+                "Unable to create cache directory {}: {}; translating without the cache",
+                dir.display(),
+                e
+            );
+            None
+        }
+    });
+
+    let mut report = Report::default();
     let mut top_level_ccfg = None;
     let mut workspace_members = vec![];
     let mut num_transpiled_files = 0;
@@ -276,23 +375,30 @@ pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]
             }
         }
 
-        let results = cmds
-            .iter()
-            .map(|cmd| transpile_single(&tcfg, cmd.abs_file(),
-                                        &ancestor_path,
-                                        &build_dir,
-                                        cc_db,
-                                        extra_clang_args))
-            .collect::<Vec<TranspileResult>>();
+        let unit_cmds = cmds.iter().map(|cmd| (**cmd).clone()).collect::<Vec<_>>();
+        let results = transpile_units(&tcfg,
+                                      cache.as_ref(),
+                                      unit_cmds,
+                                      &ancestor_path,
+                                      &build_dir,
+                                      cc_db,
+                                      extra_clang_args);
         let mut modules = vec![];
         let mut modules_skipped = false;
         let mut pragmas = PragmaSet::new();
         let mut crates = CrateSet::new();
-        for res in results {
+        let mut deferred_units = vec![];
+        for (cmd, res) in cmds.iter().zip(results) {
             match res {
-                Ok((module, pragma_vec, crate_set)) => {
+                Ok((module, pragma_vec, crate_set, decls, deferred)) => {
+                    report.units.push(UnitReport {
+                        file: cmd.abs_file(),
+                        outcome: UnitOutcome::Translated,
+                        decls,
+                    });
                     modules.push(module);
                     crates.extend(crate_set);
+                    deferred_units.extend(deferred);
 
                     num_transpiled_files += 1;
                     for (key, vals) in pragma_vec {
@@ -302,6 +408,11 @@ pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]
                     }
                 },
                 Err(_) => {
+                    report.units.push(UnitReport {
+                        file: cmd.abs_file(),
+                        outcome: UnitOutcome::Skipped,
+                        decls: vec![],
+                    });
                     modules_skipped = true;
                 }
             }
@@ -309,10 +420,20 @@ pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]
         pragmas.sort();
         crates.sort();
 
+        if tcfg.dedup_types {
+            if let Some(types_module) = dedup::emit_units(&tcfg, &build_dir, deferred_units, &crates) {
+                modules.push(types_module);
+            }
+        }
+
         if tcfg.emit_build_files {
             if modules_skipped {
                 // If we skipped a file, we may not have collected all required pragmas
                 warn!("Can't emit build files after incremental transpiler run; skipped.");
+                write_report(&tcfg, &report);
+                if let Some(ref cache) = cache {
+                    cache.print_stats();
+                }
                 return;
             }
 
@@ -334,6 +455,11 @@ pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]
         }
     }
 
+    write_report(&tcfg, &report);
+    if let Some(cache) = cache {
+        cache.print_stats();
+    }
+
     if num_transpiled_files == 0 {
         warn!("No C files found in compile_commands.json; nothing to do.");
         return;
@@ -346,6 +472,14 @@ pub fn transpile(tcfg: TranspilerConfig, cc_db: &Path, extra_clang_args: &[&str]
     }
 }
 
+fn write_report(tcfg: &TranspilerConfig, report: &Report) {
+    if let Some(ref report_path) = tcfg.report {
+        report.write(report_path).unwrap_or_else(|e| {
+            warn!("Unable to write report to {}: {}", report_path.display(), e)
+        });
+    }
+}
+
 /// Ensure that clang can locate the system headers on macOS 10.14+.
 ///
 /// MacOS 10.14 does not have a `/usr/include` folder even if Xcode
@@ -436,14 +570,79 @@ fn reorganize_definitions(
     Ok(())
 }
 
+/// Stack size of the threads translating in parallel; the translator recurses deeply on large
+/// functions, so we give them as much stack as the main thread has by default.
+const TRANSLATION_THREAD_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Translate the given compile commands with up to `tcfg.jobs` threads. The results are in the
+/// order of `cmds`, regardless of the order in which the translations finish, so the pragmas
+/// and crates they require are merged the same way on every run.
+fn transpile_units(
+    tcfg: &TranspilerConfig,
+    cache: Option<&TranslationCache>,
+    cmds: Vec<CompileCmd>,
+    ancestor_path: &Path,
+    build_dir: &Path,
+    cc_db: &Path,
+    extra_clang_args: &[&str],
+) -> Vec<TranspileResult> {
+    let jobs = tcfg.jobs.max(1).min(cmds.len());
+    if jobs <= 1 {
+        return cmds
+            .iter()
+            .map(|cmd| {
+                transpile_single(tcfg, cache, cmd, ancestor_path, build_dir, cc_db, extra_clang_args)
+            })
+            .collect();
+    }
+
+    let next_unit = AtomicUsize::new(0);
+    let results = Mutex::new(cmds.iter().map(|_| None).collect::<Vec<_>>());
+    crossbeam_utils::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope
+                .builder()
+                .stack_size(TRANSLATION_THREAD_STACK_SIZE)
+                .spawn(|_| loop {
+                    let i = next_unit.fetch_add(1, Ordering::SeqCst);
+                    let cmd = match cmds.get(i) {
+                        Some(cmd) => cmd,
+                        None => break,
+                    };
+                    let res = transpile_single(
+                        tcfg,
+                        cache,
+                        cmd,
+                        ancestor_path,
+                        build_dir,
+                        cc_db,
+                        extra_clang_args,
+                    );
+                    results.lock().unwrap()[i] = Some(res);
+                })
+                .expect("Could not spawn translation thread");
+        }
+    })
+    .expect("A translation thread panicked");
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|res| res.expect("Missing translation result"))
+        .collect()
+}
+
 fn transpile_single(
     tcfg: &TranspilerConfig,
-    input_path: PathBuf,
+    cache: Option<&TranslationCache>,
+    cmd: &CompileCmd,
     ancestor_path: &Path,
     build_dir: &Path,
     cc_db: &Path,
     extra_clang_args: &[&str],
 ) -> TranspileResult {
+    let input_path = cmd.abs_file();
     let output_path = get_output_path(tcfg, &input_path, ancestor_path, build_dir);
     if output_path.exists() && !tcfg.overwrite_existing {
         warn!("Skipping existing file {}", output_path.display());
@@ -459,17 +658,115 @@ fn transpile_single(
         return Err(());
     }
 
+    // Export the clang AST under each preprocessor configuration
+    let variant_clang_args = if tcfg.cfg_variants.is_empty() {
+        vec![extra_clang_args.to_vec()]
+    } else {
+        tcfg.cfg_variants
+            .iter()
+            .map(|variant| {
+                let mut clang_args = extra_clang_args.to_vec();
+                clang_args.extend(variant.clang_args.iter().map(String::as_str));
+                clang_args
+            })
+            .collect()
+    };
+    let cbors = variant_clang_args
+        .iter()
+        .map(|clang_args| export_c_file(tcfg, &input_path, cc_db, clang_args))
+        .collect::<Result<Vec<_>, ()>>()?;
+
+    println!("Transpiling {}", file);
+
+    let cache_key = cache.map(|cache| cache.key(tcfg, cmd, extra_clang_args, &cbors));
+    let cached = match (cache, &cache_key) {
+        (Some(cache), Some(key)) => cache.get(key),
+        _ => None,
+    };
+
+    // Perform the translation
+    let translation = match cached {
+        Some(translation) => translation,
+        None => {
+            let translation = if tcfg.cfg_variants.is_empty() {
+                let typed_context = parse_c_file(tcfg, &input_path, &cbors[0])?;
+                translator::translate(typed_context, &tcfg, input_path)
+            } else {
+                let mut variants = vec![];
+                for (variant, cbor) in tcfg.cfg_variants.iter().zip(&cbors) {
+                    let typed_context = parse_c_file(tcfg, &input_path, cbor)?;
+                    variants.push((variant.feature.clone(), typed_context));
+                }
+                translator::translate_cfg_variants(variants, &tcfg, input_path)
+            };
+            if let (Some(cache), Some(key)) = (cache, &cache_key) {
+                cache.insert(key, &translation);
+            }
+            translation
+        }
+    };
+    let (translated_string, pragmas, crates, decls, header_types) = translation;
+
+    // With `--dedup-types`, the file is only written once the types shared by all translation
+    // units are known
+    if tcfg.dedup_types {
+        let deferred = DeferredUnit {
+            output_path: output_path.clone(),
+            translation: translated_string,
+            header_types,
+        };
+        return Ok((output_path, pragmas, crates, decls, Some(deferred)));
+    }
+
+    write_translation(&output_path, &translated_string);
+    Ok((output_path, pragmas, crates, decls, None))
+}
+
+fn write_translation(output_path: &Path, translation: &str) {
+    let mut file = match File::create(output_path) {
+        Ok(file) => file,
+        Err(e) => panic!("Unable to open file {} for writing: {}", output_path.display(), e),
+    };
+
+    match file.write_all(translation.as_bytes()) {
+        Ok(()) => (),
+        Err(e) => panic!("Unable to write translation to file {}: {}", output_path.display(), e),
+    };
+}
+
+/// Run clang on a C file and get its serialized AST
+fn export_c_file(
+    tcfg: &TranspilerConfig,
+    input_path: &Path,
+    cc_db: &Path,
+    extra_clang_args: &[&str],
+) -> Result<Vec<u8>, ()> {
     if tcfg.verbose {
         println!("Additional Clang arguments: {}", extra_clang_args.join(" "));
     }
 
-    // Extract the untyped AST from the CBOR file
-    let untyped_context = match ast_exporter::get_untyped_ast(
-        input_path.as_path(),
+    ast_exporter::get_ast_cbor(
+        input_path,
         cc_db,
         extra_clang_args,
         tcfg.debug_ast_exporter,
-    ) {
+    ).map_err(|e| {
+        warn!(
+            "Error: {}. Skipping {}; is it well-formed C?",
+            e,
+            input_path.display()
+        );
+    })
+}
+
+/// Decode the AST clang exported for a C file and convert it into a typed AST
+fn parse_c_file(
+    tcfg: &TranspilerConfig,
+    input_path: &Path,
+    cbor: &[u8],
+) -> Result<TypedAstContext, ()> {
+    // Extract the untyped AST from the CBOR file
+    let untyped_context = match ast_exporter::parse_ast_cbor(cbor) {
         Err(e) => {
             warn!(
                 "Error: {}. Skipping {}; is it well-formed C?",
@@ -481,8 +778,6 @@ fn transpile_single(
         Ok(cxt) => cxt,
     };
 
-    println!("Transpiling {}", file);
-
     if tcfg.dump_untyped_context {
         println!("CBOR Clang AST");
         println!("{:#?}", untyped_context);
@@ -507,21 +802,7 @@ fn transpile_single(
         println!("{:#?}", Printer::new(io::stdout()).print(&typed_context));
     }
 
-    // Perform the translation
-    let (translated_string, pragmas, crates) =
-        translator::translate(typed_context, &tcfg, input_path);
-
-    let mut file = match File::create(&output_path) {
-        Ok(file) => file,
-        Err(e) => panic!("Unable to open file {} for writing: {}", output_path.display(), e),
-    };
-
-    match file.write_all(translated_string.as_bytes()) {
-        Ok(()) => (),
-        Err(e) => panic!("Unable to write translation to file {}: {}", output_path.display(), e),
-    };
-
-    Ok((output_path, pragmas, crates))
+    Ok(typed_context)
 }
 
 fn get_output_path(
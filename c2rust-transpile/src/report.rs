@@ -0,0 +1,79 @@
+//! Machine-readable report of the outcome of a transpiler run, written as JSON with `--report`.
+
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json;
+
+use crate::diagnostics::TranslationError;
+
+/// Outcome of translating every translation unit in the compilation database
+#[derive(Serialize, Default, Debug)]
+pub struct Report {
+    pub units: Vec<UnitReport>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UnitReport {
+    /// Path of the C source file
+    pub file: PathBuf,
+    /// Whether the file was translated at all; the outcome of each declaration is in `decls`
+    pub outcome: UnitOutcome,
+    pub decls: Vec<DeclReport>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitOutcome {
+    Translated,
+    /// The file could not be parsed or its output already existed
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeclReport {
+    pub name: Option<String>,
+    pub span: Option<String>,
+    pub outcome: DeclOutcome,
+    /// Why the declaration could not be translated as-is
+    pub error: Option<ErrorReport>,
+    /// Unstable Rust features (`#![feature(..)]`) required by the translation
+    pub features: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeclOutcome {
+    Translated,
+    /// The definition failed to translate and was replaced by an `extern` declaration
+    ReplacedWithExtern,
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorReport {
+    /// The `TranslationErrorKind` of the error
+    pub kind: String,
+    pub message: String,
+    /// Innermost source location the error was reported at
+    pub span: Option<String>,
+}
+
+impl<'a> From<&'a TranslationError> for ErrorReport {
+    fn from(e: &'a TranslationError) -> Self {
+        ErrorReport {
+            kind: format!("{:?}", e.kind()),
+            message: e.to_string().lines().next().unwrap_or("").to_string(),
+            span: e.loc().map(|loc| loc.to_string()),
+        }
+    }
+}
+
+impl Report {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! Merging of the items translated from one C file preprocessed under several configurations.
+//! Each configuration is enabled by a Cargo feature of the generated crate.
+
+use c2rust_ast_builder::mk;
+use c2rust_ast_printer::pprust;
+use indexmap::IndexMap;
+use syntax::ast::{AttrStyle, Attribute, ForeignItem, Item, MetaItemKind, NestedMetaItem};
+use syntax::ptr::P;
+
+/// Items of the configurations seen so far, keyed by their printed form, along with the
+/// features of the configurations each one was translated in.
+#[derive(Debug)]
+pub struct CfgMerger {
+    features: Vec<String>,
+    items: IndexMap<String, (P<Item>, Vec<usize>)>,
+    foreign_items: IndexMap<String, (ForeignItem, Vec<usize>)>,
+}
+
+impl CfgMerger {
+    pub fn new() -> Self {
+        CfgMerger {
+            features: Vec::new(),
+            items: IndexMap::new(),
+            foreign_items: IndexMap::new(),
+        }
+    }
+
+    /// Add the items translated under the configuration enabled by `feature`
+    pub fn add_variant(
+        &mut self,
+        feature: &str,
+        items: Vec<P<Item>>,
+        foreign_items: Vec<ForeignItem>,
+    ) {
+        let variant = self.features.len();
+        self.features.push(feature.to_string());
+
+        for item in items {
+            let key = pprust::item_to_string(&item);
+            self.items
+                .entry(key)
+                .or_insert_with(|| (item, vec![]))
+                .1
+                .push(variant);
+        }
+        for foreign_item in foreign_items {
+            let key = pprust::foreign_item_to_string(&foreign_item);
+            self.foreign_items
+                .entry(key)
+                .or_insert_with(|| (foreign_item, vec![]))
+                .1
+                .push(variant);
+        }
+    }
+
+    /// Get the merged items and foreign items. Those which were not translated identically
+    /// in every configuration are only enabled by the features of the configurations that
+    /// produced them.
+    pub fn drain(self) -> (Vec<P<Item>>, Vec<ForeignItem>) {
+        let features = &self.features;
+        let items = self
+            .items
+            .into_iter()
+            .map(|(_, (item, variants))| {
+                item.map(|mut item| {
+                    item.attrs.extend(cfg_attr(features, &variants));
+                    item
+                })
+            })
+            .collect();
+        let foreign_items = self
+            .foreign_items
+            .into_iter()
+            .map(|(_, (mut foreign_item, variants))| {
+                foreign_item.attrs.extend(cfg_attr(features, &variants));
+                foreign_item
+            })
+            .collect();
+        (items, foreign_items)
+    }
+}
+
+/// Build `#[cfg(feature = "a")]` or `#[cfg(any(feature = "a", feature = "b"))]` for the given
+/// configurations, or nothing if they are all of them
+fn cfg_attr(features: &[String], variants: &[usize]) -> Vec<Attribute> {
+    if variants.len() == features.len() {
+        return vec![];
+    }
+
+    let mut predicates: Vec<NestedMetaItem> = variants
+        .iter()
+        .map(|&v| mk().nested_meta_item(mk().meta_item(vec!["feature"], &*features[v])))
+        .collect();
+    let predicate = if predicates.len() == 1 {
+        predicates.pop().unwrap()
+    } else {
+        mk().nested_meta_item(mk().meta_item(vec!["any"], MetaItemKind::List(predicates)))
+    };
+    let cfg = mk().meta_item(vec!["cfg"], MetaItemKind::List(vec![predicate]));
+    mk().meta_item_attr(AttrStyle::Outer, cfg).into_attrs()
+}
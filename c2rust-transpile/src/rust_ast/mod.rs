@@ -1,3 +1,4 @@
+pub mod cfg_merge;
 pub mod comment_store;
 pub mod item_store;
 pub mod traverse;
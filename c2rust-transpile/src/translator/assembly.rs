@@ -2,6 +2,107 @@
 //! This module provides basic support for converting inline assembly statements.
 
 use super::*;
+use crate::convert_type::RESERVED_NAMES;
+use syntax::tokenstream::DelimSpan;
+
+/// The name of the scratch register operand that holds rbx while assembly which clobbers it runs
+const RBX_SCRATCH: &str = "c2rust_rbx";
+
+/// How an operand of an inline assembly statement is passed to `asm!`
+#[derive(Clone, Debug, PartialEq)]
+enum ArgKind {
+    /// Any register of a class, like `reg`
+    Class(&'static str),
+    /// A specific register, like `ax`
+    Register(String),
+    /// A memory operand, whose address is passed in a register
+    Memory,
+    /// An immediate value, passed as a `const` operand
+    Immediate,
+}
+
+/// The constraint of an operand of an inline assembly statement
+#[derive(Clone, Debug)]
+struct Constraint {
+    kind: ArgKind,
+    /// `+`: the output operand is also read
+    read_write: bool,
+    /// `&`: the output operand is written before all the inputs are read
+    early_clobber: bool,
+    /// The output operand this input operand is tied to
+    tied_to: Option<usize>,
+}
+
+impl Constraint {
+    /// Parse the constraint of an operand, as simplified by the AST exporter: specific register
+    /// constraints like `a` are converted into `{ax}`, and a `*` marks operands passed by address.
+    /// Only the first of several alternatives is considered.
+    fn parse(constraints: &str, aarch64: bool) -> Result<Constraint, TranslationError> {
+        let mut read_write = false;
+        let mut early_clobber = false;
+        let mut memory = false;
+        let mut letters = String::new();
+        for c in constraints.split('|').next().unwrap_or("").chars() {
+            match c {
+                '=' | '%' => {}
+                '+' => read_write = true,
+                '&' => early_clobber = true,
+                '*' => memory = true,
+                _ => letters.push(c),
+            }
+        }
+
+        let mut tied_to = None;
+        let has = |cs: &str| letters.chars().any(|c| cs.contains(c));
+        let kind = if memory {
+            ArgKind::Memory
+        } else if letters.starts_with('{') && letters.ends_with('}') {
+            ArgKind::Register(letters[1..letters.len() - 1].to_owned())
+        } else if let Ok(output_idx) = letters.parse::<usize>() {
+            // The kind of a tied operand is the one of its output
+            tied_to = Some(output_idx);
+            ArgKind::Class("reg")
+        } else if aarch64 && has("r") {
+            ArgKind::Class("reg")
+        } else if aarch64 && has("w") {
+            ArgKind::Class("vreg")
+        } else if !aarch64 && has("rRl") {
+            ArgKind::Class("reg")
+        } else if !aarch64 && has("qQ") {
+            ArgKind::Class("reg_abcd")
+        } else if !aarch64 && has("xv") {
+            ArgKind::Class("xmm_reg")
+        } else if has("inIJKLMNOeZ") {
+            ArgKind::Immediate
+        } else {
+            return Err(
+                format_err!("Unsupported inline assembly constraint: {}", constraints).into(),
+            );
+        };
+
+        Ok(Constraint {
+            kind,
+            read_write,
+            early_clobber,
+            tied_to,
+        })
+    }
+}
+
+/// An operand of `asm!`
+struct AsmArg {
+    /// The symbolic name of the C operand
+    name: Option<String>,
+    kind: ArgKind,
+    /// The direction of the operand, like `in` or `lateout`
+    dir: &'static str,
+    /// The input, or the place of the output
+    expr: P<Expr>,
+    /// The place of the output of an `inout` operand, if it isn't its input
+    out_expr: Option<P<Expr>>,
+    /// The C type of the operand
+    ty: CTypeId,
+}
 
 impl<'c> Translation<'c> {
     /// Convert an inline-assembly statement into one or more Rust statements.
@@ -29,8 +130,8 @@ impl<'c> Translation<'c> {
 
         self.use_feature("asm");
 
-        fn push_expr(tokens: &mut Vec<TokenTree>, expr: P<Expr>) {
-            tokens.push(TokenTree::token(token::Interpolated(Rc::new(Nonterminal::NtExpr(expr))), DUMMY_SP));
+        if self.tcfg.new_asm_syntax {
+            return self.convert_new_asm(ctx, span, is_volatile, asm, inputs, outputs, clobbers);
         }
 
         let mut stmts: Vec<Stmt> = vec![];
@@ -65,6 +166,7 @@ impl<'c> Translation<'c> {
             for (operand_idx, &AsmOperand {
                 ref constraints,
                 expression,
+                ..
             }) in list.iter().enumerate()
             {
                 if first {
@@ -194,4 +296,601 @@ impl<'c> Translation<'c> {
 
         Ok(stmts)
     }
+
+    /// Convert an inline-assembly statement into a statement using the `asm!` syntax of newer
+    /// nightlies. The assembly template, which uses the AT&T syntax on x86, is rewritten into
+    /// the format of `asm!`: operands become `{name}` placeholders, their constraints become
+    /// register classes, and clobbered registers become `out("reg") _` operands.
+    fn convert_new_asm(
+        &self,
+        ctx: ExprContext,
+        span: Span,
+        is_volatile: bool,
+        asm: &str,
+        inputs: &[AsmOperand],
+        outputs: &[AsmOperand],
+        clobbers: &[String],
+    ) -> Result<Vec<Stmt>, TranslationError> {
+        let aarch64 = self.targets_aarch64();
+        let parse = |operands: &[AsmOperand]| {
+            operands
+                .iter()
+                .map(|operand| Constraint::parse(&operand.constraints, aarch64))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let output_constraints = parse(outputs)?;
+        let input_constraints = parse(inputs)?;
+
+        // LLVM reserves rbx on x86-64, so `asm!` rejects it as an operand. Assembly that only
+        // clobbers it saves and restores it in a scratch register instead, the way
+        // `core::arch::x86_64::__cpuid` does.
+        let uses_rbx = |constraint: &Constraint| constraint.kind == ArgKind::Register("bx".into());
+        if !aarch64
+            && output_constraints
+                .iter()
+                .chain(&input_constraints)
+                .any(uses_rbx)
+        {
+            return Err(format_err!("rbx can't be an operand of inline assembly").into());
+        }
+        let saves_rbx = !aarch64 && clobbers.iter().any(|clobber| clobber == "bx");
+
+        // Inputs tied to an output are passed along with it, as an `inout` operand
+        let mut tied_inputs = HashMap::new();
+        for (input_idx, constraint) in input_constraints.iter().enumerate() {
+            if let Some(output_idx) = constraint.tied_to {
+                if output_idx >= outputs.len() {
+                    return Err(format_err!("Input tied to missing output {}", output_idx).into());
+                }
+                tied_inputs.insert(output_idx, input_idx);
+            }
+        }
+
+        let mut stmts: Vec<Stmt> = vec![];
+        let mut post_stmts: Vec<Stmt> = vec![];
+        let convert_operand = |stmts: &mut Vec<Stmt>, operand: &AsmOperand| {
+            let mut result = self.convert_expr(ctx.used(), operand.expression)?;
+            stmts.append(result.stmts_mut());
+            Ok::<_, TranslationError>(result.into_value())
+        };
+        let operand_ty = |operand: &AsmOperand| {
+            self.ast_context[operand.expression]
+                .kind
+                .get_type()
+                .ok_or_else(|| format_err!("bad inline assembly operand type"))
+        };
+
+        // The `asm!` operands, and the operand each C operand is passed as, in the order the
+        // template numbers them: outputs first, then inputs
+        let mut args: Vec<AsmArg> = vec![];
+        let mut operand_args: Vec<usize> = vec![];
+
+        for (output_idx, (output, constraint)) in
+            outputs.iter().zip(&output_constraints).enumerate()
+        {
+            let out_expr = convert_operand(&mut stmts, output)?;
+            let ty = operand_ty(output)?;
+            let mut arg = AsmArg {
+                name: output.name.clone(),
+                kind: constraint.kind.clone(),
+                dir: "out",
+                expr: out_expr,
+                out_expr: None,
+                ty,
+            };
+
+            match constraint.kind {
+                ArgKind::Memory => {
+                    // The assembly writes to the memory the address points to
+                    arg.dir = "in";
+                    arg.kind = ArgKind::Memory;
+                    arg.expr = operand_address(arg.expr, true);
+                }
+                ArgKind::Immediate => {
+                    return Err(format_err!("Output operand with an immediate constraint").into());
+                }
+                _ => {
+                    if let Some(&input_idx) = tied_inputs.get(&output_idx) {
+                        let input = &inputs[input_idx];
+                        arg.dir = "inout";
+                        arg.name = arg.name.or_else(|| input.name.clone());
+
+                        let in_ty = operand_ty(input)?;
+                        if self.ast_context.resolve_type(in_ty).kind
+                            == self.ast_context.resolve_type(ty).kind
+                        {
+                            arg.out_expr = Some(arg.expr);
+                            arg.expr = convert_operand(&mut stmts, input)?;
+                        } else {
+                            // If we have an input operand tied to an output operand of a
+                            // different type, we need to replicate clang's behavior: the
+                            // inline assembly uses the larger type internally, and the smaller
+                            // value gets extended to the larger one before the call, and
+                            // truncated back after (if needed). For portability, we moved the
+                            // type conversions into the `c2rust-asm-casts` crate, so we call
+                            // into that one from here.
+                            self.use_crate(ExternCrate::C2RustAsmCasts);
+
+                            // Import the trait into scope
+                            self.with_cur_file_item_store(|item_store| {
+                                item_store.add_use(vec!["c2rust_asm_casts".into()], "AsmCastTrait");
+                            });
+
+                            // `let freshN = &mut x;`
+                            let output_name = self.renamer.borrow_mut().fresh();
+                            let output_local = mk().local(
+                                mk().ident_pat(&output_name),
+                                None as Option<P<Ty>>,
+                                Some(mk().mutbl().addr_of_expr(arg.expr)),
+                            );
+
+                            // `let freshN;`
+                            let inner_name = self.renamer.borrow_mut().fresh();
+                            let inner_local = mk().local(
+                                mk().ident_pat(&inner_name),
+                                None as Option<P<Ty>>,
+                                None as Option<P<Expr>>,
+                            );
+
+                            let input_name = self.renamer.borrow_mut().fresh();
+                            let input_local = mk().local(
+                                mk().ident_pat(&input_name),
+                                None as Option<P<Ty>>,
+                                Some(convert_operand(&mut stmts, input)?),
+                            );
+                            stmts.push(mk().local_stmt(P(output_local)));
+                            stmts.push(mk().local_stmt(P(inner_local)));
+                            stmts.push(mk().local_stmt(P(input_local)));
+
+                            // Pass `c2rust_asm_casts::AsmCast::cast_in(output, input)` in,
+                            // and get `inner` out
+                            let path_expr =
+                                mk().path_expr(vec!["c2rust_asm_casts", "AsmCast", "cast_in"]);
+                            let output = mk().ident_expr(&output_name);
+                            let input = mk().ident_expr(&input_name);
+                            arg.expr =
+                                mk().call_expr(path_expr, vec![output.clone(), input.clone()]);
+                            arg.out_expr = Some(mk().ident_expr(&inner_name));
+
+                            // Append the cast-out call after the assembly macro:
+                            // `c2rust_asm_casts::AsmCast::cast_out(output, input, inner);`
+                            let path_expr =
+                                mk().path_expr(vec!["c2rust_asm_casts", "AsmCast", "cast_out"]);
+                            let inner = mk().ident_expr(&inner_name);
+                            let cast_out = mk().call_expr(path_expr, vec![output, input, inner]);
+                            post_stmts.push(mk().semi_stmt(cast_out));
+                        }
+                    } else if constraint.read_write {
+                        arg.dir = "inout";
+                    } else if !constraint.early_clobber {
+                        // Outputs may share a register with an input unless they are early
+                        // clobbers
+                        arg.dir = "lateout";
+                    }
+                }
+            }
+
+            operand_args.push(args.len());
+            args.push(arg);
+        }
+
+        for (input, constraint) in inputs.iter().zip(&input_constraints) {
+            if let Some(output_idx) = constraint.tied_to {
+                operand_args.push(operand_args[output_idx]);
+                continue;
+            }
+
+            let expr = convert_operand(&mut stmts, input)?;
+            let (dir, expr) = match constraint.kind {
+                ArgKind::Memory => ("in", operand_address(expr, false)),
+                ArgKind::Immediate => ("const", expr),
+                _ => ("in", expr),
+            };
+            operand_args.push(args.len());
+            args.push(AsmArg {
+                name: input.name.clone(),
+                kind: constraint.kind.clone(),
+                dir,
+                expr,
+                out_expr: None,
+                ty: operand_ty(input)?,
+            });
+        }
+
+        // Operands keep their C names, unless they are Rust keywords
+        let rust_name = |arg: &AsmArg| {
+            arg.name
+                .clone()
+                .filter(|name| !RESERVED_NAMES.contains(&name.as_str()))
+        };
+
+        // Positional operands come first, then named ones, then the specific registers, which
+        // can't be named
+        let mut order: Vec<usize> = (0..args.len()).collect();
+        order.sort_by_key(|&idx| match args[idx].kind {
+            ArgKind::Register(_) => 2,
+            _ if rust_name(&args[idx]).is_some() => 1,
+            _ => 0,
+        });
+        let mut placeholders = vec![None; args.len()];
+        for (position, &idx) in order.iter().enumerate() {
+            placeholders[idx] = match args[idx].kind {
+                ArgKind::Register(_) => None,
+                _ => Some(rust_name(&args[idx]).unwrap_or_else(|| position.to_string())),
+            };
+        }
+
+        let template_operands: Vec<(Option<&str>, &ArgKind, CTypeId)> = operand_args
+            .iter()
+            .map(|&idx| {
+                (
+                    placeholders[idx].as_ref().map(String::as_str),
+                    &args[idx].kind,
+                    args[idx].ty,
+                )
+            })
+            .collect();
+        let mut template = self.rewrite_asm_template(asm, &template_operands, aarch64)?;
+        if saves_rbx {
+            template = format!(
+                "movq %rbx, {{{0}:r}}\n\t{1}\n\tmovq {{{0}:r}}, %rbx",
+                RBX_SCRATCH, template
+            );
+        }
+
+        let mut tokens: Vec<TokenTree> = vec![];
+        push_expr(&mut tokens, mk().lit_expr(template));
+
+        // Operands. The scratch register for rbx is named, so it goes before the specific
+        // registers.
+        let push_rbx_scratch = |tokens: &mut Vec<TokenTree>| {
+            tokens.push(TokenTree::token(token::Comma, DUMMY_SP));
+            push_ident(tokens, RBX_SCRATCH);
+            tokens.push(TokenTree::token(token::Eq, DUMMY_SP));
+            push_ident(tokens, "out");
+            push_parens(tokens, vec![ident_token("reg")]);
+            push_ident(tokens, "_");
+        };
+        let mut rbx_scratch_pushed = !saves_rbx;
+        for &idx in &order {
+            let arg = &args[idx];
+            if let ArgKind::Register(_) = arg.kind {
+                if !rbx_scratch_pushed {
+                    push_rbx_scratch(&mut tokens);
+                    rbx_scratch_pushed = true;
+                }
+            }
+            tokens.push(TokenTree::token(token::Comma, DUMMY_SP));
+            if let Some(name) = rust_name(arg).filter(|_| placeholders[idx].is_some()) {
+                push_ident(&mut tokens, &name);
+                tokens.push(TokenTree::token(token::Eq, DUMMY_SP));
+            }
+            push_ident(&mut tokens, arg.dir);
+            match arg.kind {
+                ArgKind::Class(class) => push_parens(&mut tokens, vec![ident_token(class)]),
+                ArgKind::Register(ref reg) => push_parens(&mut tokens, vec![lit_token(reg)]),
+                ArgKind::Memory => push_parens(&mut tokens, vec![ident_token("reg")]),
+                ArgKind::Immediate => {}
+            }
+            push_expr(&mut tokens, arg.expr.clone());
+            if let Some(ref out_expr) = arg.out_expr {
+                tokens.push(TokenTree::token(token::FatArrow, DUMMY_SP));
+                push_expr(&mut tokens, out_expr.clone());
+            }
+        }
+        if !rbx_scratch_pushed {
+            push_rbx_scratch(&mut tokens);
+        }
+
+        // Clobbers. `asm!` assumes that memory and the flags are clobbered unless told otherwise.
+        for clobber in clobbers {
+            let is_operand = args
+                .iter()
+                .any(|arg| arg.kind == ArgKind::Register(clobber.clone()));
+            match clobber.as_str() {
+                "memory" | "cc" | "dirflag" | "fpsr" | "flags" => {}
+                "bx" if saves_rbx => {}
+                _ if is_operand => {}
+                reg => {
+                    tokens.push(TokenTree::token(token::Comma, DUMMY_SP));
+                    push_ident(&mut tokens, "out");
+                    push_parens(&mut tokens, vec![lit_token(reg)]);
+                    push_ident(&mut tokens, "_");
+                }
+            }
+        }
+
+        // Calls from the assembly clobber the registers the C calling convention doesn't preserve
+        let calls = if aarch64 {
+            &["bl", "blr"][..]
+        } else {
+            &["call", "callq", "calll"][..]
+        };
+        if asm
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| calls.contains(&word))
+        {
+            tokens.push(TokenTree::token(token::Comma, DUMMY_SP));
+            push_ident(&mut tokens, "clobber_abi");
+            push_parens(&mut tokens, vec![lit_token("C")]);
+        }
+
+        // Options
+        let mut options = vec![];
+        if !aarch64 {
+            options.push("att_syntax");
+        }
+        // Non-volatile assembly with outputs that doesn't access memory can be removed or
+        // deduplicated
+        let accesses_memory = clobbers.iter().any(|clobber| clobber == "memory")
+            || args.iter().any(|arg| arg.kind == ArgKind::Memory);
+        if !is_volatile && !outputs.is_empty() && !accesses_memory {
+            options.push("pure");
+            options.push("nomem");
+        }
+        if !options.is_empty() {
+            let mut option_tokens = vec![];
+            for option in options {
+                if !option_tokens.is_empty() {
+                    option_tokens.push(TokenTree::token(token::Comma, DUMMY_SP));
+                }
+                option_tokens.push(ident_token(option));
+            }
+            tokens.push(TokenTree::token(token::Comma, DUMMY_SP));
+            push_ident(&mut tokens, "options");
+            push_parens(&mut tokens, option_tokens);
+        }
+
+        let mac = mk().mac(
+            vec!["asm"],
+            tokens.into_iter().collect::<TokenStream>(),
+            MacDelimiter::Parenthesis,
+        );
+        let mac = mk().mac_expr(mac);
+        let mac = mk().span(span).expr_stmt(mac);
+        stmts.push(mac);
+
+        // Push the post-macro statements
+        stmts.extend(post_stmts.into_iter());
+
+        Ok(stmts)
+    }
+
+    /// Rewrite an assembly template into the format of `asm!`. Clang exports templates in the
+    /// format of LLVM, which refers to operands as `$N` or `${N:modifier}`, escapes `$` as `$$`,
+    /// and puts alternatives for several assembly dialects in `$(att$|intel$)`. `operands` are
+    /// the placeholder, kind, and type of each operand, in the order the template numbers them.
+    fn rewrite_asm_template(
+        &self,
+        template: &str,
+        operands: &[(Option<&str>, &ArgKind, CTypeId)],
+        aarch64: bool,
+    ) -> Result<String, TranslationError> {
+        let mut result = String::new();
+        // The index of the dialect alternative we are in; only the first one is kept
+        let mut alternative: Option<usize> = None;
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let keep = alternative.map_or(true, |alternative| alternative == 0);
+            match c {
+                '$' => {
+                    let (operand, modifier) = match chars.next() {
+                        Some('$') => {
+                            if keep {
+                                result.push('$');
+                            }
+                            continue;
+                        }
+                        Some('(') => {
+                            alternative = Some(0);
+                            continue;
+                        }
+                        Some('|') => {
+                            alternative = alternative.map(|alternative| alternative + 1);
+                            continue;
+                        }
+                        Some(')') => {
+                            alternative = None;
+                            continue;
+                        }
+                        Some('{') => {
+                            let reference: String =
+                                chars.by_ref().take_while(|&c| c != '}').collect();
+                            let mut parts = reference.splitn(2, ':');
+                            let operand = parts.next().unwrap_or("").to_owned();
+                            (operand, parts.next().map(str::to_owned))
+                        }
+                        Some(c) if c.is_ascii_digit() => {
+                            let mut operand = c.to_string();
+                            while let Some(&c) = chars.peek() {
+                                if !c.is_ascii_digit() {
+                                    break;
+                                }
+                                operand.push(c);
+                                chars.next();
+                            }
+                            (operand, None)
+                        }
+                        c => {
+                            return Err(format_err!(
+                                "Unexpected escape in assembly template: ${}",
+                                c.map_or(String::new(), |c| c.to_string())
+                            )
+                            .into())
+                        }
+                    };
+
+                    let operand_idx = operand.parse::<usize>().map_err(|_| {
+                        format_err!("Unsupported assembly template operand: {}", operand)
+                    })?;
+                    let &(placeholder, kind, ty) = operands.get(operand_idx).ok_or_else(|| {
+                        format_err!(
+                            "Assembly template refers to missing operand {}",
+                            operand_idx
+                        )
+                    })?;
+                    if keep {
+                        let operand = self.asm_template_operand(
+                            placeholder,
+                            kind,
+                            ty,
+                            modifier.as_ref().map(String::as_str),
+                            aarch64,
+                        )?;
+                        result.push_str(&operand);
+                    }
+                }
+                // `asm!` templates are format strings
+                '{' if keep => result.push_str("{{"),
+                '}' if keep => result.push_str("}}"),
+                _ if keep => result.push(c),
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The reference to an operand in an `asm!` template, for a GCC operand reference with an
+    /// optional modifier
+    fn asm_template_operand(
+        &self,
+        placeholder: Option<&str>,
+        kind: &ArgKind,
+        ty: CTypeId,
+        modifier: Option<&str>,
+        aarch64: bool,
+    ) -> Result<String, TranslationError> {
+        let unsupported = || {
+            format_err!(
+                "Unsupported inline assembly operand modifier: {:?}",
+                modifier
+            )
+        };
+        let placeholder = placeholder.unwrap_or("");
+
+        let operand = match *kind {
+            ArgKind::Register(ref reg) if aarch64 => reg.clone(),
+            ArgKind::Register(ref reg) => {
+                // Specific registers can't be referred to by placeholders, so we name them
+                let size = match modifier {
+                    None => self.asm_operand_size(ty),
+                    Some("b") => 1,
+                    Some("w") => 2,
+                    Some("k") => 4,
+                    Some("q") => 8,
+                    Some("h") if reg.len() == 2 && reg.ends_with('x') => {
+                        return Ok(format!("%{}h", &reg[..1]))
+                    }
+                    _ => return Err(unsupported().into()),
+                };
+                format!("%{}", x86_register_name(reg, size))
+            }
+            ArgKind::Memory if modifier.is_none() => {
+                if aarch64 {
+                    format!("[{{{}}}]", placeholder)
+                } else {
+                    format!("({{{}}})", placeholder)
+                }
+            }
+            ArgKind::Memory => return Err(unsupported().into()),
+            ArgKind::Immediate => match modifier {
+                // AT&T immediates are prefixed by `$`, which the `c` modifier omits
+                None if !aarch64 => format!("${{{}}}", placeholder),
+                None | Some("c") | Some("P") => format!("{{{}}}", placeholder),
+                _ => return Err(unsupported().into()),
+            },
+            ArgKind::Class(_) => {
+                let modifier = match modifier {
+                    None => None,
+                    Some(m) if aarch64 && ["w", "x", "b", "h", "s", "d", "q", "v"].contains(&m) => {
+                        Some(m)
+                    }
+                    Some(_) if aarch64 => return Err(unsupported().into()),
+                    Some("b") => Some("l"),
+                    Some("h") => Some("h"),
+                    Some("w") => Some("x"),
+                    Some("k") => Some("e"),
+                    Some("q") => Some("r"),
+                    Some("x") => Some("x"),
+                    Some("t") => Some("y"),
+                    Some("g") => Some("z"),
+                    _ => return Err(unsupported().into()),
+                };
+                match modifier {
+                    Some(modifier) => format!("{{{}:{}}}", placeholder, modifier),
+                    None => format!("{{{}}}", placeholder),
+                }
+            }
+        };
+
+        Ok(operand)
+    }
+
+    /// The size in bytes of an operand of x86-64 inline assembly
+    fn asm_operand_size(&self, ty: CTypeId) -> usize {
+        match self.ast_context.resolve_type(ty).kind {
+            CTypeKind::Bool | CTypeKind::Char | CTypeKind::SChar | CTypeKind::UChar => 1,
+            CTypeKind::Short | CTypeKind::UShort => 2,
+            CTypeKind::Int | CTypeKind::UInt | CTypeKind::Float => 4,
+            _ => 8,
+        }
+    }
+}
+
+/// The name of the part of size `size` of an x86 general purpose register, like `eax` for `ax`
+fn x86_register_name(reg: &str, size: usize) -> String {
+    let legacy = reg.len() == 2 && !reg.starts_with('r');
+    match size {
+        1 if legacy && reg.ends_with('x') => format!("{}l", &reg[..1]),
+        1 if legacy => format!("{}l", reg),
+        1 => format!("{}b", reg),
+        2 if legacy => reg.to_owned(),
+        2 => format!("{}w", reg),
+        4 if legacy => format!("e{}", reg),
+        4 => format!("{}d", reg),
+        _ if legacy => format!("r{}", reg),
+        _ => reg.to_owned(),
+    }
+}
+
+/// The address of a memory operand, which `asm!` takes as a raw pointer
+fn operand_address(expr: P<Expr>, mutable: bool) -> P<Expr> {
+    if mutable {
+        let ptr_ty = mk().mutbl().ptr_ty(mk().infer_ty());
+        mk().cast_expr(mk().mutbl().addr_of_expr(expr), ptr_ty)
+    } else {
+        mk().cast_expr(mk().addr_of_expr(expr), mk().ptr_ty(mk().infer_ty()))
+    }
+}
+
+fn push_expr(tokens: &mut Vec<TokenTree>, expr: P<Expr>) {
+    tokens.push(TokenTree::token(
+        token::Interpolated(Rc::new(Nonterminal::NtExpr(expr))),
+        DUMMY_SP,
+    ));
+}
+
+fn ident_token(name: &str) -> TokenTree {
+    TokenTree::token(token::Ident(name.into_symbol(), false), DUMMY_SP)
+}
+
+fn lit_token(s: &str) -> TokenTree {
+    TokenTree::token(
+        token::Interpolated(Rc::new(Nonterminal::NtExpr(mk().lit_expr(s)))),
+        DUMMY_SP,
+    )
+}
+
+fn push_ident(tokens: &mut Vec<TokenTree>, name: &str) {
+    tokens.push(ident_token(name));
+}
+
+fn push_parens(tokens: &mut Vec<TokenTree>, inner: Vec<TokenTree>) {
+    tokens.push(TokenTree::Delimited(
+        DelimSpan::dummy(),
+        DelimToken::Paren,
+        inner.into_iter().collect::<TokenStream>(),
+    ));
 }
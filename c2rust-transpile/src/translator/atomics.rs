@@ -1,6 +1,42 @@
 use std::sync::atomic::Ordering;
 use super::*;
 
+/// Memory ordering argument of an atomic builtin. This is usually one of the constant
+/// `__ATOMIC_*` values, but it can also be computed at runtime.
+pub(crate) enum MemOrdering {
+    Static(Ordering),
+    Dynamic(P<Expr>),
+}
+
+impl MemOrdering {
+    fn is_dynamic(&self) -> bool {
+        match *self {
+            MemOrdering::Static(_) => false,
+            MemOrdering::Dynamic(_) => true,
+        }
+    }
+}
+
+/// Pick the `atomic_cxchg*` intrinsic for a compare-exchange with the given strength and
+/// success/failure orderings, if that combination is allowed
+fn cxchg_intrinsic(weak: bool, order: Ordering, order_fail: Ordering) -> Option<String> {
+    let suffix = match (order, order_fail) {
+        (_, Ordering::Release) | (_, Ordering::AcqRel) => return None,
+        (Ordering::SeqCst, Ordering::SeqCst) => "",
+        (Ordering::SeqCst, Ordering::Acquire) => "_failacq",
+        (Ordering::SeqCst, Ordering::Relaxed) => "_failrelaxed",
+        (Ordering::AcqRel, Ordering::Acquire) => "_acqrel",
+        (Ordering::AcqRel, Ordering::Relaxed) => "_acqrel_failrelaxed",
+        (Ordering::Release, Ordering::Relaxed) => "_rel",
+        (Ordering::Acquire, Ordering::Acquire) => "_acq",
+        (Ordering::Acquire, Ordering::Relaxed) => "_acq_failrelaxed",
+        (Ordering::Relaxed, Ordering::Relaxed) => "_relaxed",
+        _ => return None,
+    };
+    let base = if weak { "atomic_cxchgweak" } else { "atomic_cxchg" };
+    Some(format!("{}{}", base, suffix))
+}
+
 impl<'c> Translation<'c> {
     fn convert_constant_bool(&self, expr: CExprId) -> Option<bool> {
         let val = self.ast_context.resolve_expr_value(expr);
@@ -29,6 +65,72 @@ impl<'c> Translation<'c> {
         }
     }
 
+    /// Convert a memory ordering argument, keeping the runtime value around when it is not a
+    /// compile-time constant
+    fn convert_memordering_arg(
+        &self,
+        ctx: ExprContext,
+        expr: CExprId,
+    ) -> Result<MemOrdering, TranslationError> {
+        match self.convert_memordering(expr) {
+            Some(order) => Ok(MemOrdering::Static(order)),
+            None => Ok(MemOrdering::Dynamic(self.convert_expr(ctx.used(), expr)?.to_expr())),
+        }
+    }
+
+    /// Build `f(ordering)` for a memory ordering argument. A constant ordering builds a single
+    /// expression, while one only known at runtime becomes a `match` on its `__ATOMIC_*` value
+    /// with an arm for each ordering.
+    fn dispatch_memordering<F>(
+        &self,
+        order: &MemOrdering,
+        mut f: F,
+    ) -> Result<P<Expr>, TranslationError>
+    where
+        F: FnMut(Ordering) -> Result<P<Expr>, TranslationError>,
+    {
+        let order = match *order {
+            MemOrdering::Static(order) => return f(order),
+            MemOrdering::Dynamic(ref order) => order.clone(),
+        };
+
+        let int_pat = |i| mk().lit_pat(mk().lit_expr(mk().int_lit(i, LitIntType::Unsuffixed)));
+        let arms = vec![
+            (int_pat(0), Ordering::Relaxed),
+            (mk().or_pat(vec![int_pat(1), int_pat(2)]), Ordering::Acquire),
+            (int_pat(3), Ordering::Release),
+            (int_pat(4), Ordering::AcqRel),
+            // Out of range values are treated as __ATOMIC_SEQ_CST, as in GCC
+            (mk().wild_pat(), Ordering::SeqCst),
+        ];
+        let arms = arms
+            .into_iter()
+            .map(|(pat, order)| Ok(mk().arm(pat, None as Option<P<Expr>>, f(order)?)))
+            .collect::<Result<Vec<_>, TranslationError>>()?;
+        Ok(mk().match_expr(order, arms))
+    }
+
+    /// Orderings that an operation does not allow are rejected when they are constant. When they
+    /// are only known at runtime, they fall back to sequential consistency like they do in GCC.
+    fn check_memordering<T>(
+        &self,
+        intrinsic: Option<T>,
+        seq_cst: T,
+        dynamic: bool,
+        order_id: CExprId,
+        builtin: &str,
+    ) -> Result<T, TranslationError> {
+        match intrinsic {
+            Some(intrinsic) => Ok(intrinsic),
+            None if dynamic => Ok(seq_cst),
+            None => Err(format_translation_err!(
+                self.ast_context.display_loc(&self.ast_context[order_id].loc),
+                "Invalid memory ordering for {}",
+                builtin,
+            )),
+        }
+    }
+
     pub fn convert_atomic(
         &self,
         ctx: ExprContext,
@@ -42,33 +144,37 @@ impl<'c> Translation<'c> {
     ) -> Result<WithStmts<P<Expr>>, TranslationError> {
         let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
         let ptr = self.convert_expr(ctx.used(), ptr_id)?;
-        let order = self.convert_memordering(order_id);
+        let order = self.convert_memordering_arg(ctx, order_id)?;
         let val1 = val1_id.map(|x| self.convert_expr(ctx.used(), x)).transpose()?;
-        let order_fail = order_fail_id.and_then(|x| self.convert_memordering(x));
         let val2 = val2_id.map(|x| self.convert_expr(ctx.used(), x)).transpose()?;
-        let weak = weak_id.and_then(|x| self.convert_constant_bool(x));
+        let dynamic = order.is_dynamic();
 
         match name {
             "__atomic_load" | "__atomic_load_n" => {
                 ptr.and_then(|ptr| {
-                    let intrinsic_name = match order {
-                        None => unimplemented!("Dynamic memory consistency arguments are not yet supported"),
-                        Some(Ordering::SeqCst) => Some("atomic_load"),
-                        Some(Ordering::AcqRel) => None,
-                        Some(Ordering::Acquire) => Some("atomic_load_acq"),
-                        Some(Ordering::Release) => None,
-                        Some(Ordering::Relaxed) => Some("atomic_load_relaxed"),
-                        Some(_) => unreachable!("Did we not handle a case above??"),
-                    }.ok_or_else(|| format_translation_err!(
-                        self.ast_context.display_loc(&self.ast_context[order_id].loc),
-                        "Invalid memory ordering for __atomic_load",
-                    ))?;
-
                     self.use_feature("core_intrinsics");
 
-                    let atomic_load =
-                        mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name]);
-                    let call = mk().call_expr(atomic_load, vec![ptr]);
+                    let call = self.dispatch_memordering(&order, |order| {
+                        let intrinsic_name = match order {
+                            Ordering::SeqCst => Some("atomic_load"),
+                            Ordering::AcqRel => None,
+                            Ordering::Acquire => Some("atomic_load_acq"),
+                            Ordering::Release => None,
+                            Ordering::Relaxed => Some("atomic_load_relaxed"),
+                            _ => unreachable!("Did we not handle a case above??"),
+                        };
+                        let intrinsic_name = self.check_memordering(
+                            intrinsic_name,
+                            "atomic_load",
+                            dynamic,
+                            order_id,
+                            "__atomic_load",
+                        )?;
+
+                        let atomic_load =
+                            mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name]);
+                        Ok(mk().call_expr(atomic_load, vec![ptr.clone()]))
+                    })?;
                     if name == "__atomic_load" {
                         let ret = val1.expect("__atomic_load should have a ret argument");
                         ret.and_then(|ret| {
@@ -96,29 +202,34 @@ impl<'c> Translation<'c> {
                 let val = val1.expect("__atomic_store must have a val argument");
                 ptr.and_then(|ptr| {
                     val.and_then(|val| {
-                        let intrinsic_name = match order {
-                            None => unimplemented!("Dynamic memory consistency arguments are not yet supported"),
-                            Some(Ordering::SeqCst) => Some("atomic_store"),
-                            Some(Ordering::AcqRel) => None,
-                            Some(Ordering::Acquire) => None,
-                            Some(Ordering::Release) => Some("atomic_store_rel"),
-                            Some(Ordering::Relaxed) => Some("atomic_store_relaxed"),
-                            Some(_) => unreachable!("Did we not handle a case above??"),
-                        }.ok_or_else(|| format_translation_err!(
-                            self.ast_context.display_loc(&self.ast_context[order_id].loc),
-                            "Invalid memory ordering for __atomic_store",
-                        ))?;
-
                         self.use_feature("core_intrinsics");
 
-                        let atomic_store =
-                            mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name]);
                         let val = if name == "__atomic_store" {
                             mk().unary_expr(ast::UnOp::Deref, val)
                         } else {
                             val
                         };
-                        let call = mk().call_expr(atomic_store, vec![ptr, val]);
+                        let call = self.dispatch_memordering(&order, |order| {
+                            let intrinsic_name = match order {
+                                Ordering::SeqCst => Some("atomic_store"),
+                                Ordering::AcqRel => None,
+                                Ordering::Acquire => None,
+                                Ordering::Release => Some("atomic_store_rel"),
+                                Ordering::Relaxed => Some("atomic_store_relaxed"),
+                                _ => unreachable!("Did we not handle a case above??"),
+                            };
+                            let intrinsic_name = self.check_memordering(
+                                intrinsic_name,
+                                "atomic_store",
+                                dynamic,
+                                order_id,
+                                "__atomic_store",
+                            )?;
+
+                            let atomic_store =
+                                mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name]);
+                            Ok(mk().call_expr(atomic_store, vec![ptr.clone(), val.clone()]))
+                        })?;
                         self.convert_side_effects_expr(
                             ctx,
                             WithStmts::new_val(call),
@@ -132,29 +243,34 @@ impl<'c> Translation<'c> {
                 let val = val1.expect("__atomic_store must have a val argument");
                 ptr.and_then(|ptr| {
                     val.and_then(|val| {
-                        let intrinsic_name = match order {
-                            None => unimplemented!("Dynamic memory consistency arguments are not yet supported"),
-                            Some(Ordering::SeqCst) => Some("atomic_xchg"),
-                            Some(Ordering::AcqRel) => Some("atomic_xchg_acqrel"),
-                            Some(Ordering::Acquire) => Some("atomic_xchg_acq"),
-                            Some(Ordering::Release) => Some("atomic_xchg_rel"),
-                            Some(Ordering::Relaxed) => Some("atomic_xchg_relaxed"),
-                            Some(_) => unreachable!("Did we not handle a case above??"),
-                        }.ok_or_else(|| format_translation_err!(
-                            self.ast_context.display_loc(&self.ast_context[order_id].loc),
-                            "Invalid memory ordering for __atomic_exchange",
-                        ))?;
-
                         self.use_feature("core_intrinsics");
 
-                        let fn_path =
-                            mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name]);
                         let val = if name == "__atomic_exchange" {
                             mk().unary_expr(ast::UnOp::Deref, val)
                         } else {
                             val
                         };
-                        let call = mk().call_expr(fn_path, vec![ptr, val]);
+                        let call = self.dispatch_memordering(&order, |order| {
+                            let intrinsic_name = match order {
+                                Ordering::SeqCst => Some("atomic_xchg"),
+                                Ordering::AcqRel => Some("atomic_xchg_acqrel"),
+                                Ordering::Acquire => Some("atomic_xchg_acq"),
+                                Ordering::Release => Some("atomic_xchg_rel"),
+                                Ordering::Relaxed => Some("atomic_xchg_relaxed"),
+                                _ => unreachable!("Did we not handle a case above??"),
+                            };
+                            let intrinsic_name = self.check_memordering(
+                                intrinsic_name,
+                                "atomic_xchg",
+                                dynamic,
+                                order_id,
+                                "__atomic_exchange",
+                            )?;
+
+                            let fn_path =
+                                mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name]);
+                            Ok(mk().call_expr(fn_path, vec![ptr.clone(), val.clone()]))
+                        })?;
                         if name == "__atomic_exchange" {
                             // LLVM stores the ret pointer in the order_fail slot
                             order_fail_id
@@ -186,81 +302,20 @@ impl<'c> Translation<'c> {
             "__atomic_compare_exchange" | "__atomic_compare_exchange_n" => {
                 let expected = val1.expect("__atomic_compare_exchange must have a expected argument");
                 let desired = val2.expect("__atomic_compare_exchange must have a desired argument");
+                let order_fail_id =
+                    order_fail_id.expect("__atomic_compare_exchange must have a failure ordering");
+                let weak_id = weak_id.expect("__atomic_compare_exchange must have a weak argument");
+                let order_fail = self.convert_memordering_arg(ctx, order_fail_id)?;
+                let weak = self.convert_constant_bool(weak_id);
+                let weak_cond = match weak {
+                    Some(_) => None,
+                    None => Some(self.convert_condition(ctx, true, weak_id)?.to_expr()),
+                };
+                let dynamic = dynamic || order_fail.is_dynamic() || weak.is_none();
+
                 ptr.and_then(|ptr| {
                     expected.and_then(|expected| {
                         desired.and_then(|desired| {
-                            let intrinsic_name = match (weak, order, order_fail) {
-                                (None, _, _) | (_, None, _) | (_, _, None) => {
-                                    // We have to select which intrinsic to use at runtime
-                                    unimplemented!("Dynamic memory consistency arguments are not yet supported");
-                                }
-                                (_, _, Some(Ordering::Release)) | (_, _, Some(Ordering::AcqRel)) =>
-                                    None,
-
-                                (Some(false), Some(Ordering::SeqCst), Some(Ordering::SeqCst)) =>
-                                    Some("atomic_cxchg"),
-                                (Some(false), Some(Ordering::SeqCst), Some(Ordering::Acquire)) =>
-                                    Some("atomic_cxchg_failacq"),
-                                (Some(false), Some(Ordering::SeqCst), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchg_failrelaxed"),
-                                (Some(false), Some(Ordering::SeqCst), Some(_)) =>
-                                    None,
-                                (Some(false), Some(Ordering::AcqRel), Some(Ordering::Acquire)) =>
-                                    Some("atomic_cxchg_acqrel"),
-                                (Some(false), Some(Ordering::AcqRel), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchg_acqrel_failrelaxed"),
-                                (Some(false), Some(Ordering::AcqRel), Some(_)) =>
-                                    None,
-                                (Some(false), Some(Ordering::Release), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchg_rel"),
-                                (Some(false), Some(Ordering::Release), Some(_)) =>
-                                    None,
-                                (Some(false), Some(Ordering::Acquire), Some(Ordering::Acquire)) =>
-                                    Some("atomic_cxchg_acq"),
-                                (Some(false), Some(Ordering::Acquire), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchg_acq_failrelaxed"),
-                                (Some(false), Some(Ordering::Acquire), Some(_)) =>
-                                    None,
-                                (Some(false), Some(Ordering::Relaxed), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchg_relaxed"),
-                                (Some(false), Some(Ordering::Relaxed), Some(_)) =>
-                                    None,
-
-                                (Some(true), Some(Ordering::SeqCst), Some(Ordering::SeqCst)) =>
-                                    Some("atomic_cxchgweak"),
-                                (Some(true), Some(Ordering::SeqCst), Some(Ordering::Acquire)) =>
-                                    Some("atomic_cxchgweak_failacq"),
-                                (Some(true), Some(Ordering::SeqCst), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchgweak_failrelaxed"),
-                                (Some(true), Some(Ordering::SeqCst), Some(_)) =>
-                                    None,
-                                (Some(true), Some(Ordering::AcqRel), Some(Ordering::Acquire)) =>
-                                    Some("atomic_cxchgweak_acqrel"),
-                                (Some(true), Some(Ordering::AcqRel), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchgweak_acqrel_failrelaxed"),
-                                (Some(true), Some(Ordering::AcqRel), Some(_)) =>
-                                    None,
-                                (Some(true), Some(Ordering::Release), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchgweak_rel"),
-                                (Some(true), Some(Ordering::Release), Some(_)) =>
-                                    None,
-                                (Some(true), Some(Ordering::Acquire), Some(Ordering::Acquire)) =>
-                                    Some("atomic_cxchgweak_acq"),
-                                (Some(true), Some(Ordering::Acquire), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchgweak_acq_failrelaxed"),
-                                (Some(true), Some(Ordering::Acquire), Some(_)) =>
-                                    None,
-                                (Some(true), Some(Ordering::Relaxed), Some(Ordering::Relaxed)) =>
-                                    Some("atomic_cxchgweak_relaxed"),
-                                (Some(true), Some(Ordering::Relaxed), Some(_)) =>
-                                    None,
-
-                                (Some(_), Some(_), Some(_)) => unreachable!("Did we not handle a case above??"),
-                            }.ok_or_else(|| format_translation_err!(
-                                self.ast_context.display_loc(&self.ast_context[order_fail_id.unwrap()].loc),
-                                "Invalid failure memory ordering",
-                            ))?;
-
                             self.use_feature("core_intrinsics");
                             let expected = mk().unary_expr(ast::UnOp::Deref, expected);
                             let desired = if name == "__atomic_compare_exchange_n" {
@@ -269,9 +324,39 @@ impl<'c> Translation<'c> {
                                 mk().unary_expr(ast::UnOp::Deref, desired)
                             };
 
-                            let atomic_cxchg =
-                                mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name]);
-                            let call = mk().call_expr(atomic_cxchg, vec![ptr, expected.clone(), desired]);
+                            let cxchg_call = |weak: bool| {
+                                self.dispatch_memordering(&order, |order| {
+                                    self.dispatch_memordering(&order_fail, |order_fail| {
+                                        let seq_cst = cxchg_intrinsic(weak, Ordering::SeqCst, Ordering::SeqCst)
+                                            .expect("SeqCst is always a valid ordering");
+                                        let intrinsic_name = self.check_memordering(
+                                            cxchg_intrinsic(weak, order, order_fail),
+                                            seq_cst,
+                                            dynamic,
+                                            order_fail_id,
+                                            "__atomic_compare_exchange",
+                                        )?;
+
+                                        let atomic_cxchg = mk().path_expr(
+                                            vec!["", std_or_core, "intrinsics", intrinsic_name.as_str()],
+                                        );
+                                        Ok(mk().call_expr(
+                                            atomic_cxchg,
+                                            vec![ptr.clone(), expected.clone(), desired.clone()],
+                                        ))
+                                    })
+                                })
+                            };
+                            let call = match (weak, weak_cond) {
+                                (Some(weak), _) => cxchg_call(weak)?,
+                                (None, Some(cond)) => mk().ifte_expr(
+                                    cond,
+                                    mk().block(vec![mk().expr_stmt(cxchg_call(true)?)]),
+                                    Some(cxchg_call(false)?),
+                                ),
+                                (None, None) => unreachable!("Missing weak condition"),
+                            };
+
                             let res_name = self.renamer.borrow_mut().fresh();
                             let res_let = mk().local_stmt(P(mk().local(
                                 mk().ident_pat(&res_name),
@@ -319,27 +404,14 @@ impl<'c> Translation<'c> {
                     "atomic_and"
                 };
 
-                let intrinsic_name = match order {
-                    None => {
-                        unimplemented!("Dynamic memory consistency arguments are not yet supported");
-                    }
-
-                    Some(Ordering::SeqCst) => format!("{}", intrinsic_name),
-                    Some(Ordering::AcqRel) => format!("{}_acqrel", intrinsic_name),
-                    Some(Ordering::Acquire) => format!("{}_acq", intrinsic_name),
-                    Some(Ordering::Release) => format!("{}_rel", intrinsic_name),
-                    Some(Ordering::Relaxed) => format!("{}_relaxed", intrinsic_name),
-
-                    _ => unreachable!("Unknown memory ordering"),
-                };
-
                 let fetch_first = name.starts_with("__atomic_fetch");
                 let val = val1.expect("__atomic arithmetic operations must have a val argument");
                 ptr.and_then(|ptr| {
                     val.and_then(|val| {
                         self.convert_atomic_op(
                             ctx,
-                            &intrinsic_name,
+                            intrinsic_name,
+                            &order,
                             ptr,
                             val,
                             fetch_first,
@@ -381,6 +453,7 @@ impl<'c> Translation<'c> {
         &self,
         ctx: ExprContext,
         func_name: &str,
+        order: &MemOrdering,
         dst: P<Expr>,
         src: P<Expr>,
         fetch_first: bool,
@@ -388,11 +461,26 @@ impl<'c> Translation<'c> {
         self.use_feature("core_intrinsics");
         let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
 
-        // Emit `atomic_func(a0, a1) (op a1)?`
-        let atomic_func = mk().path_expr(vec!["", std_or_core, "intrinsics", func_name]);
+        // Emit `atomic_func(a0, a1) (op a1)?`, where `atomic_func` is the variant of `func_name`
+        // for the requested memory ordering
+        let atomic_call = |args: Vec<P<Expr>>| {
+            self.dispatch_memordering(order, |order| {
+                let intrinsic_name = match order {
+                    Ordering::SeqCst => format!("{}", func_name),
+                    Ordering::AcqRel => format!("{}_acqrel", func_name),
+                    Ordering::Acquire => format!("{}_acq", func_name),
+                    Ordering::Release => format!("{}_rel", func_name),
+                    Ordering::Relaxed => format!("{}_relaxed", func_name),
+                    _ => unreachable!("Unknown memory ordering"),
+                };
+                let atomic_func =
+                    mk().path_expr(vec!["", std_or_core, "intrinsics", intrinsic_name.as_str()]);
+                Ok(mk().call_expr(atomic_func, args.clone()))
+            })
+        };
 
         if fetch_first {
-            let call_expr = mk().call_expr(atomic_func, vec![dst, src]);
+            let call_expr = atomic_call(vec![dst, src])?;
             self.convert_side_effects_expr(
                 ctx,
                 WithStmts::new_val(call_expr),
@@ -432,10 +520,7 @@ impl<'c> Translation<'c> {
                 Some(src),
             )));
 
-            let call = mk().call_expr(
-                atomic_func,
-                vec![mk().ident_expr(&arg0_name), mk().ident_expr(&arg1_name)],
-            );
+            let call = atomic_call(vec![mk().ident_expr(&arg0_name), mk().ident_expr(&arg1_name)])?;
             let val = mk().binary_expr(binary_op, call, mk().ident_expr(arg1_name));
             let val = if is_nand {
                 // For nand, return `!(atomic_nand(arg0, arg1) & arg1)`
@@ -454,4 +539,270 @@ impl<'c> Translation<'c> {
         }
     }
 
+    /// `Ordering::SeqCst`, the ordering C11 uses for plain accesses to `_Atomic` objects
+    fn atomic_seq_cst(&self) -> P<Expr> {
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+        mk().path_expr(vec!["", std_or_core, "sync", "atomic", "Ordering", "SeqCst"])
+    }
+
+    /// `AtomicPtr<T>` only stores `*mut T`, so atomic pointers to const need extra casts
+    fn is_atomic_const_ptr(&self, value_ty: CQualTypeId) -> bool {
+        match self.ast_context.resolve_type(value_ty.ctype).kind {
+            CTypeKind::Pointer(pointee) => pointee.qualifiers.is_const,
+            _ => false,
+        }
+    }
+
+    fn atomic_value_to_repr(&self, value_ty: CQualTypeId, val: P<Expr>) -> P<Expr> {
+        if self.is_atomic_const_ptr(value_ty) {
+            mk().cast_expr(val, mk().mutbl().ptr_ty(mk().infer_ty()))
+        } else {
+            val
+        }
+    }
+
+    fn atomic_value_from_repr(
+        &self,
+        value_ty: CQualTypeId,
+        val: P<Expr>,
+    ) -> Result<P<Expr>, TranslationError> {
+        if self.is_atomic_const_ptr(value_ty) {
+            Ok(mk().cast_expr(val, self.convert_type(value_ty.ctype)?))
+        } else {
+            Ok(val)
+        }
+    }
+
+    /// Read the value out of an `_Atomic` object
+    pub(crate) fn convert_atomic_load(
+        &self,
+        value_ty: CQualTypeId,
+        atomic: P<Expr>,
+    ) -> Result<P<Expr>, TranslationError> {
+        let load = mk().method_call_expr(atomic, "load", vec![self.atomic_seq_cst()]);
+        self.atomic_value_from_repr(value_ty, load)
+    }
+
+    /// Build a new `_Atomic` object holding the given value. The `new` constructors are `const
+    /// fn`, so this also works in static initializers.
+    pub(crate) fn convert_atomic_new(
+        &self,
+        value_ty: CQualTypeId,
+        val: P<Expr>,
+    ) -> Result<P<Expr>, TranslationError> {
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+        let name = TypeConverter::atomic_type_name(&self.ast_context, value_ty)?;
+        let new = mk().path_expr(vec!["", std_or_core, "sync", "atomic", name, "new"]);
+        Ok(mk().call_expr(new, vec![self.atomic_value_to_repr(value_ty, val)]))
+    }
+
+    /// Translate an assignment, compound assignment, or increment/decrement whose target has
+    /// `_Atomic` type. Plain assignments become a `store`, integer arithmetic and bitwise updates
+    /// use the matching `fetch_*` method, and anything else falls back to a `compare_exchange`
+    /// loop. When `fetch_first` is set the expression evaluates to the old value rather than the
+    /// new one.
+    pub(crate) fn convert_atomic_update(
+        &self,
+        ctx: ExprContext,
+        op: c_ast::BinOp,
+        lhs: CExprId,
+        value_ty: CQualTypeId,
+        rhs_ty: CQualTypeId,
+        rhs_translation: WithStmts<P<Expr>>,
+        compute_lhs_ty: Option<CQualTypeId>,
+        compute_res_ty: Option<CQualTypeId>,
+        fetch_first: bool,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let value_kind = &self.ast_context.resolve_type(value_ty.ctype).kind;
+        let value_rust_ty = self.convert_type(value_ty.ctype)?;
+
+        // Compound assignments to atomics may report the atomic type itself as the computation
+        // type; the arithmetic always happens on the underlying value type.
+        let non_atomic = |ty: Option<CQualTypeId>| match ty {
+            Some(ty) => match self.ast_context.resolve_type(ty.ctype).kind {
+                CTypeKind::Atomic(_) => value_ty,
+                _ => ty,
+            },
+            None => value_ty,
+        };
+        let compute_lhs_ty = non_atomic(compute_lhs_ty);
+        let compute_res_ty = non_atomic(compute_res_ty);
+
+        let fetch_method = match op {
+            _ if !value_kind.is_integral_type() || *value_kind == CTypeKind::Bool => None,
+            c_ast::BinOp::AssignAdd => Some(("fetch_add", BinOpKind::Add)),
+            c_ast::BinOp::AssignSubtract => Some(("fetch_sub", BinOpKind::Sub)),
+            c_ast::BinOp::AssignBitAnd => Some(("fetch_and", BinOpKind::BitAnd)),
+            c_ast::BinOp::AssignBitOr => Some(("fetch_or", BinOpKind::BitOr)),
+            c_ast::BinOp::AssignBitXor => Some(("fetch_xor", BinOpKind::BitXor)),
+            _ => None,
+        };
+
+        let place = self.name_reference_write(ctx, lhs)?;
+        rhs_translation.and_then(|rhs| place.and_then(|place| {
+            let rhs_name = self.renamer.borrow_mut().fresh();
+            let mut stmts = vec![];
+
+            let val = match (op, fetch_method) {
+                (c_ast::BinOp::Assign, _) => {
+                    if ctx.is_unused() {
+                        let store = mk().method_call_expr(
+                            place,
+                            "store",
+                            vec![self.atomic_value_to_repr(value_ty, rhs), self.atomic_seq_cst()],
+                        );
+                        return Ok(WithStmts::new(
+                            vec![mk().semi_stmt(store)],
+                            self.panic_or_err("Assignment is not supposed to be used"),
+                        ));
+                    }
+
+                    // let rhs = ...; place.store(rhs, SeqCst); rhs
+                    stmts.push(mk().local_stmt(P(mk().local(
+                        mk().ident_pat(&rhs_name),
+                        None as Option<P<Ty>>,
+                        Some(rhs),
+                    ))));
+                    let store = mk().method_call_expr(
+                        place,
+                        "store",
+                        vec![
+                            self.atomic_value_to_repr(value_ty, mk().ident_expr(&rhs_name)),
+                            self.atomic_seq_cst(),
+                        ],
+                    );
+                    stmts.push(mk().semi_stmt(store));
+                    mk().ident_expr(&rhs_name)
+                }
+
+                (_, Some((fetch, combine))) => {
+                    // place.fetch_op(rhs as T, SeqCst) (op rhs)?
+                    stmts.push(mk().local_stmt(P(mk().local(
+                        mk().ident_pat(&rhs_name),
+                        None as Option<P<Ty>>,
+                        Some(mk().cast_expr(rhs, value_rust_ty)),
+                    ))));
+                    let old = mk().method_call_expr(
+                        place,
+                        fetch,
+                        vec![mk().ident_expr(&rhs_name), self.atomic_seq_cst()],
+                    );
+                    let rhs_val = mk().ident_expr(&rhs_name);
+                    match combine {
+                        _ if fetch_first || ctx.is_unused() => old,
+                        BinOpKind::Add => mk().method_call_expr(old, "wrapping_add", vec![rhs_val]),
+                        BinOpKind::Sub => mk().method_call_expr(old, "wrapping_sub", vec![rhs_val]),
+                        op => mk().binary_expr(op, old, rhs_val),
+                    }
+                }
+
+                (op, None) => {
+                    let op = op
+                        .underlying_assignment()
+                        .ok_or_else(|| format_err!("Cannot convert non-assignment operator"))?;
+                    let old_name = self.renamer.borrow_mut().fresh();
+                    let new_name = self.renamer.borrow_mut().fresh();
+                    let current_name = self.renamer.borrow_mut().fresh();
+                    let old = mk().ident_expr(&old_name);
+                    let rhs_val = mk().ident_expr(&rhs_name);
+
+                    stmts.push(mk().local_stmt(P(mk().local(
+                        mk().ident_pat(&rhs_name),
+                        None as Option<P<Ty>>,
+                        Some(rhs),
+                    ))));
+                    stmts.push(mk().local_stmt(P(mk().local(
+                        mk().mutbl().ident_pat(&old_name),
+                        None as Option<P<Ty>>,
+                        Some(self.convert_atomic_load(value_ty, place.clone())?),
+                    ))));
+
+                    // Compute the updated value the same way a non-atomic update would
+                    let new_val = match self.ast_context.resolve_type(value_ty.ctype).kind {
+                        CTypeKind::Pointer(pointee)
+                            if op == c_ast::BinOp::Add || op == c_ast::BinOp::Subtract =>
+                        {
+                            let mul = self.compute_size_of_expr(pointee.ctype);
+                            pointer_offset(old.clone(), rhs_val, mul, op == c_ast::BinOp::Subtract, false)
+                        }
+                        _ if compute_lhs_ty.ctype == value_ty.ctype => self.convert_binary_operator(
+                            ctx,
+                            op,
+                            value_rust_ty.clone(),
+                            value_ty.ctype,
+                            value_ty,
+                            rhs_ty,
+                            old.clone(),
+                            rhs_val,
+                            None,
+                        )?,
+                        _ => {
+                            let lhs_rust_ty = self.convert_type(compute_lhs_ty.ctype)?;
+                            let val = self.convert_binary_operator(
+                                ctx,
+                                op,
+                                self.convert_type(compute_res_ty.ctype)?,
+                                compute_res_ty.ctype,
+                                compute_lhs_ty,
+                                rhs_ty,
+                                mk().cast_expr(old.clone(), lhs_rust_ty),
+                                rhs_val,
+                                None,
+                            )?;
+                            mk().cast_expr(val, value_rust_ty.clone())
+                        }
+                    };
+
+                    // loop {
+                    //     let new = old op rhs;
+                    //     match place.compare_exchange(old, new, SeqCst, SeqCst) {
+                    //         Ok(_) => break new,
+                    //         Err(current) => old = current,
+                    //     }
+                    // }
+                    let result = if fetch_first { old.clone() } else { mk().ident_expr(&new_name) };
+                    let cxchg = mk().method_call_expr(
+                        place,
+                        "compare_exchange",
+                        vec![
+                            self.atomic_value_to_repr(value_ty, old.clone()),
+                            self.atomic_value_to_repr(value_ty, mk().ident_expr(&new_name)),
+                            self.atomic_seq_cst(),
+                            self.atomic_seq_cst(),
+                        ],
+                    );
+                    let arms = vec![
+                        mk().arm(
+                            mk().tuple_struct_pat(vec!["Ok"], vec![mk().wild_pat()]),
+                            None as Option<P<Expr>>,
+                            mk().break_expr_value(None as Option<Ident>, Some(result)),
+                        ),
+                        mk().arm(
+                            mk().tuple_struct_pat(vec!["Err"], vec![mk().ident_pat(&current_name)]),
+                            None as Option<P<Expr>>,
+                            mk().assign_expr(
+                                old.clone(),
+                                self.atomic_value_from_repr(value_ty, mk().ident_expr(&current_name))?,
+                            ),
+                        ),
+                    ];
+                    let body = mk().block(vec![
+                        mk().local_stmt(P(mk().local(
+                            mk().ident_pat(&new_name),
+                            None as Option<P<Ty>>,
+                            Some(new_val),
+                        ))),
+                        mk().expr_stmt(mk().match_expr(cxchg, arms)),
+                    ]);
+                    mk().loop_expr(body, None as Option<Ident>)
+                }
+            };
+
+            self.convert_side_effects_expr(
+                ctx,
+                WithStmts::new(stmts, val),
+                "Atomic update is not supposed to be used",
+            )
+        }))
+    }
 }
@@ -2,6 +2,8 @@
 //! Implementations of clang's builtin functions
 
 use super::*;
+use super::atomics::MemOrdering;
+use std::sync::atomic::Ordering;
 
 impl<'c> Translation<'c> {
     /// Convert a call to a builtin function to a Rust expression
@@ -264,7 +266,12 @@ impl<'c> Translation<'c> {
                             let fn_ctx = self.function_context.borrow();
                             let src = fn_ctx.get_va_list_arg_name();
 
-                            let call_expr = mk().method_call_expr(mk().ident_expr(src), "clone", vec![] as Vec<P<Expr>>);
+                            let call_expr = if fn_ctx.va_args_slice {
+                                let new = mk().path_expr(vec!["c2rust_varargs", "VaList", "new"]);
+                                mk().call_expr(new, vec![mk().ident_expr(src)])
+                            } else {
+                                mk().method_call_expr(mk().ident_expr(src), "clone", vec![] as Vec<P<Expr>>)
+                            };
                             let assign_expr = mk().assign_expr(dst.to_expr(), call_expr);
                             let stmt = mk().semi_stmt(assign_expr);
 
@@ -525,6 +532,7 @@ impl<'c> Translation<'c> {
                         self.convert_atomic_op(
                             ctx,
                             func_name,
+                            &MemOrdering::Static(Ordering::SeqCst),
                             arg0,
                             arg1,
                             fetch_first,
@@ -607,7 +615,22 @@ impl<'c> Translation<'c> {
                 ))
             }
 
-            _ => Err(format_translation_err!(self.ast_context.display_loc(src_loc), "Unimplemented builtin {}", builtin_name)),
+            _ if builtin_name.starts_with("__builtin_neon_") => {
+                self.convert_neon_builtin(ctx, builtin_name, args)
+            }
+
+            _ => {
+                if let Some(val) = self.convert_complex_libm_call(ctx, builtin_name, args)? {
+                    return Ok(val);
+                }
+                if let Some(val) = self.convert_setjmp_call(ctx, fexp, builtin_name, args)? {
+                    return Ok(val);
+                }
+                if let Some(val) = self.convert_simd_builtin_fallback(ctx, builtin_name, args)? {
+                    return Ok(val);
+                }
+                Err(format_translation_err!(self.ast_context.display_loc(src_loc), "Unimplemented builtin {}", builtin_name))
+            }
         }
     }
 
@@ -0,0 +1,236 @@
+//! This module provides translations of C99 `_Complex` values and the operations on them. Complex
+//! numbers are translated to `num_complex::Complex`, which is `#[repr(C)]` and laid out like the
+//! corresponding C type, and which already implements the arithmetic operators.
+
+use super::*;
+
+/// `<complex.h>` functions which have an equivalent `num_complex::Complex` method. The `float`
+/// variants of these functions (`cexpf`, etc.) map to the same methods.
+static COMPLEX_METHODS: &[(&str, &str)] = &[
+    ("cabs", "norm"),
+    ("carg", "arg"),
+    ("conj", "conj"),
+    ("cexp", "exp"),
+    ("clog", "ln"),
+    ("csqrt", "sqrt"),
+    ("cpow", "powc"),
+    ("csin", "sin"),
+    ("ccos", "cos"),
+    ("ctan", "tan"),
+    ("casin", "asin"),
+    ("cacos", "acos"),
+    ("catan", "atan"),
+    ("csinh", "sinh"),
+    ("ccosh", "cosh"),
+    ("ctanh", "tanh"),
+    ("casinh", "asinh"),
+    ("cacosh", "acosh"),
+    ("catanh", "atanh"),
+];
+
+fn float_zero() -> P<Expr> {
+    mk().lit_expr(mk().float_unsuffixed_lit("0."))
+}
+
+impl<'c> Translation<'c> {
+    /// Build a `num_complex::Complex` value out of its real and imaginary parts. This uses a
+    /// struct literal rather than `Complex::new` so that it is usable in constant expressions.
+    pub(crate) fn mk_complex(&self, re: P<Expr>, im: P<Expr>) -> P<Expr> {
+        self.use_crate(ExternCrate::NumComplex);
+        mk().struct_expr(
+            vec!["num_complex", "Complex"],
+            vec![mk().field("re", re), mk().field("im", im)],
+        )
+    }
+
+    /// Get the element type of a complex type, checking that complex numbers are being
+    /// translated and that we support the element type.
+    fn complex_element_type(&self, ty: CTypeId) -> Result<CTypeId, TranslationError> {
+        if !self.tcfg.translate_complex {
+            return Err(TranslationError::generic(
+                "Complex numbers are only translated with --translate-complex",
+            ));
+        }
+        match self.ast_context.resolve_type(ty).kind {
+            CTypeKind::Complex(elt) => match self.ast_context.resolve_type(elt).kind {
+                CTypeKind::Float | CTypeKind::Double => Ok(elt),
+                ref t => Err(format_err!("Unsupported complex element type {:?}", t).into()),
+            },
+            ref t => Err(format_err!("Expected complex type, found {:?}", t).into()),
+        }
+    }
+
+    fn is_complex_expr(&self, expr: CExprId) -> bool {
+        self.ast_context[expr]
+            .kind
+            .get_type()
+            .map_or(false, |ty| self.ast_context.resolve_type(ty).kind.is_complex())
+    }
+
+    /// Translate an imaginary literal such as `2.0i`
+    pub(crate) fn convert_imaginary_literal(
+        &self,
+        ctx: ExprContext,
+        ty: CQualTypeId,
+        val: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        self.complex_element_type(ty.ctype)?;
+        Ok(self
+            .convert_expr(ctx, val)?
+            .map(|im| self.mk_complex(float_zero(), im)))
+    }
+
+    /// Translate casts from, to, and between floating complex types. Conversions to `bool` are
+    /// handled along with the other boolean conversions by `match_bool`.
+    pub(crate) fn convert_complex_cast(
+        &self,
+        kind: CastKind,
+        source_ty: CQualTypeId,
+        ty: CQualTypeId,
+        val: WithStmts<P<Expr>>,
+        expr: Option<CExprId>,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        match kind {
+            // Clang converts the real value to the element type before this cast
+            CastKind::FloatingRealToComplex => {
+                self.complex_element_type(ty.ctype)?;
+                Ok(val.map(|re| self.mk_complex(re, float_zero())))
+            }
+
+            CastKind::FloatingComplexToReal => {
+                self.complex_element_type(source_ty.ctype)?;
+                Ok(val.map(|z| mk().field_expr(z, "re")))
+            }
+
+            CastKind::FloatingComplexCast => {
+                self.complex_element_type(source_ty.ctype)?;
+                let elt = self.complex_element_type(ty.ctype)?;
+                let elt_ty = self.convert_type(elt)?;
+                let is_pure = expr.map_or(false, |e| self.ast_context.is_expr_pure(e));
+
+                val.and_then(|z| {
+                    // Both parts of the value are needed, so bind it unless it is pure
+                    let (stmts, z) = if is_pure {
+                        (vec![], z)
+                    } else {
+                        let name = self.renamer.borrow_mut().fresh();
+                        let local = mk().local_stmt(P(mk().local(
+                            mk().ident_pat(&name),
+                            None as Option<P<Ty>>,
+                            Some(z),
+                        )));
+                        (vec![local], mk().ident_expr(&name))
+                    };
+                    let re = mk().cast_expr(mk().field_expr(z.clone(), "re"), elt_ty.clone());
+                    let im = mk().cast_expr(mk().field_expr(z, "im"), elt_ty);
+                    Ok(WithStmts::new(stmts, self.mk_complex(re, im)))
+                })
+            }
+
+            _ => Err(TranslationError::generic(
+                "Integer complex numbers are not supported",
+            )),
+        }
+    }
+
+    /// Translate `__real__` and `__imag__`. These are lvalues on complex operands, so we
+    /// translate them into field accesses. On real operands `__real__` is the identity and
+    /// `__imag__` is zero.
+    pub(crate) fn convert_complex_part(
+        &self,
+        ctx: ExprContext,
+        op: c_ast::UnOp,
+        ty: CQualTypeId,
+        arg: CExprId,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        if self.is_complex_expr(arg) {
+            let arg_ty = self.ast_context[arg]
+                .kind
+                .get_type()
+                .ok_or_else(|| format_err!("bad complex operand type"))?;
+            self.complex_element_type(arg_ty)?;
+
+            let field = match op {
+                c_ast::UnOp::Real => "re",
+                _ => "im",
+            };
+            Ok(self
+                .convert_expr(ctx.used(), arg)?
+                .map(|z| mk().field_expr(z, field)))
+        } else if let c_ast::UnOp::Real = op {
+            self.convert_expr(ctx, arg)
+        } else {
+            // The operand is still evaluated for its side effects
+            self.convert_expr(ctx.unused(), arg)?
+                .and_then(|_| self.implicit_default_expr(ty.ctype, ctx.is_static))
+        }
+    }
+
+    /// `num_complex::Complex` can only be compared against other complex values, so promote the
+    /// real operand when comparing a complex value with a real one.
+    pub(crate) fn convert_complex_comparands(
+        &self,
+        lhs_type: CQualTypeId,
+        rhs_type: CQualTypeId,
+        lhs: P<Expr>,
+        rhs: P<Expr>,
+    ) -> (P<Expr>, P<Expr>) {
+        let lhs_complex = self.ast_context.resolve_type(lhs_type.ctype).kind.is_complex();
+        let rhs_complex = self.ast_context.resolve_type(rhs_type.ctype).kind.is_complex();
+
+        match (lhs_complex, rhs_complex) {
+            (true, false) => (lhs, self.mk_complex(rhs, float_zero())),
+            (false, true) => (self.mk_complex(lhs, float_zero()), rhs),
+            _ => (lhs, rhs),
+        }
+    }
+
+    /// Translate a call to a `<complex.h>` function (or its `__builtin_` form) on complex
+    /// arguments into the equivalent `num_complex::Complex` field access or method call. Returns
+    /// `None` if this isn't such a call, in which case it should be translated normally.
+    pub(crate) fn convert_complex_libm_call(
+        &self,
+        ctx: ExprContext,
+        name: &str,
+        args: &[CExprId],
+    ) -> Result<Option<WithStmts<P<Expr>>>, TranslationError> {
+        if !self.tcfg.translate_complex {
+            return Ok(None);
+        }
+        match args.first() {
+            Some(&arg) if self.is_complex_expr(arg) => {}
+            _ => return Ok(None),
+        }
+
+        let name = name.trim_start_matches("__builtin_");
+        let is_known = |name: &str| {
+            name == "creal" || name == "cimag" || COMPLEX_METHODS.iter().any(|&(f, _)| f == name)
+        };
+        let name = if !is_known(name) && name.ends_with('f') {
+            &name[..name.len() - 1]
+        } else {
+            name
+        };
+        if !is_known(name) {
+            return Ok(None);
+        }
+
+        let args = self.convert_exprs(ctx.used(), args)?;
+        let val = args.map(|mut args| {
+            let z = args.remove(0);
+            match name {
+                "creal" => mk().field_expr(z, "re"),
+                "cimag" => mk().field_expr(z, "im"),
+                _ => {
+                    let &(_, method) = COMPLEX_METHODS
+                        .iter()
+                        .find(|&&(f, _)| f == name)
+                        .unwrap();
+                    // Only `cpow` takes a second argument, the exponent
+                    mk().method_call_expr(z, method, args)
+                }
+            }
+        });
+        Ok(Some(val))
+    }
+}
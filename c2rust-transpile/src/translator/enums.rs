@@ -0,0 +1,495 @@
+//! This module provides the opt-in translation of closed C enums into Rust enums. An enum is
+//! closed if every value of its type comes from one of its own constants or from another value
+//! of its type: its constants are the only values it can hold, so it can become a `#[repr]`
+//! Rust enum instead of an integer type alias. Switches over closed enums become matches over
+//! their variants, which are exhaustive when every constant has a `case`.
+
+use crate::c_ast::iterators::immediate_children_all_types;
+
+use super::*;
+
+impl<'c> Translation<'c> {
+    /// Find the enums whose values all come from their own constants.
+    pub(crate) fn find_closed_enums(&self) -> IndexSet<CEnumId> {
+        let mut closed: IndexSet<CEnumId> = self
+            .ast_context
+            .iter_decls()
+            .map(|(&decl_id, _)| decl_id)
+            .filter(|&decl_id| self.is_closed_enum_candidate(decl_id))
+            .collect();
+
+        for (&decl_id, decl) in self.ast_context.iter_decls() {
+            match decl.kind {
+                // Bit-fields are stored as integers, and unions can hold any bits
+                CDeclKind::Field {
+                    typ,
+                    bitfield_width,
+                    ..
+                } => {
+                    let in_union = match self.ast_context.parents.get(&decl_id) {
+                        Some(&record) => match self.ast_context[record].kind {
+                            CDeclKind::Union { .. } => true,
+                            _ => false,
+                        },
+                        None => false,
+                    };
+                    if bitfield_width.is_some() || in_union {
+                        if let Some(enum_id) = self.enum_of(typ.ctype) {
+                            closed.remove(&enum_id);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Code outside the translation unit may store any value in the enums it shares with
+        // it: through the parameters and returns of functions it can call or define, through
+        // globals with external linkage, and through pointers and records reachable from those
+        let address_taken = self.address_taken_fns();
+        for (&decl_id, decl) in self.ast_context.iter_decls() {
+            let shared_ty = match decl.kind {
+                CDeclKind::Function { is_global, typ, .. }
+                    if is_global || address_taken.contains(&decl_id) =>
+                {
+                    typ
+                }
+                CDeclKind::Variable {
+                    has_static_duration: true,
+                    is_externally_visible,
+                    is_defn,
+                    typ,
+                    ..
+                } if is_externally_visible || !is_defn => typ.ctype,
+                _ => continue,
+            };
+            self.open_reachable_enums(shared_ty, &mut closed, &mut visited);
+        }
+
+        let mut visited = HashSet::new();
+        for &decl_id in &self.ast_context.c_decls_top {
+            for expr_id in DFExpr::new(&self.ast_context, decl_id.into()).flat_map(SomeId::expr) {
+                if let Some(enum_id) = self.opened_enum(expr_id) {
+                    closed.remove(&enum_id);
+                }
+                for ty in self.reinterpreted_types(expr_id) {
+                    self.open_reachable_enums(ty, &mut closed, &mut visited);
+                }
+            }
+        }
+
+        closed
+    }
+
+    /// Can this enum be translated into a Rust enum, if its values are closed? Its constants
+    /// have to be distinct, as Rust discriminants are, and include 0, which is the value of
+    /// zero-initialized storage.
+    fn is_closed_enum_candidate(&self, decl_id: CDeclId) -> bool {
+        let variants = match self.ast_context[decl_id].kind {
+            CDeclKind::Enum {
+                ref variants,
+                integral_type: Some(_),
+                ..
+            } if self.closed_enum_repr(decl_id).is_some() => variants,
+            _ => return false,
+        };
+        let values: IndexSet<i128> = variants
+            .iter()
+            .filter_map(|&variant| self.enum_constant_value(variant))
+            .collect();
+        values.len() == variants.len() && values.contains(&0)
+    }
+
+    /// Remove the enums that values of this type can hold from `closed`, including the ones
+    /// behind pointers, in arrays and records, and in function parameters and returns
+    fn open_reachable_enums(
+        &self,
+        ty: CTypeId,
+        closed: &mut IndexSet<CEnumId>,
+        visited: &mut HashSet<CTypeId>,
+    ) {
+        if !visited.insert(ty) {
+            return;
+        }
+        match self.ast_context.resolve_type(ty).kind {
+            CTypeKind::Enum(enum_id) => {
+                closed.remove(&enum_id);
+            }
+            CTypeKind::Pointer(pointee)
+            | CTypeKind::Reference(pointee)
+            | CTypeKind::BlockPointer(pointee)
+            | CTypeKind::Atomic(pointee)
+            | CTypeKind::Vector(pointee, _) => {
+                self.open_reachable_enums(pointee.ctype, closed, visited)
+            }
+            CTypeKind::ConstantArray(elem, _)
+            | CTypeKind::IncompleteArray(elem)
+            | CTypeKind::VariableArray(elem, _)
+            | CTypeKind::Complex(elem) => self.open_reachable_enums(elem, closed, visited),
+            CTypeKind::Function(ret, ref params, ..) => {
+                self.open_reachable_enums(ret.ctype, closed, visited);
+                for param in params {
+                    self.open_reachable_enums(param.ctype, closed, visited);
+                }
+            }
+            CTypeKind::Struct(record_id) | CTypeKind::Union(record_id) => {
+                let fields = match self.ast_context[record_id].kind {
+                    CDeclKind::Struct {
+                        fields: Some(ref fields),
+                        ..
+                    }
+                    | CDeclKind::Union {
+                        fields: Some(ref fields),
+                        ..
+                    } => fields,
+                    _ => return,
+                };
+                for &field_id in fields {
+                    if let CDeclKind::Field { typ, .. } = self.ast_context[field_id].kind {
+                        self.open_reachable_enums(typ.ctype, closed, visited);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Find the enum, if any, that this expression produces a value of from outside the enum's
+    /// constants and values
+    fn opened_enum(&self, expr_id: CExprId) -> Option<CEnumId> {
+        let expr_kind = &self.ast_context[expr_id].kind;
+        let enum_id = self.enum_of(expr_kind.get_type()?)?;
+
+        let is_closed = match *expr_kind {
+            CExprKind::DeclRef(..)
+            | CExprKind::Member(..)
+            | CExprKind::ArraySubscript(..)
+            | CExprKind::Unary(_, c_ast::UnOp::Deref, _, _)
+            | CExprKind::Binary(_, c_ast::BinOp::Assign, _, _, _, _)
+            | CExprKind::Binary(_, c_ast::BinOp::Comma, _, _, _, _)
+            | CExprKind::Conditional(..)
+            | CExprKind::BinaryConditional(..)
+            | CExprKind::Choose(..)
+            | CExprKind::Paren(..)
+            | CExprKind::Statements(..)
+            | CExprKind::CompoundLiteral(..)
+            | CExprKind::InitList(..)
+            | CExprKind::DesignatedInitExpr(..)
+            | CExprKind::ImplicitValueInit(..) => true,
+
+            // Functions defined elsewhere may return any value
+            CExprKind::Call(_, func, _) => match self.direct_callee(func) {
+                Some(callee) => match self.ast_context[callee].kind {
+                    CDeclKind::Function { body, .. } => body.is_some(),
+                    _ => false,
+                },
+                None => true,
+            },
+
+            CExprKind::ImplicitCast(_, source, kind, _, _)
+            | CExprKind::ExplicitCast(_, source, kind, _, _) => match kind {
+                CastKind::LValueToRValue | CastKind::NoOp | CastKind::ConstCast => true,
+                _ => {
+                    let source_ty = self.ast_context[source].kind.get_type();
+                    source_ty.and_then(|ty| self.enum_of(ty)) == Some(enum_id)
+                        || self.enum_constant_ref(source) == Some(enum_id)
+                }
+            },
+
+            _ => false,
+        };
+
+        if is_closed {
+            None
+        } else {
+            Some(enum_id)
+        }
+    }
+
+    /// Get the types whose memory this expression lets code access as other types, like bytes:
+    /// the pointees of pointer casts, and of pointers passed as variadic arguments
+    fn reinterpreted_types(&self, expr_id: CExprId) -> Vec<CTypeId> {
+        let pointee = |ty: CTypeId| {
+            self.ast_context
+                .get_pointee_qual_type(ty)
+                .map(|pointee| pointee.ctype)
+        };
+        let expr_pointee = |e: CExprId| self.ast_context[e].kind.get_type().and_then(pointee);
+
+        match self.ast_context[expr_id].kind {
+            CExprKind::ImplicitCast(ty, source, CastKind::BitCast, _, _)
+            | CExprKind::ExplicitCast(ty, source, CastKind::BitCast, _, _) => {
+                match (pointee(ty.ctype), expr_pointee(source)) {
+                    (Some(target), Some(source))
+                        if self.ast_context.resolve_type(target).kind
+                            == self.ast_context.resolve_type(source).kind =>
+                    {
+                        vec![]
+                    }
+                    (target, source) => target.into_iter().chain(source).collect(),
+                }
+            }
+            CExprKind::Call(_, func, ref args) => {
+                let fixed_args = self.ast_context[func]
+                    .kind
+                    .get_type()
+                    .and_then(pointee)
+                    .map(|ty| match self.ast_context.resolve_type(ty).kind {
+                        CTypeKind::Function(_, ref params, _, _, _) => params.len(),
+                        _ => 0,
+                    })
+                    .unwrap_or(0);
+                args.iter()
+                    .skip(fixed_args)
+                    .filter_map(|&arg| expr_pointee(arg))
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Get the enum of an enum type
+    fn enum_of(&self, ty: CTypeId) -> Option<CEnumId> {
+        match self.ast_context.resolve_type(ty).kind {
+            CTypeKind::Enum(enum_id) => Some(enum_id),
+            _ => None,
+        }
+    }
+
+    /// Get the enum of the constant this expression refers to, if it is one
+    fn enum_constant_ref(&self, expr_id: CExprId) -> Option<CEnumId> {
+        match self.ast_context[expr_id].kind {
+            CExprKind::DeclRef(_, decl_id, _) => match self.ast_context[decl_id].kind {
+                CDeclKind::EnumConstant { .. } => self.ast_context.parents.get(&decl_id).cloned(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn enum_constant_value(&self, variant_id: CEnumConstantId) -> Option<i128> {
+        match self.ast_context[variant_id].kind {
+            CDeclKind::EnumConstant { value, .. } => Some(const_int_value(value)),
+            _ => None,
+        }
+    }
+
+    /// Get the enum of a closed enum type
+    pub(crate) fn closed_enum(&self, ty: CTypeId) -> Option<CEnumId> {
+        self.enum_of(ty)
+            .filter(|enum_id| self.closed_enums.contains(enum_id))
+    }
+
+    /// Get the primitive integer type that represents the values of an enum
+    fn closed_enum_repr(&self, enum_id: CEnumId) -> Option<&'static str> {
+        let integral_type = match self.ast_context[enum_id].kind {
+            CDeclKind::Enum {
+                integral_type: Some(integral_type),
+                ..
+            } => integral_type,
+            _ => return None,
+        };
+        let repr = match self.ast_context.resolve_type(integral_type.ctype).kind {
+            CTypeKind::Char | CTypeKind::SChar => "i8",
+            CTypeKind::UChar => "u8",
+            CTypeKind::Short => "i16",
+            CTypeKind::UShort => "u16",
+            CTypeKind::Int => "i32",
+            CTypeKind::UInt => "u32",
+            CTypeKind::Long | CTypeKind::LongLong => "i64",
+            CTypeKind::ULong | CTypeKind::ULongLong => "u64",
+            _ => return None,
+        };
+        Some(repr)
+    }
+
+    /// Translate a closed enum into a Rust enum with the values of its constants as
+    /// discriminants, and a `TryFrom` implementation that converts the values back.
+    pub(crate) fn convert_closed_enum(
+        &self,
+        enum_id: CEnumId,
+        span: Span,
+        enum_name: &str,
+    ) -> Result<ConvertedDecl, TranslationError> {
+        let variants = match self.ast_context[enum_id].kind {
+            CDeclKind::Enum { ref variants, .. } => variants,
+            _ => return Err(format_err!("Expected an enum declaration").into()),
+        };
+        let repr = self
+            .closed_enum_repr(enum_id)
+            .ok_or_else(|| format_err!("Unsupported closed enum type"))?;
+
+        let mut enum_variants = vec![];
+        let mut arms = vec![];
+        for &variant_id in variants {
+            let name = self
+                .renamer
+                .borrow_mut()
+                .get(&variant_id)
+                .expect("Enum constant not named");
+            let value = match self.ast_context[variant_id].kind {
+                CDeclKind::EnumConstant { value, .. } => value,
+                _ => return Err(format_err!("Expected an enum constant").into()),
+            };
+            let value = match value {
+                ConstIntExpr::I(value) => signed_int_expr(value),
+                ConstIntExpr::U(value) => {
+                    mk().lit_expr(mk().int_lit(value as u128, LitIntType::Unsuffixed))
+                }
+            };
+
+            let variant = mk().path_expr(vec![enum_name, name.as_str()]);
+            let ok = mk().call_expr(mk().ident_expr("Ok"), vec![variant]);
+            arms.push(mk().arm(mk().lit_pat(value.clone()), None as Option<P<Expr>>, ok));
+            enum_variants.push(mk().unit_variant(name, Some(value)));
+        }
+
+        let enum_item = mk()
+            .span(span)
+            .pub_()
+            .call_attr("derive", vec!["Copy", "Clone", "PartialEq", "Eq", "Debug"])
+            .call_attr("repr", vec![repr])
+            .enum_item(enum_name, enum_variants);
+
+        // impl TryFrom<u32> for E {
+        //     type Error = u32;
+        //     fn try_from(value: u32) -> Result<E, u32> {
+        //         match value { 0 => Ok(E::A), ..., _ => Err(value) }
+        //     }
+        // }
+        let value = mk().ident_expr("value");
+        let err = mk().call_expr(mk().ident_expr("Err"), vec![value.clone()]);
+        arms.push(mk().arm(mk().wild_pat(), None as Option<P<Expr>>, err));
+        let body = mk().block(vec![mk().expr_stmt(mk().match_expr(value, arms))]);
+
+        let repr_ty = mk().path_ty(vec![repr]);
+        let result_ty = mk().path_ty(vec![mk().path_segment_with_args(
+            "Result",
+            mk().angle_bracketed_args(vec![mk().path_ty(vec![enum_name]), repr_ty.clone()]),
+        )]);
+        let decl = mk().fn_decl(
+            vec![mk().arg(repr_ty.clone(), mk().ident_pat("value"))],
+            FunctionRetTy::Ty(result_ty),
+        );
+        let try_from = mk().fn_impl_item("try_from", decl, body);
+        let error = mk().ty_alias_impl_item("Error", repr_ty.clone());
+
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+        let trait_path = vec![
+            mk().path_segment(""),
+            mk().path_segment(std_or_core),
+            mk().path_segment("convert"),
+            mk().path_segment_with_args("TryFrom", mk().angle_bracketed_args(vec![repr_ty])),
+        ];
+        let impl_item = mk().span(span).trait_impl_item(
+            trait_path,
+            mk().path_ty(vec![enum_name]),
+            vec![error, try_from],
+        );
+
+        Ok(ConvertedDecl::Items(vec![enum_item, impl_item]))
+    }
+
+    /// Get the path of the variant of a closed enum for one of its constants, importing the
+    /// enum if needed
+    pub(crate) fn closed_enum_variant_path(&self, variant_id: CEnumConstantId) -> Vec<String> {
+        let enum_id = self.ast_context.parents[&variant_id];
+        let enum_name = self
+            .type_converter
+            .borrow()
+            .resolve_decl_name(enum_id)
+            .expect("Enums should already be renamed");
+        let name = self
+            .renamer
+            .borrow()
+            .get(&variant_id)
+            .expect("Enum constant not named");
+        if let Some(cur_file) = *self.cur_file.borrow() {
+            self.add_import(cur_file, enum_id, &enum_name);
+        }
+        vec![enum_name, name]
+    }
+
+    /// If this switch is over a closed enum, and all its cases are constants of that enum,
+    /// get the enum value to match on, the enum, and whether the cases cover every constant.
+    pub(crate) fn closed_enum_switch(
+        &self,
+        scrutinee: CExprId,
+        body: CStmtId,
+    ) -> Option<(CExprId, CEnumId, bool)> {
+        // The enum value is promoted to an integer
+        let value = match self.ast_context[scrutinee].kind {
+            CExprKind::ImplicitCast(_, e, CastKind::IntegralCast, _, _) => e,
+            _ => scrutinee,
+        };
+        let enum_id = self.closed_enum(self.ast_context[value].kind.get_type()?)?;
+        let variants = match self.ast_context[enum_id].kind {
+            CDeclKind::Enum { ref variants, .. } => variants,
+            _ => return None,
+        };
+        let values: IndexSet<i128> = variants
+            .iter()
+            .filter_map(|&variant| self.enum_constant_value(variant))
+            .collect();
+
+        let mut cases = IndexSet::new();
+        if !self.collect_switch_cases(body.into(), &mut cases) {
+            return None;
+        }
+        if !cases.is_subset(&values) {
+            return None;
+        }
+        Some((value, enum_id, cases.len() == values.len()))
+    }
+
+    /// Collect the values of the cases of a switch body, outside nested switches. Returns
+    /// `false` if there is a case range.
+    fn collect_switch_cases(&self, id: SomeId, cases: &mut IndexSet<i128>) -> bool {
+        if let SomeId::Stmt(stmt_id) = id {
+            match self.ast_context[stmt_id].kind {
+                CStmtKind::Case(_, _, value) => {
+                    cases.insert(const_int_value(value));
+                }
+                CStmtKind::CaseRange(..) => return false,
+                CStmtKind::Switch { .. } => return true,
+                _ => {}
+            }
+        }
+        immediate_children_all_types(&self.ast_context, id)
+            .into_iter()
+            .all(|child| match child {
+                SomeId::Type(_) => true,
+                _ => self.collect_switch_cases(child, cases),
+            })
+    }
+
+    /// Get the pattern matching the variant of a closed enum with the value of a case
+    pub(crate) fn closed_enum_case_pat(
+        &self,
+        enum_id: CEnumId,
+        value: ConstIntExpr,
+    ) -> Result<P<Pat>, TranslationError> {
+        let value = const_int_value(value);
+        let variants = match self.ast_context[enum_id].kind {
+            CDeclKind::Enum { ref variants, .. } => variants,
+            _ => return Err(format_err!("Expected an enum declaration").into()),
+        };
+        let variant_id = *variants
+            .iter()
+            .find(|&&variant| self.enum_constant_value(variant) == Some(value))
+            .ok_or_else(|| {
+                format_err!(
+                    "Case {} of a closed enum switch is not a constant of the enum",
+                    value
+                )
+            })?;
+        Ok(mk().qpath_pat(None, self.closed_enum_variant_path(variant_id)))
+    }
+}
+
+fn const_int_value(value: ConstIntExpr) -> i128 {
+    match value {
+        ConstIntExpr::I(value) => value as i128,
+        ConstIntExpr::U(value) => value as i128,
+    }
+}
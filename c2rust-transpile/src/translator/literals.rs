@@ -39,6 +39,9 @@ impl<'c> Translation<'c> {
             match self.ast_context[variant_id].kind {
                 CDeclKind::EnumConstant { value: v, .. } => {
                     if v == ConstIntExpr::I(value) || v == ConstIntExpr::U(value as u64) {
+                        if self.closed_enums.contains(&def_id) {
+                            return mk().path_expr(self.closed_enum_variant_path(variant_id));
+                        }
                         let name = self.renamer.borrow().get(&variant_id).unwrap();
 
                         // Import the enum variant if needed
@@ -16,13 +16,14 @@ use syntax::ast::*;
 use syntax::util::comments::CommentStyle;
 use syntax::token::{self, DelimToken, Nonterminal};
 use syntax::ptr::*;
-use syntax::source_map::{FilePathMapping, SourceMap};
+use syntax::source_map::{dummy_spanned, FilePathMapping, SourceMap};
 use syntax::tokenstream::{TokenStream, TokenTree};
 use syntax::{ast, with_globals};
 use syntax_pos::{FileName, Span, DUMMY_SP};
 use syntax_pos::edition::Edition;
 
 use crate::rust_ast::pos_to_span;
+use crate::rust_ast::cfg_merge::CfgMerger;
 use crate::rust_ast::comment_store::CommentStore;
 use crate::rust_ast::item_store::ItemStore;
 use crate::rust_ast::traverse::Traversal;
@@ -34,7 +35,9 @@ use crate::c_ast::iterators::{DFExpr, SomeId};
 use crate::c_ast::*;
 use crate::cfg;
 use crate::convert_type::TypeConverter;
+use crate::dedup::{self, HeaderType};
 use crate::renamer::Renamer;
+use crate::report::{DeclOutcome, DeclReport};
 use crate::with_stmts::WithStmts;
 use crate::{ExternCrate, ExternCrateDetails, TranspilerConfig};
 use c2rust_ast_exporter::clang_ast::LRValue;
@@ -43,10 +46,14 @@ mod assembly;
 mod atomics;
 mod builtins;
 mod comments;
+mod complex;
+mod enums;
 mod literals;
 mod main_function;
 mod named_references;
+mod restrict;
 mod operators;
+mod setjmp;
 mod simd;
 mod structs;
 mod variadic;
@@ -215,6 +222,12 @@ pub struct FunContext {
     va_list_arg_name: Option<String>,
     /// The va_list decls that are either `va_start`ed or `va_copy`ed.
     va_list_decl_ids: Option<IndexSet<CDeclId>>,
+    /// Whether the variadic arguments are passed as a slice of `c2rust_varargs::VarArg`s
+    va_args_slice: bool,
+    /// Whether the function returns a value, rather than `()`
+    returns_value: bool,
+    /// Whether we're translating the closure for the branch taken when `setjmp` returns 0
+    in_setjmp_closure: bool,
 }
 
 impl FunContext {
@@ -223,6 +236,9 @@ impl FunContext {
             name: None,
             va_list_arg_name: None,
             va_list_decl_ids: None,
+            va_args_slice: false,
+            returns_value: false,
+            in_setjmp_closure: false,
         }
     }
 
@@ -230,6 +246,9 @@ impl FunContext {
         self.name = Some(fn_name.to_string());
         self.va_list_arg_name = None;
         self.va_list_decl_ids = None;
+        self.va_args_slice = false;
+        self.returns_value = false;
+        self.in_setjmp_closure = false;
     }
 
     pub fn get_name(&self) -> &str {
@@ -244,6 +263,8 @@ impl FunContext {
 #[derive(Clone)]
 struct MacroExpansion {
     ty: CTypeId,
+    /// Parameter types of a function-like macro
+    param_tys: Vec<CTypeId>,
 }
 
 pub struct Translation<'c> {
@@ -263,6 +284,27 @@ pub struct Translation<'c> {
     function_context: RefCell<FunContext>,
     potential_flexible_array_members: RefCell<IndexSet<CDeclId>>,
     macro_expansions: RefCell<IndexMap<CDeclId, Option<MacroExpansion>>>,
+    // Macro argument expressions to replace with the corresponding parameter
+    // while translating the body of a function-like macro
+    macro_arg_substitutions: RefCell<IndexMap<CExprId, String>>,
+    // `restrict` pointer parameters translated into references of the given mutability
+    reference_params: HashMap<CParamId, Mutability>,
+    // Variadic functions which take their variadic arguments as a slice
+    slice_variadic_fns: IndexSet<CDeclId>,
+    // Enums translated into Rust enums because their values only come from their own constants
+    closed_enums: IndexSet<CEnumId>,
+    // Functions which may return while a `longjmp` is pending
+    longjmp_fns: IndexSet<CDeclId>,
+    // Values of the statement expressions lowered into the control-flow graph of the enclosing
+    // function, keyed on their compound statement
+    stmt_expr_values: RefCell<IndexMap<CStmtId, P<Expr>>>,
+
+    // Translation report support
+    decl_reports: RefCell<Vec<DeclReport>>,
+    // Features used while translating the current declaration
+    decl_features: RefCell<IndexSet<&'static str>>,
+    // Function definitions replaced by extern declarations, with the error that caused it
+    replaced_decls: RefCell<IndexMap<CDeclId, TranslationError>>,
 
     // Comment support
     pub comment_context: CommentContext, // Incoming comments
@@ -272,6 +314,8 @@ pub struct Translation<'c> {
 
     // Items indexed by file id of the source
     items: RefCell<IndexMap<FileId, ItemStore>>,
+    // Items translated from types defined in headers, set aside for `--dedup-types`
+    header_type_items: RefCell<Vec<P<Item>>>,
 
     // Mod names to try to stop collisions from happening
     mod_names: RefCell<IndexMap<String, PathBuf>>,
@@ -502,291 +546,12 @@ pub fn translate(
     ast_context: TypedAstContext,
     tcfg: &TranspilerConfig,
     main_file: PathBuf,
-) -> (String, PragmaVec, CrateSet) {
+) -> (String, PragmaVec, CrateSet, Vec<DeclReport>, Vec<HeaderType>) {
     let mut t = Translation::new(ast_context, tcfg, main_file.as_path());
-    let ctx = ExprContext {
-        used: true,
-        is_static: false,
-        is_const: false,
-        decay_ref: DecayRef::Default,
-        is_bitfield_write: false,
-        needs_address: false,
-        expecting_valistimpl: false,
-        ternary_needs_parens: false,
-        expanding_macro: None,
-    };
 
     // `with_globals` sets up a thread-local variable required by the syntax crate.
     with_globals(Edition::Edition2018, || {
-        t.use_crate(ExternCrate::Libc);
-
-        // Sort the top-level declarations by file and source location so that we
-        // preserve the ordering of all declarations in each file.
-        t.ast_context.sort_top_decls();
-
-        t.locate_comments();
-
-        // Headers often pull in declarations that are unused;
-        // we simplify the translator output by omitting those.
-        t.ast_context.prune_unused_decls();
-
-        enum Name<'a> {
-            VarName(&'a str),
-            TypeName(&'a str),
-            AnonymousType,
-            NoName,
-        }
-
-        fn some_type_name(s: Option<&str>) -> Name {
-            match s {
-                None => Name::AnonymousType,
-                Some(r) => Name::TypeName(r),
-            }
-        }
-
-        // Used for testing; so that we don't overlap with C function names
-        if let Some(ref prefix) = t.tcfg.prefix_function_names {
-            prefix_names(&mut t, prefix);
-        }
-
-        // Identify typedefs that name unnamed types and collapse the two declarations
-        // into a single name and declaration, eliminating the typedef altogether.
-        let mut prenamed_decls: IndexMap<CDeclId, CDeclId> = IndexMap::new();
-        for (&decl_id, decl) in t.ast_context.iter_decls() {
-            if let CDeclKind::Typedef { ref name, typ, .. } = decl.kind {
-                if let Some(subdecl_id) = t
-                    .ast_context
-                    .resolve_type(typ.ctype)
-                    .kind
-                    .as_underlying_decl()
-                {
-                    let is_unnamed = match t.ast_context[subdecl_id].kind {
-                        CDeclKind::Struct { name: None, .. }
-                        | CDeclKind::Union { name: None, .. }
-                        | CDeclKind::Enum { name: None, .. } => true,
-
-                        // Detect case where typedef and struct share the same name.
-                        // In this case the purpose of the typedef was simply to eliminate
-                        // the need for the 'struct' tag when refering to the type name.
-                        CDeclKind::Struct {
-                            name: Some(ref target_name),
-                            ..
-                        }
-                        | CDeclKind::Union {
-                            name: Some(ref target_name),
-                            ..
-                        }
-                        | CDeclKind::Enum {
-                            name: Some(ref target_name),
-                            ..
-                        } => name == target_name,
-
-                        _ => false,
-                    };
-
-                    if is_unnamed
-                        && !prenamed_decls
-                            .values()
-                            .find(|decl_id| *decl_id == &subdecl_id)
-                            .is_some()
-                    {
-                        prenamed_decls.insert(decl_id, subdecl_id);
-
-                        t.type_converter
-                            .borrow_mut()
-                            .declare_decl_name(decl_id, name);
-                        t.type_converter
-                            .borrow_mut()
-                            .alias_decl_name(subdecl_id, decl_id);
-                    }
-                }
-            }
-        }
-
-        t.ast_context.prenamed_decls = prenamed_decls;
-
-        // Helper function that returns true if there is either a matching typedef or its
-        // corresponding struct/union/enum
-        fn contains(prenamed_decls: &IndexMap<CDeclId, CDeclId>, decl_id: &CDeclId) -> bool {
-            (prenamed_decls.contains_key(decl_id)
-                || prenamed_decls.values().find(|id| *id == decl_id).is_some())
-        }
-
-        // Populate renamer with top-level names
-        for (&decl_id, decl) in t.ast_context.iter_decls() {
-            let decl_name = match decl.kind {
-                _ if contains(&t.ast_context.prenamed_decls, &decl_id) => Name::NoName,
-                CDeclKind::Struct { ref name, .. } => {
-                    some_type_name(name.as_ref().map(String::as_str))
-                }
-                CDeclKind::Enum { ref name, .. } => {
-                    some_type_name(name.as_ref().map(String::as_str))
-                }
-                CDeclKind::Union { ref name, .. } => {
-                    some_type_name(name.as_ref().map(String::as_str))
-                }
-                CDeclKind::Typedef { ref name, .. } => Name::TypeName(name),
-                CDeclKind::Function { ref name, .. } => Name::VarName(name),
-                CDeclKind::EnumConstant { ref name, .. } => Name::VarName(name),
-                CDeclKind::Variable { ref ident, .. }
-                    if t.ast_context.c_decls_top.contains(&decl_id) =>
-                {
-                    Name::VarName(ident)
-                }
-                CDeclKind::MacroObject { ref name, .. } => Name::VarName(name),
-                _ => Name::NoName,
-            };
-            match decl_name {
-                Name::NoName => (),
-                Name::AnonymousType => {
-                    t.type_converter
-                        .borrow_mut()
-                        .declare_decl_name(decl_id, "C2RustUnnamed");
-                }
-                Name::TypeName(name) => {
-                    t.type_converter
-                        .borrow_mut()
-                        .declare_decl_name(decl_id, name);
-                }
-                Name::VarName(name) => {
-                    t.renamer.borrow_mut().insert(decl_id, &name);
-                }
-            }
-        }
-
-        {
-            let convert_type = |decl_id: CDeclId, decl: &CDecl| {
-                let decl_file_id = t.ast_context.file_id(decl);
-                if t.tcfg.reorganize_definitions {
-                    *t.cur_file.borrow_mut() = decl_file_id;
-                }
-                match t.convert_decl(ctx, decl_id) {
-                    Ok(ConvertedDecl::Item(item)) => {
-                        t.insert_item(item, decl);
-                    }
-                    Ok(ConvertedDecl::ForeignItem(item)) => {
-                        t.insert_foreign_item(item, decl);
-                    }
-                    Ok(ConvertedDecl::Items(items)) => {
-                        for item in items {
-                            t.insert_item(item, decl);
-                        }
-                    }
-                    Ok(ConvertedDecl::NoItem) => {}
-                    Err(e) => {
-                        let ref k = t.ast_context.get_decl(&decl_id).map(|x| &x.kind);
-                        let msg = format!("Skipping declaration {:?} due to error: {}", k, e);
-                        translate_failure(&t.tcfg, &msg);
-                    }
-                }
-                t.cur_file.borrow_mut().take();
-
-                if t.tcfg.reorganize_definitions
-                    && decl_file_id.map_or(false, |id| id != t.main_file)
-                {
-                    t.generate_submodule_imports(decl_id, decl_file_id);
-                }
-            };
-
-            // Export all types
-            for (&decl_id, decl) in t.ast_context.iter_decls() {
-                let needs_export = match decl.kind {
-                    CDeclKind::Struct { .. } => true,
-                    CDeclKind::Enum { .. } => true,
-                    CDeclKind::EnumConstant { .. } => true,
-                    CDeclKind::Union { .. } => true,
-                    CDeclKind::Typedef { .. } =>
-                    // Only check the key as opposed to `contains` because the key should be the
-                    // typedef id
-                    {
-                        !t.ast_context.prenamed_decls.contains_key(&decl_id)
-                    }
-                    _ => false,
-                };
-                if needs_export {
-                    convert_type(decl_id, decl);
-                }
-            }
-        }
-
-        // Export top-level value declarations
-        for top_id in &t.ast_context.c_decls_top {
-            let needs_export = match t.ast_context[*top_id].kind {
-                CDeclKind::Function { is_implicit, .. } => !is_implicit,
-                CDeclKind::Variable { .. } => true,
-                CDeclKind::MacroObject { .. } => tcfg.translate_const_macros,
-                _ => false,
-            };
-            if needs_export {
-                let decl_opt = t.ast_context.get_decl(top_id);
-                let decl = decl_opt.as_ref().unwrap();
-                let decl_file_id = t.ast_context.file_id(decl);
-
-                if t.tcfg.reorganize_definitions
-                    && decl_file_id.map_or(false, |id| id != t.main_file)
-                {
-                    *t.cur_file.borrow_mut() = decl_file_id;
-                }
-                match t.convert_decl(ctx, *top_id) {
-                    Ok(ConvertedDecl::Item(item)) => {
-                        t.insert_item(item, decl);
-                    }
-                    Ok(ConvertedDecl::ForeignItem(item)) => {
-                        t.insert_foreign_item(item, decl);
-                    }
-                    Ok(ConvertedDecl::Items(items)) => {
-                        for item in items {
-                            t.insert_item(item, decl);
-                        }
-                    }
-                    Ok(ConvertedDecl::NoItem) => {}
-                    Err(e) => {
-                        let ref decl = t.ast_context.get_decl(top_id);
-                        let msg = match decl {
-                            Some(decl) => {
-                                let decl_identifier = decl.kind.get_name().map_or_else(
-                                    || {
-                                        t.ast_context.display_loc(&decl.loc)
-                                            .map_or("Unknown".to_string(), |l| format!("at {}", l))
-                                    },
-                                    |name| name.clone(),
-                                );
-                                format!("Failed to translate {}: {}", decl_identifier, e)
-                            }
-                            _ => format!("Failed to translate declaration: {}", e,),
-                        };
-                        translate_failure(&t.tcfg, &msg);
-                    }
-                }
-                t.cur_file.borrow_mut().take();
-
-                if t.tcfg.reorganize_definitions
-                    && decl_file_id.map_or(false, |id| id != t.main_file)
-                {
-                    t.generate_submodule_imports(*top_id, decl_file_id);
-                }
-            }
-        }
-
-        // Add the main entry point
-        if let Some(main_id) = t.ast_context.c_main {
-            match t.convert_main(main_id) {
-                Ok(item) => t.items.borrow_mut()[&t.main_file].add_item(item),
-                Err(e) => {
-                    let msg = format!("Failed to translate main: {}", e);
-                    translate_failure(&t.tcfg, &msg)
-                }
-            }
-        }
-
-        // Initialize global statics when necessary
-        if !t.sectioned_static_initializers.borrow().is_empty() {
-            let (initializer_fn, initializer_static) = t.generate_global_static_init();
-            let store = &mut t.items.borrow_mut()[&t.main_file];
-
-            store.add_item(initializer_fn);
-            store.add_item(initializer_static);
-        }
+        translate_decls(&mut t, &mut IndexMap::new());
 
         let pragmas = t.get_pragmas();
         let crates = t.extern_crates.borrow().clone();
@@ -842,6 +607,24 @@ pub fn translate(
             .into_iter()
             .map(|p_i| p_i.map(|i| traverser.traverse_item(i)))
             .collect();
+        // Header types are printed without their comments
+        let header_type_items: Vec<P<Item>> = t
+            .header_type_items
+            .replace(vec![])
+            .into_iter()
+            .map(|p_i| p_i.map(|i| traverser.traverse_item(i)))
+            .collect();
+        let use_items = uses.into_items();
+        let mut defined_names = items
+            .iter()
+            .chain(&header_type_items)
+            .map(|i| i.ident.to_string())
+            .chain(foreign_items.iter().map(|fi| fi.ident.to_string()))
+            .collect();
+        for use_item in &use_items {
+            dedup::collect_imported_names(use_item, &mut defined_names);
+        }
+        let header_types = dedup::header_types(header_type_items, &defined_names);
 
         let mut reordered_comment_store = traverser.into_comment_store();
         let remaining_comments = t.comment_context.get_remaining_comments(t.main_file);
@@ -869,7 +652,7 @@ pub fn translate(
             // imports near the top of the file than randomly scattered about. Also, there is probably
             // no reason to have comments associated with imports so it doesn't need to go through
             // the above comment store process
-            for use_item in uses.into_items() {
+            for use_item in use_items {
                 s.print_item(&use_item);
             }
 
@@ -890,10 +673,503 @@ pub fn translate(
 
             s.print_remaining_comments();
         });
-        (translation, pragmas, crates)
+        (translation, pragmas, crates, t.decl_reports.replace(vec![]), header_types)
+    })
+}
+
+/// Translate the same C file preprocessed under several configurations, each enabled by the
+/// Cargo feature it is paired with, into a single module. Items that differ between the
+/// configurations are emitted once per configuration with a `#[cfg(feature = "...")]`
+/// attribute, along with the comments of the configuration they were first translated in. The
+/// header enables the features and crates used by any configuration.
+pub fn translate_cfg_variants(
+    variants: Vec<(String, TypedAstContext)>,
+    tcfg: &TranspilerConfig,
+    main_file: PathBuf,
+) -> (String, PragmaVec, CrateSet, Vec<DeclReport>, Vec<HeaderType>) {
+    // `with_globals` sets up a thread-local variable required by the syntax crate.
+    with_globals(Edition::Edition2018, || {
+        let mut merger = CfgMerger::new();
+        // Declarations are reported once, not once per configuration
+        let mut decl_reports: IndexMap<_, DeclReport> = IndexMap::new();
+        let mut header: Option<Translation> = None;
+        let mut unnamed_names = IndexMap::new();
+        // The comments of every configuration go in the same store, so that their positions
+        // don't overlap
+        let mut comment_store = CommentStore::new();
+        let mut remaining_comments: IndexSet<String> = IndexSet::new();
+
+        for (feature, ast_context) in variants {
+            let mut t = Translation::new(ast_context, tcfg, main_file.as_path());
+            t.comment_store.replace(comment_store);
+            translate_decls(&mut t, &mut unnamed_names);
+
+            let mut items: Vec<P<Item>> = Vec::new();
+            let mut new_uses = ItemStore::new();
+            for (file_id, ref mut mod_item_store) in t.items.borrow_mut().iter_mut() {
+                if *file_id != t.main_file {
+                    let mut submodule = make_submodule(
+                        &t.ast_context,
+                        mod_item_store,
+                        *file_id,
+                        &mut new_uses,
+                        &t.mod_names,
+                    );
+                    let comments = t.comment_context.get_remaining_comments(*file_id);
+                    submodule.span = match t
+                        .comment_store
+                        .borrow_mut()
+                        .add_comments(&comments)
+                    {
+                        Some(pos) => submodule.span.with_hi(pos),
+                        None => submodule.span,
+                    };
+                    items.push(submodule);
+                }
+            }
+            let (main_items, foreign_items, uses) = t.items.borrow_mut()[&t.main_file].drain();
+            let (_, _, new_uses) = new_uses.drain();
+            items.extend(uses.into_items());
+            items.extend(new_uses.into_items());
+            items.extend(main_items);
+            remaining_comments.extend(t.comment_context.get_remaining_comments(t.main_file));
+            comment_store = t.comment_store.replace(CommentStore::new());
+
+            merger.add_variant(&feature, items, foreign_items);
+            for report in t.decl_reports.replace(vec![]) {
+                let key = (report.name.clone(), report.span.clone());
+                match decl_reports.entry(key) {
+                    indexmap::map::Entry::Occupied(mut entry) => {
+                        let merged = entry.get_mut();
+                        // Report a failure under any configuration
+                        if merged.outcome == DeclOutcome::Translated {
+                            merged.outcome = report.outcome;
+                            merged.error = report.error;
+                        }
+                        for feature in report.features {
+                            if !merged.features.contains(&feature) {
+                                merged.features.push(feature);
+                            }
+                        }
+                    }
+                    indexmap::map::Entry::Vacant(entry) => {
+                        entry.insert(report);
+                    }
+                }
+            }
+
+            // The header of the merged module needs the features and crates of every
+            // configuration, so accumulate them in the first translation
+            match header {
+                Some(ref first) => {
+                    first.features.borrow_mut().extend(t.features.borrow().iter());
+                    first
+                        .features
+                        .borrow_mut()
+                        .extend(t.type_converter.borrow().features_used());
+                    first
+                        .extern_crates
+                        .borrow_mut()
+                        .extend(t.extern_crates.borrow().iter().cloned());
+                }
+                None => header = Some(t),
+            }
+        }
+
+        let t = header.expect("no preprocessor configurations to translate");
+        let pragmas = t.get_pragmas();
+        let crates = t.extern_crates.borrow().clone();
+        let (items, foreign_items) = merger.drain();
+
+        // Only the comments of the items kept from each configuration are reinserted, so the
+        // comments of items translated identically in several configurations are printed once
+        let mut traverser = comment_store.into_comment_traverser();
+        let foreign_items: Vec<ForeignItem> = foreign_items
+            .into_iter()
+            .map(|fi| traverser.traverse_foreign_item(fi))
+            .collect();
+        let items: Vec<P<Item>> = items
+            .into_iter()
+            .map(|p_i| p_i.map(|i| traverser.traverse_item(i)))
+            .collect();
+        let mut reordered_comment_store = traverser.into_comment_store();
+        let remaining_comments: Vec<String> = remaining_comments.into_iter().collect();
+        reordered_comment_store.add_comments(&remaining_comments);
+
+        // A dummy SourceMap for pprust to look up line numbers in, as in `translate`
+        let sm = SourceMap::new(FilePathMapping::empty());
+        sm.new_source_file(FileName::Custom("<dummy>".to_string()), " ".to_string());
+        let comments = Comments::new(&sm, reordered_comment_store.into_comments());
+
+        let translation = pprust::to_string_with_comments(comments, |s| {
+            print_header(s, &t, t.tcfg.is_binary(main_file.as_path()));
+
+            if !foreign_items.is_empty() {
+                s.print_item(&mk().extern_("C").foreign_items(foreign_items))
+            }
+
+            for x in items {
+                s.print_item(&*x);
+            }
+
+            s.print_remaining_comments();
+        });
+        let decl_reports = decl_reports.into_iter().map(|(_, report)| report).collect();
+        (translation, pragmas, crates, decl_reports, vec![])
     })
 }
 
+/// Identifies an anonymous type across the preprocessor configurations of a file: the file and
+/// position of its declaration, and its index among the anonymous types declared there
+type UnnamedTypeKey = (Option<PathBuf>, u64, u64, usize);
+
+/// Translate the declarations of the C file into the item stores of the translation.
+/// `unnamed_names` holds the names given to anonymous types by the translations of the file under
+/// other preprocessor configurations, which the same types keep in this one.
+fn translate_decls(t: &mut Translation, unnamed_names: &mut IndexMap<UnnamedTypeKey, String>) {
+    let ctx = ExprContext {
+        used: true,
+        is_static: false,
+        is_const: false,
+        decay_ref: DecayRef::Default,
+        is_bitfield_write: false,
+        needs_address: false,
+        expecting_valistimpl: false,
+        ternary_needs_parens: false,
+        expanding_macro: None,
+    };
+
+    t.use_crate(ExternCrate::Libc);
+
+    // Sort the top-level declarations by file and source location so that we
+    // preserve the ordering of all declarations in each file.
+    t.ast_context.sort_top_decls();
+
+    t.locate_comments();
+
+    // Headers often pull in declarations that are unused;
+    // we simplify the translator output by omitting those.
+    t.ast_context.prune_unused_decls();
+
+    if t.tcfg.translate_restrict {
+        t.reference_params = t.find_reference_params();
+    }
+
+    if t.tcfg.stable_variadics {
+        t.slice_variadic_fns = t.find_slice_variadic_fns();
+    }
+
+    if t.tcfg.translate_closed_enums {
+        t.closed_enums = t.find_closed_enums();
+    }
+
+    if t.tcfg.translate_setjmp {
+        t.longjmp_fns = t.find_longjmp_fns();
+    }
+
+    enum Name<'a> {
+        VarName(&'a str),
+        TypeName(&'a str),
+        AnonymousType,
+        NoName,
+    }
+
+    fn some_type_name(s: Option<&str>) -> Name {
+        match s {
+            None => Name::AnonymousType,
+            Some(r) => Name::TypeName(r),
+        }
+    }
+
+    // Used for testing; so that we don't overlap with C function names
+    if let Some(ref prefix) = t.tcfg.prefix_function_names {
+        prefix_names(t, prefix);
+    }
+
+    // Identify typedefs that name unnamed types and collapse the two declarations
+    // into a single name and declaration, eliminating the typedef altogether.
+    let mut prenamed_decls: IndexMap<CDeclId, CDeclId> = IndexMap::new();
+    for (&decl_id, decl) in t.ast_context.iter_decls() {
+        if let CDeclKind::Typedef { ref name, typ, .. } = decl.kind {
+            if let Some(subdecl_id) = t
+                .ast_context
+                .resolve_type(typ.ctype)
+                .kind
+                .as_underlying_decl()
+            {
+                let is_unnamed = match t.ast_context[subdecl_id].kind {
+                    CDeclKind::Struct { name: None, .. }
+                    | CDeclKind::Union { name: None, .. }
+                    | CDeclKind::Enum { name: None, .. } => true,
+
+                    // Detect case where typedef and struct share the same name.
+                    // In this case the purpose of the typedef was simply to eliminate
+                    // the need for the 'struct' tag when refering to the type name.
+                    CDeclKind::Struct {
+                        name: Some(ref target_name),
+                        ..
+                    }
+                    | CDeclKind::Union {
+                        name: Some(ref target_name),
+                        ..
+                    }
+                    | CDeclKind::Enum {
+                        name: Some(ref target_name),
+                        ..
+                    } => name == target_name,
+
+                    _ => false,
+                };
+
+                if is_unnamed
+                    && !prenamed_decls
+                        .values()
+                        .find(|decl_id| *decl_id == &subdecl_id)
+                        .is_some()
+                {
+                    prenamed_decls.insert(decl_id, subdecl_id);
+
+                    t.type_converter
+                        .borrow_mut()
+                        .declare_decl_name(decl_id, name);
+                    t.type_converter
+                        .borrow_mut()
+                        .alias_decl_name(subdecl_id, decl_id);
+                }
+            }
+        }
+    }
+
+    t.ast_context.prenamed_decls = prenamed_decls;
+
+    // Helper function that returns true if there is either a matching typedef or its
+    // corresponding struct/union/enum
+    fn contains(prenamed_decls: &IndexMap<CDeclId, CDeclId>, decl_id: &CDeclId) -> bool {
+        (prenamed_decls.contains_key(decl_id)
+            || prenamed_decls.values().find(|id| *id == decl_id).is_some())
+    }
+
+    // Name anonymous types after the same types of other preprocessor configurations first, so
+    // that the items using them are translated identically and merge
+    let mut unnamed_keys: IndexMap<CDeclId, UnnamedTypeKey> = IndexMap::new();
+    let mut unnamed_counts: HashMap<(Option<PathBuf>, u64, u64), usize> = HashMap::new();
+    for (&decl_id, decl) in t.ast_context.iter_decls() {
+        let is_unnamed = match decl.kind {
+            _ if contains(&t.ast_context.prenamed_decls, &decl_id) => false,
+            CDeclKind::Struct { name: None, .. }
+            | CDeclKind::Union { name: None, .. }
+            | CDeclKind::Enum { name: None, .. } => true,
+            _ => false,
+        };
+        let loc = match decl.loc {
+            Some(ref loc) if is_unnamed => loc,
+            _ => continue,
+        };
+        let file = t
+            .ast_context
+            .file_id(decl)
+            .and_then(|file_id| t.ast_context.get_file_path(file_id))
+            .map(|path| path.to_path_buf());
+        let count = unnamed_counts
+            .entry((file.clone(), loc.begin_line, loc.begin_column))
+            .or_insert(0);
+        let key = (file, loc.begin_line, loc.begin_column, *count);
+        *count += 1;
+
+        if let Some(name) = unnamed_names.get(&key) {
+            t.type_converter
+                .borrow_mut()
+                .declare_decl_name(decl_id, name);
+        }
+        unnamed_keys.insert(decl_id, key);
+    }
+
+    // Populate renamer with top-level names
+    for (&decl_id, decl) in t.ast_context.iter_decls() {
+        let decl_name = match decl.kind {
+            _ if contains(&t.ast_context.prenamed_decls, &decl_id) => Name::NoName,
+            CDeclKind::Struct { ref name, .. } => {
+                some_type_name(name.as_ref().map(String::as_str))
+            }
+            CDeclKind::Enum { ref name, .. } => {
+                some_type_name(name.as_ref().map(String::as_str))
+            }
+            CDeclKind::Union { ref name, .. } => {
+                some_type_name(name.as_ref().map(String::as_str))
+            }
+            CDeclKind::Typedef { ref name, .. } => Name::TypeName(name),
+            CDeclKind::Function { ref name, .. } => Name::VarName(name),
+            CDeclKind::EnumConstant { ref name, .. } => Name::VarName(name),
+            CDeclKind::Variable { ref ident, .. }
+                if t.ast_context.c_decls_top.contains(&decl_id) =>
+            {
+                Name::VarName(ident)
+            }
+            CDeclKind::MacroObject { ref name, .. } => Name::VarName(name),
+            CDeclKind::MacroFunction { ref name, .. } => Name::VarName(name),
+            _ => Name::NoName,
+        };
+        match decl_name {
+            Name::NoName => (),
+            // Named above if it was translated under another configuration
+            Name::AnonymousType
+                if t.type_converter.borrow().resolve_decl_name(decl_id).is_some() => {}
+            Name::AnonymousType => {
+                let name = t
+                    .type_converter
+                    .borrow_mut()
+                    .declare_decl_name(decl_id, "C2RustUnnamed");
+                if let Some(key) = unnamed_keys.remove(&decl_id) {
+                    unnamed_names.insert(key, name);
+                }
+            }
+            Name::TypeName(name) => {
+                t.type_converter
+                    .borrow_mut()
+                    .declare_decl_name(decl_id, name);
+            }
+            Name::VarName(name) => {
+                t.renamer.borrow_mut().insert(decl_id, &name);
+            }
+        }
+    }
+
+    {
+        let convert_type = |decl_id: CDeclId, decl: &CDecl| {
+            let decl_file_id = t.ast_context.file_id(decl);
+            if t.tcfg.reorganize_definitions {
+                *t.cur_file.borrow_mut() = decl_file_id;
+            }
+            match t.convert_decl_reported(ctx, decl_id) {
+                Ok(ConvertedDecl::Item(item)) => {
+                    t.insert_item(item, decl);
+                }
+                Ok(ConvertedDecl::ForeignItem(item)) => {
+                    t.insert_foreign_item(item, decl);
+                }
+                Ok(ConvertedDecl::Items(items)) => {
+                    for item in items {
+                        t.insert_item(item, decl);
+                    }
+                }
+                Ok(ConvertedDecl::NoItem) => {}
+                Err(e) => {
+                    let ref k = t.ast_context.get_decl(&decl_id).map(|x| &x.kind);
+                    let msg = format!("Skipping declaration {:?} due to error: {}", k, e);
+                    translate_failure(&t.tcfg, &msg);
+                }
+            }
+            t.cur_file.borrow_mut().take();
+
+            if t.tcfg.reorganize_definitions
+                && decl_file_id.map_or(false, |id| id != t.main_file)
+            {
+                t.generate_submodule_imports(decl_id, decl_file_id);
+            }
+        };
+
+        // Export all types
+        for (&decl_id, decl) in t.ast_context.iter_decls() {
+            let needs_export = match decl.kind {
+                CDeclKind::Struct { .. } => true,
+                CDeclKind::Enum { .. } => true,
+                CDeclKind::EnumConstant { .. } => true,
+                CDeclKind::Union { .. } => true,
+                CDeclKind::Typedef { .. } =>
+                // Only check the key as opposed to `contains` because the key should be the
+                // typedef id
+                {
+                    !t.ast_context.prenamed_decls.contains_key(&decl_id)
+                }
+                _ => false,
+            };
+            if needs_export {
+                convert_type(decl_id, decl);
+            }
+        }
+    }
+
+    // Export top-level value declarations
+    for top_id in &t.ast_context.c_decls_top {
+        let needs_export = match t.ast_context[*top_id].kind {
+            CDeclKind::Function { is_implicit, .. } => !is_implicit,
+            CDeclKind::Variable { .. } => true,
+            CDeclKind::MacroObject { .. } => t.tcfg.translate_const_macros,
+            CDeclKind::MacroFunction { .. } => t.tcfg.translate_fn_macros,
+            _ => false,
+        };
+        if needs_export {
+            let decl_opt = t.ast_context.get_decl(top_id);
+            let decl = decl_opt.as_ref().unwrap();
+            let decl_file_id = t.ast_context.file_id(decl);
+
+            if t.tcfg.reorganize_definitions
+                && decl_file_id.map_or(false, |id| id != t.main_file)
+            {
+                *t.cur_file.borrow_mut() = decl_file_id;
+            }
+            match t.convert_decl_reported(ctx, *top_id) {
+                Ok(ConvertedDecl::Item(item)) => {
+                    t.insert_item(item, decl);
+                }
+                Ok(ConvertedDecl::ForeignItem(item)) => {
+                    t.insert_foreign_item(item, decl);
+                }
+                Ok(ConvertedDecl::Items(items)) => {
+                    for item in items {
+                        t.insert_item(item, decl);
+                    }
+                }
+                Ok(ConvertedDecl::NoItem) => {}
+                Err(e) => {
+                    let ref decl = t.ast_context.get_decl(top_id);
+                    let msg = match decl {
+                        Some(decl) => {
+                            let decl_identifier = decl.kind.get_name().map_or_else(
+                                || {
+                                    t.ast_context.display_loc(&decl.loc)
+                                        .map_or("Unknown".to_string(), |l| format!("at {}", l))
+                                },
+                                |name| name.clone(),
+                            );
+                            format!("Failed to translate {}: {}", decl_identifier, e)
+                        }
+                        _ => format!("Failed to translate declaration: {}", e,),
+                    };
+                    translate_failure(&t.tcfg, &msg);
+                }
+            }
+            t.cur_file.borrow_mut().take();
+
+            if t.tcfg.reorganize_definitions
+                && decl_file_id.map_or(false, |id| id != t.main_file)
+            {
+                t.generate_submodule_imports(*top_id, decl_file_id);
+            }
+        }
+    }
+
+    // Add the main entry point
+    if let Some(main_id) = t.ast_context.c_main {
+        match t.convert_main(main_id) {
+            Ok(item) => t.items.borrow_mut()[&t.main_file].add_item(item),
+            Err(e) => {
+                let msg = format!("Failed to translate main: {}", e);
+                translate_failure(&t.tcfg, &msg)
+            }
+        }
+    }
+
+    // Initialize global statics when necessary
+    if !t.sectioned_static_initializers.borrow().is_empty() {
+        let (initializer_fn, initializer_static) = t.generate_global_static_init();
+        let store = &mut t.items.borrow_mut()[&t.main_file];
+
+        store.add_item(initializer_fn);
+        store.add_item(initializer_static);
+    }
+}
+
 fn make_submodule(
     ast_context: &TypedAstContext,
     item_store: &mut ItemStore,
@@ -950,7 +1226,7 @@ fn print_header(s: &mut pprust::State, t: &Translation, is_binary: bool) {
     if t.tcfg.emit_modules && !is_binary {
         for c in t.extern_crates.borrow().iter() {
             s.print_item(&mk().use_simple_item(
-                vec![String::new(), ExternCrateDetails::from(*c).ident],
+                ExternCrateDetails::from(*c).use_path(),
                 None as Option<Ident>,
             ));
         }
@@ -1026,6 +1302,18 @@ fn print_header(s: &mut pprust::State, t: &Translation, is_binary: bool) {
             }
 
             s.print_item(&mk().use_glob_item(vec!["", &t.tcfg.crate_name()]));
+        } else {
+            // Runtime support modules are declared at the crate root, which unlike extern
+            // crates is not in scope in every module
+            for c in t.extern_crates.borrow().iter() {
+                let extern_crate = ExternCrateDetails::from(*c);
+                if extern_crate.runtime.is_some() {
+                    s.print_item(&mk().use_simple_item(
+                        extern_crate.use_path(),
+                        None as Option<Ident>,
+                    ));
+                }
+            }
         }
     }
 }
@@ -1094,6 +1382,12 @@ impl<'c> Translation<'c> {
         if tcfg.translate_valist {
             type_converter.translate_valist = true
         }
+        if tcfg.translate_complex {
+            type_converter.translate_complex = true
+        }
+        if tcfg.scalar_simd_fallback && !ast_context.targets_x86() {
+            type_converter.translate_vector_lanes = true
+        }
 
         let main_file = ast_context.find_file_id(main_file).unwrap_or(0);
         let items = indexmap!{main_file => ItemStore::new()};
@@ -1120,11 +1414,21 @@ impl<'c> Translation<'c> {
             function_context: RefCell::new(FunContext::new()),
             potential_flexible_array_members: RefCell::new(IndexSet::new()),
             macro_expansions: RefCell::new(IndexMap::new()),
+            macro_arg_substitutions: RefCell::new(IndexMap::new()),
+            reference_params: HashMap::new(),
+            slice_variadic_fns: IndexSet::new(),
+            closed_enums: IndexSet::new(),
+            longjmp_fns: IndexSet::new(),
+            stmt_expr_values: RefCell::new(IndexMap::new()),
+            decl_reports: RefCell::new(vec![]),
+            decl_features: RefCell::new(IndexSet::new()),
+            replaced_decls: RefCell::new(IndexMap::new()),
             comment_context,
             comment_store: RefCell::new(CommentStore::new()),
             spans: HashMap::new(),
             sectioned_static_initializers: RefCell::new(Vec::new()),
             items: RefCell::new(items),
+            header_type_items: RefCell::new(vec![]),
             mod_names: RefCell::new(IndexMap::new()),
             main_file,
             extern_crates: RefCell::new(IndexSet::new()),
@@ -1154,9 +1458,42 @@ impl<'c> Translation<'c> {
         f(item_store)
     }
 
-    /// Called when translation makes use of a language feature that will require a feature-gate.
-    pub fn use_feature(&self, feature: &'static str) {
-        self.features.borrow_mut().insert(feature);
+    /// Called when translation makes use of a language feature that will require a feature-gate.
+    pub fn use_feature(&self, feature: &'static str) {
+        self.features.borrow_mut().insert(feature);
+        self.decl_features.borrow_mut().insert(feature);
+    }
+
+    /// Translate a declaration, recording the outcome for the translation report if one was
+    /// requested
+    fn convert_decl_reported(
+        &self,
+        ctx: ExprContext,
+        decl_id: CDeclId,
+    ) -> Result<ConvertedDecl, TranslationError> {
+        if self.tcfg.report.is_none() {
+            return self.convert_decl(ctx, decl_id);
+        }
+
+        self.decl_features.borrow_mut().clear();
+        let result = self.convert_decl(ctx, decl_id);
+
+        let replaced = self.replaced_decls.borrow_mut().remove(&decl_id);
+        let (outcome, error) = match (&result, replaced) {
+            (Err(e), _) => (DeclOutcome::Skipped, Some(e.into())),
+            (Ok(_), Some(e)) => (DeclOutcome::ReplacedWithExtern, Some((&e).into())),
+            (Ok(_), None) => (DeclOutcome::Translated, None),
+        };
+        let decl = &self.ast_context[decl_id];
+        self.decl_reports.borrow_mut().push(DeclReport {
+            name: decl.kind.get_name().cloned(),
+            span: self.ast_context.display_loc(&decl.loc).map(|loc| loc.to_string()),
+            outcome,
+            error,
+            features: self.decl_features.borrow().iter().map(|f| f.to_string()).collect(),
+        });
+
+        result
     }
 
     pub fn get_pragmas(&self) -> PragmaVec {
@@ -1514,7 +1851,14 @@ impl<'c> Translation<'c> {
                 let field_entries =
                     self.convert_struct_fields(decl_id, fields, platform_byte_size)?;
 
-                let mut derives = vec!["Copy", "Clone"];
+                // Atomic fields cannot be copied, so neither can the struct holding them
+                let copyable = fields
+                    .iter()
+                    .all(|field_id| match self.ast_context.index(*field_id).kind {
+                        CDeclKind::Field { typ, .. } => !self.ast_context.contains_atomic(typ.ctype),
+                        _ => unreachable!("Found non-field in record field list"),
+                    });
+                let mut derives = if copyable { vec!["Copy", "Clone"] } else { vec![] };
                 let has_bitfields = fields
                     .iter()
                     .any(|field_id| match self.ast_context.index(*field_id).kind {
@@ -1573,10 +1917,11 @@ impl<'c> Translation<'c> {
                         // TODO: copy others from `reprs` above
                     ];
                     let repr_attr = mk().meta_item(vec!["repr"], MetaItemKind::List(outer_reprs));
-                    let outer_field = mk().pub_().enum_field(mk().ident_ty(inner_name));
+                    let outer_field = mk().pub_().enum_field(mk().ident_ty(inner_name.clone()));
+                    let outer_derives = if copyable { vec!["Copy", "Clone"] } else { vec![] };
                     let outer_struct = mk().span(s)
                         .pub_()
-                        .call_attr("derive", vec!["Copy", "Clone"])
+                        .call_attr("derive", outer_derives)
                         .meta_item_attr(AttrStyle::Outer, repr_attr)
                         .struct_item(name, vec![outer_field], true);
 
@@ -1594,18 +1939,32 @@ impl<'c> Translation<'c> {
                         .call_attr("allow", vec!["dead_code", "non_upper_case_globals"])
                         .const_item(padding_name, padding_ty, padding_value);
 
-                    let structs = vec![outer_struct, inner_struct, padding_const];
+                    let mut structs = vec![outer_struct, inner_struct, padding_const];
+                    // Flexible array members of packed structs may be unaligned,
+                    // so we can't safely take a slice of them
+                    if max_field_alignment.is_none() {
+                        structs.extend(
+                            self.convert_flexible_array_accessors(decl_id, &inner_name, fields, s)?,
+                        );
+                    }
                     Ok(ConvertedDecl::Items(structs))
                 } else {
                     assert!(!self.ast_context.has_inner_struct_decl(decl_id));
+                    let accessors = if max_field_alignment.is_none() {
+                        self.convert_flexible_array_accessors(decl_id, &name, fields, s)?
+                    } else {
+                        None
+                    };
                     let repr_attr = mk().meta_item(vec!["repr"], MetaItemKind::List(reprs));
-                    Ok(ConvertedDecl::Item(
-                        mk().span(s)
-                            .pub_()
-                            .call_attr("derive", derives)
-                            .meta_item_attr(AttrStyle::Outer, repr_attr)
-                            .struct_item(name, field_entries, false),
-                    ))
+                    let struct_item = mk().span(s)
+                        .pub_()
+                        .call_attr("derive", derives)
+                        .meta_item_attr(AttrStyle::Outer, repr_attr)
+                        .struct_item(name, field_entries, false);
+                    match accessors {
+                        Some(accessors) => Ok(ConvertedDecl::Items(vec![struct_item, accessors])),
+                        None => Ok(ConvertedDecl::Item(struct_item)),
+                    }
                 }
             }
 
@@ -1623,6 +1982,11 @@ impl<'c> Translation<'c> {
                 for &x in fields {
                     let field_decl = self.ast_context.index(x);
                     match field_decl.kind {
+                        CDeclKind::Field { typ, .. } if self.ast_context.contains_atomic(typ.ctype) => {
+                            return Err(TranslationError::generic(
+                                "Unions with atomic fields are not supported",
+                            ))
+                        }
                         CDeclKind::Field { ref name, typ, .. } => {
                             let name = self
                                 .type_converter
@@ -1672,6 +2036,9 @@ impl<'c> Translation<'c> {
                     .borrow()
                     .resolve_decl_name(decl_id)
                     .expect("Enums should already be renamed");
+                if self.closed_enums.contains(&decl_id) {
+                    return self.convert_closed_enum(decl_id, s, enum_name);
+                }
                 let ty = self.convert_type(integral_type.ctype)?;
                 Ok(ConvertedDecl::Item(
                     mk().span(s).pub_().type_item(enum_name, ty),
@@ -1679,12 +2046,16 @@ impl<'c> Translation<'c> {
             }
 
             CDeclKind::EnumConstant { value, .. } => {
+                let enum_id = self.ast_context.parents[&decl_id];
+                // The constants of closed enums are their variants
+                if self.closed_enums.contains(&enum_id) {
+                    return Ok(ConvertedDecl::NoItem);
+                }
                 let name = self
                     .renamer
                     .borrow_mut()
                     .get(&decl_id)
                     .expect("Enum constant not named");
-                let enum_id = self.ast_context.parents[&decl_id];
                 let enum_name = self
                     .type_converter
                     .borrow()
@@ -1727,15 +2098,22 @@ impl<'c> Translation<'c> {
                     .get(&decl_id)
                     .expect("Functions should already be renamed");
 
-                if self.import_simd_function(new_name)? {
+                if self.import_simd_function(new_name)?
+                    || self.import_neon_decl(decl_id, new_name)?
+                {
                     return Ok(ConvertedDecl::NoItem);
                 }
 
                 let (ret, is_var): (Option<CQualTypeId>, bool) =
                     match self.ast_context.resolve_type(typ).kind {
-                        CTypeKind::Function(ret, _, is_var, is_noreturn, _) => {
-                            (if is_noreturn { None } else { Some(ret) }, is_var)
+                        CTypeKind::Function(_, _, is_var, is_noreturn, _)
+                            if is_noreturn && !self.longjmp_fns.contains(&decl_id) =>
+                        {
+                            (None, is_var)
                         }
+                        // A `noreturn` function which may start a `longjmp` returns while
+                        // the jump is pending
+                        CTypeKind::Function(ret, _, is_var, _, _) => (Some(ret), is_var),
                         ref k => {
                             return Err(format_err!(
                                 "Type of function {:?} was not a function type, got {:?}",
@@ -1760,17 +2138,28 @@ impl<'c> Translation<'c> {
                 }
 
                 let is_main = self.ast_context.c_main == Some(decl_id);
+                let is_slice_var = self.slice_variadic_fns.contains(&decl_id);
 
                 let converted_function = self.convert_function(
-                    ctx, s, is_global, is_inline, is_main, is_var, is_extern,
+                    ctx, s, is_global, is_inline, is_main, is_var, is_slice_var, is_extern,
                     new_name, name, &args, ret, body, attrs,
                 );
 
                 converted_function.or_else(|e| match self.tcfg.replace_unsupported_decls {
                     ReplaceMode::Extern if body.is_none() => self.convert_function(
-                        ctx, s, is_global, false, is_main, is_var, is_extern,
+                        ctx, s, is_global, false, is_main, is_var, false, is_extern,
                         new_name, name, &args, ret, None, attrs,
                     ),
+                    // A definition with external linkage that fails to translate can still be
+                    // called through an `extern` declaration of the C definition
+                    ReplaceMode::Extern if is_global && !is_main => {
+                        let converted = self.convert_function(
+                            ctx, s, is_global, false, is_main, is_var, false, is_extern,
+                            new_name, name, &args, ret, None, attrs,
+                        )?;
+                        self.replaced_decls.borrow_mut().insert(decl_id, e);
+                        Ok(converted)
+                    }
                     _ => Err(e),
                 })
             }
@@ -1782,7 +2171,8 @@ impl<'c> Translation<'c> {
                     .resolve_decl_name(decl_id)
                     .unwrap();
 
-                if self.import_simd_typedef(new_name) {
+                if self.import_simd_typedef(new_name) || self.import_neon_decl(decl_id, new_name)?
+                {
                     return Ok(ConvertedDecl::NoItem);
                 }
 
@@ -1963,6 +2353,13 @@ impl<'c> Translation<'c> {
                 "This should be handled in 'convert_decl_stmt'",
             )),
 
+            CDeclKind::MacroObject { .. } | CDeclKind::MacroFunction { .. }
+                if !self.macro_enabled(decl_id) =>
+            {
+                self.macro_expansions.borrow_mut().insert(decl_id, None);
+                Ok(ConvertedDecl::NoItem)
+            }
+
             CDeclKind::MacroObject {
                 ref replacements, ..
             } => {
@@ -1983,7 +2380,7 @@ impl<'c> Translation<'c> {
                     Ok((replacement, ty)) => {
                         trace!("  to {:?}", replacement);
 
-                        let expansion = MacroExpansion {ty};
+                        let expansion = MacroExpansion { ty, param_tys: vec![] };
                         self.macro_expansions.borrow_mut().insert(decl_id, Some(expansion));
                         let ty = self.convert_type(ty)?;
 
@@ -2001,6 +2398,47 @@ impl<'c> Translation<'c> {
                 }
             }
 
+            CDeclKind::MacroFunction {
+                ref parameters,
+                ref replacements,
+                ref arguments,
+                ..
+            } => {
+                let name = self
+                    .renamer
+                    .borrow_mut()
+                    .get(&decl_id)
+                    .expect("Macro function not named");
+
+                trace!("Translating function-like macro {:?}: {:?}", decl_id, self.ast_context[decl_id]);
+
+                let maybe_function = self.canonical_macro_function(
+                    ctx.set_expanding_macro(decl_id),
+                    parameters,
+                    replacements,
+                    arguments,
+                );
+
+                match maybe_function {
+                    Ok((decl, block, expansion)) => {
+                        self.macro_expansions.borrow_mut().insert(decl_id, Some(expansion));
+
+                        Ok(ConvertedDecl::Item(
+                            mk().span(s)
+                                .pub_()
+                                .single_attr("inline")
+                                .unsafe_()
+                                .fn_item(name, decl, block),
+                        ))
+                    }
+                    Err(e) => {
+                        self.macro_expansions.borrow_mut().insert(decl_id, None);
+                        info!("Could not translate function-like macro {}: {}", name, e);
+                        Ok(ConvertedDecl::NoItem)
+                    }
+                }
+            }
+
             // Do not translate non-canonical decls. They will be translated at
             // their canonical declaration.
             CDeclKind::NonCanonicalDecl { .. } => Ok(ConvertedDecl::NoItem),
@@ -2054,6 +2492,149 @@ impl<'c> Translation<'c> {
         // common type to minimize casts.
     }
 
+    /// Is translation of this kind of macro enabled?
+    fn macro_enabled(&self, decl_id: CDeclId) -> bool {
+        match self.ast_context[decl_id].kind {
+            CDeclKind::MacroObject { .. } => self.tcfg.translate_const_macros,
+            CDeclKind::MacroFunction { .. } => self.tcfg.translate_fn_macros,
+            _ => false,
+        }
+    }
+
+    /// Build a single Rust function out of the expansions of a function-like
+    /// macro. This only succeeds if every expansion agrees on the types of
+    /// the arguments and the result, the body does not capture local
+    /// variables from the expansion site, and all arguments are pure rvalues,
+    /// so that evaluating each of them once at the call site is equivalent to
+    /// the expansion.
+    fn canonical_macro_function(
+        &self,
+        ctx: ExprContext,
+        parameters: &[String],
+        replacements: &[CExprId],
+        arguments: &[Vec<(usize, CExprId)>],
+    ) -> Result<(P<FnDecl>, P<Block>, MacroExpansion), TranslationError> {
+        let expr_ty = |expr_id: CExprId| -> Result<CTypeId, TranslationError> {
+            let ty = self.ast_context[expr_id].kind.get_type()
+                .ok_or_else(|| format_err!("Invalid expression type"))?;
+            Ok(self.ast_context.resolve_type_id(ty))
+        };
+
+        let mut signature: Option<(Vec<CTypeId>, CTypeId)> = None;
+        for (&replacement, args) in replacements.iter().zip(arguments) {
+            let mut param_tys = vec![None; parameters.len()];
+            for &(param, arg) in args {
+                if self.ast_context[arg].kind.lrvalue() == LRValue::LValue {
+                    return Err(format_err!("Macro argument is used as an lvalue").into());
+                }
+                if !self.ast_context.is_expr_pure(arg) {
+                    return Err(format_err!("Macro argument is not a pure expression").into());
+                }
+                let ty = Some(expr_ty(arg)?);
+                match param_tys.get_mut(param) {
+                    Some(slot) if slot.is_none() => *slot = ty,
+                    Some(slot) if *slot == ty => {}
+                    _ => return Err(format_err!("Macro parameter is used at different types").into()),
+                }
+            }
+            let param_tys = param_tys
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| format_err!("Macro parameter is not used as an expression"))?;
+
+            let cur_signature = (param_tys, expr_ty(replacement)?);
+            match signature {
+                Some(ref sig) if *sig != cur_signature => {
+                    return Err(format_err!("Not all macro expansions have the same types").into())
+                }
+                Some(_) => {}
+                None => signature = Some(cur_signature),
+            }
+        }
+        let (param_tys, ret_ty) =
+            signature.ok_or_else(|| format_err!("Could not find an expansion of the macro"))?;
+
+        // Use the first expansion as the body of the function
+        let replacement = replacements[0];
+        let args = &arguments[0];
+
+        // The body may only refer to the parameters and to declarations that
+        // are visible outside of the expansion site.
+        let arg_nodes: IndexSet<SomeId> = args
+            .iter()
+            .flat_map(|&(_, arg)| DFExpr::new(&self.ast_context, arg.into()))
+            .collect();
+        let mut body_decls = IndexSet::new();
+        for node in DFExpr::new(&self.ast_context, replacement.into()) {
+            if arg_nodes.contains(&node) {
+                continue;
+            }
+            match node {
+                SomeId::Decl(decl_id) => {
+                    body_decls.insert(decl_id);
+                }
+                SomeId::Expr(expr_id) => {
+                    if let CExprKind::DeclRef(_, decl_id, _) = self.ast_context[expr_id].kind {
+                        match self.ast_context[decl_id].kind {
+                            CDeclKind::Variable {
+                                has_static_duration: false,
+                                has_thread_duration: false,
+                                ..
+                            } if !body_decls.contains(&decl_id) => {
+                                return Err(format_err!("Macro refers to a local variable").into())
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let is_void_ret = self.ast_context[ret_ty].kind == CTypeKind::Void;
+
+        self.with_scope(|| {
+            let names: Vec<String> = parameters
+                .iter()
+                .map(|param| self.renamer.borrow_mut().pick_name(param))
+                .collect();
+
+            let fn_args = names
+                .iter()
+                .zip(&param_tys)
+                .map(|(name, &ty)| Ok(mk().arg(self.convert_type(ty)?, mk().ident_pat(name))))
+                .collect::<Result<Vec<_>, TranslationError>>()?;
+
+            let ret = if is_void_ret {
+                FunctionRetTy::Default(DUMMY_SP)
+            } else {
+                FunctionRetTy::Ty(self.convert_type(ret_ty)?)
+            };
+
+            for &(param, arg) in args {
+                self.macro_arg_substitutions
+                    .borrow_mut()
+                    .insert(arg, names[param].clone());
+            }
+            let body = if is_void_ret {
+                self.convert_expr(ctx.unused(), replacement)
+                    .map(|body| stmts_block(body.into_stmts()))
+            } else {
+                self.convert_expr(ctx.used(), replacement)
+                    .map(|body| body.to_block())
+            };
+            for &(_, arg) in args {
+                self.macro_arg_substitutions.borrow_mut().remove(&arg);
+            }
+
+            let expansion = MacroExpansion {
+                ty: ret_ty,
+                param_tys: param_tys.clone(),
+            };
+            Ok((mk().fn_decl(fn_args, ret), body?, expansion))
+        })
+    }
+
     fn convert_function(
         &self,
         ctx: ExprContext,
@@ -2062,6 +2643,7 @@ impl<'c> Translation<'c> {
         is_inline: bool,
         is_main: bool,
         is_variadic: bool,
+        is_slice_variadic: bool,
         is_extern: bool,
         new_name: &str,
         name: &str,
@@ -2079,6 +2661,12 @@ impl<'c> Translation<'c> {
             for &(decl_id, ref var, typ) in arguments {
                 let (ty, mutbl, _) = self.convert_variable(ctx, None, typ)?;
 
+                // `restrict` pointers which are only dereferenced may become references
+                let (ty, mutbl) = match self.convert_reference_param_type(decl_id, typ)? {
+                    Some(ref_ty) => (ref_ty, Mutability::Immutable),
+                    None => (ty, mutbl),
+                };
+
                 let pat = if var.is_empty() {
                     mk().wild_pat()
                 } else {
@@ -2107,11 +2695,16 @@ impl<'c> Translation<'c> {
             if is_variadic {
                 // function definitions
                 if let Some(body_id) = body {
-                    let arg_va_list_name = self.register_va_decls(body_id);
+                    let arg_va_list_name = self.register_va_decls(body_id, is_slice_variadic);
 
-                    // FIXME: detect mutability requirements.
-                    let pat = mk().set_mutbl(Mutability::Mutable).ident_pat(arg_va_list_name);
-                    args.push(mk().arg(mk().cvar_args_ty(), pat));
+                    if is_slice_variadic {
+                        let pat = mk().ident_pat(arg_va_list_name);
+                        args.push(mk().arg(self.var_args_slice_ty(), pat));
+                    } else {
+                        // FIXME: detect mutability requirements.
+                        let pat = mk().set_mutbl(Mutability::Mutable).ident_pat(arg_va_list_name);
+                        args.push(mk().arg(mk().cvar_args_ty(), pat));
+                    }
                 } else {  // function declarations
                     args.push(mk().arg(mk().cvar_args_ty(), mk().wild_pat()));
                 }
@@ -2133,6 +2726,8 @@ impl<'c> Translation<'c> {
             } else {
                 FunctionRetTy::Ty(ret)
             };
+            self.function_context.borrow_mut().returns_value =
+                !is_void_ret && return_type.is_some();
 
             let decl = mk().fn_decl(args, ret);
 
@@ -2182,10 +2777,15 @@ impl<'c> Translation<'c> {
                     // extern inlines, which become subject to their gnu89 visibility (private)
 
                     mk_linkage(false, new_name, name).extern_("C").pub_()
-                } else if self.cur_file.borrow().is_some() {
-                    mk().extern_("C").pub_()
                 } else {
-                    mk().extern_("C")
+                    // A slice of variadic arguments is not FFI-safe, but these functions are
+                    // only called from Rust anyway
+                    let mk_ = if is_slice_variadic { mk() } else { mk().extern_("C") };
+                    if self.cur_file.borrow().is_some() {
+                        mk_.pub_()
+                    } else {
+                        mk_
+                    }
                 };
 
                 for attr in attrs {
@@ -2197,6 +2797,10 @@ impl<'c> Translation<'c> {
                     };
                 }
 
+                if self.uses_neon(body) {
+                    mk_ = mk_.call_attr("cfg", vec!["target_arch = \"aarch64\""]);
+                }
+
                 // If this function is just a regular inline
                 if is_inline && !attrs.contains(&c_ast::Attribute::AlwaysInline) {
                     mk_ = mk_.single_attr("inline");
@@ -2440,6 +3044,40 @@ impl<'c> Translation<'c> {
         false
     }
 
+    /// Convert the head of a C `for` loop over a range, `for (T i = start; i < end; i++)`, into
+    /// the pattern and the range of a Rust `for` loop. This declares the induction variable in
+    /// the current scope.
+    pub fn convert_for_range_head(
+        &self,
+        ctx: ExprContext,
+        var: CDeclId,
+        start: CExprId,
+        end: CExprId,
+        inclusive: bool,
+    ) -> Result<WithStmts<(P<Pat>, P<Expr>)>, TranslationError> {
+        let ident = match self.ast_context.index(var).kind {
+            CDeclKind::Variable { ref ident, .. } => ident,
+            _ => return Err(format_err!("Loop variable {:?} is not a variable", var).into()),
+        };
+
+        let start = self.convert_expr(ctx.used(), start)?;
+        let rust_name = self
+            .renamer
+            .borrow_mut()
+            .insert(var, ident)
+            .expect(&format!("Failed to insert variable '{}'", ident));
+        let end = self.convert_expr(ctx.used(), end)?;
+
+        start.and_then(|start| {
+            Ok(end.map(|end| {
+                (
+                    mk().ident_pat(rust_name),
+                    mk().range_expr(start, end, inclusive),
+                )
+            }))
+        })
+    }
+
     pub fn convert_decl_stmt_info(
         &self,
         ctx: ExprContext,
@@ -2517,7 +3155,9 @@ impl<'c> Translation<'c> {
                 if self.ast_context.is_va_list(typ.ctype) {
                     // translate `va_list` variables to `VaListImpl`s and omit the initializer.
                     let pat_mut = mk().set_mutbl("mut").ident_pat(rust_name.clone());
-                    let ty = {
+                    let ty = if self.function_context.borrow().va_args_slice {
+                        mk().path_ty(vec!["c2rust_varargs", "VaList"])
+                    } else {
                         let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
                         let path = vec!["", std_or_core, "ffi", "VaListImpl"];
                         mk().path_ty(path)
@@ -2881,6 +3521,92 @@ impl<'c> Translation<'c> {
         Ok(mk().call_expr(read_volatile_expr, vec![addr_lhs]))
     }
 
+    /// Take the address of (part of) a field of a packed struct. Such places may be misaligned,
+    /// so we can't borrow them; instead, we offset the address of the enclosing struct by the
+    /// field's offset: `(&mut s as *mut _ as *mut u8).offset(4) as *mut libc::c_int`
+    pub fn convert_packed_addr_of(
+        &self,
+        ctx: ExprContext,
+        expr: CExprId,
+        mutbl: Mutability,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        if ctx.is_static {
+            return Err(format_translation_err!(
+                self.ast_context.display_loc(&self.ast_context[expr].loc),
+                "Cannot take the address of a packed struct field in a static initializer",
+            ));
+        }
+        let ptr_ty = |ty: P<Ty>| mk().set_mutbl(mutbl).ptr_ty(ty);
+        let ty = self.ast_context[expr]
+            .kind
+            .get_type()
+            .ok_or_else(|| format_err!("bad packed place type"))?;
+
+        match self.ast_context[expr].kind {
+            CExprKind::Paren(_, e) => self.convert_packed_addr_of(ctx, e, mutbl),
+
+            CExprKind::Member(_, base, field, kind, _) => {
+                let base = match kind {
+                    MemberKind::Arrow => self.convert_expr(ctx.used(), base)?,
+                    MemberKind::Dot if self.ast_context.is_packed_place(base) => {
+                        self.convert_packed_addr_of(ctx, base, mutbl)?
+                    }
+                    MemberKind::Dot => {
+                        let base = self.convert_expr(ctx.used().set_needs_address(true), base)?;
+                        base.map(|b| {
+                            mk().cast_expr(
+                                mk().set_mutbl(mutbl).addr_of_expr(b),
+                                ptr_ty(mk().infer_ty()),
+                            )
+                        })
+                    }
+                };
+                let offset = match self.ast_context[field].kind {
+                    CDeclKind::Field {
+                        platform_bit_offset,
+                        ..
+                    } => platform_bit_offset / 8,
+                    _ => return Err(format_err!("Expected a field").into()),
+                };
+                let field_ty = self.convert_type(ty)?;
+                Ok(base.map(|base| {
+                    let bytes = mk().cast_expr(base, ptr_ty(mk().path_ty(vec!["u8"])));
+                    let offset = mk().lit_expr(mk().int_lit(offset.into(), LitIntType::Unsuffixed));
+                    let field = mk().method_call_expr(bytes, "offset", vec![offset]);
+                    mk().cast_expr(field, ptr_ty(field_ty))
+                }))
+            }
+
+            CExprKind::ArraySubscript(_, lhs, rhs, _) => {
+                let array_decay = |e: CExprId| match self.ast_context[e].kind {
+                    CExprKind::ImplicitCast(_, arr, CastKind::ArrayToPointerDecay, _, _)
+                        if self.ast_context.is_packed_place(arr) =>
+                    {
+                        Some(arr)
+                    }
+                    _ => None,
+                };
+                let (arr, index) = match (array_decay(lhs), array_decay(rhs)) {
+                    (Some(arr), _) => (arr, rhs),
+                    (None, Some(arr)) => (arr, lhs),
+                    (None, None) => return Err(format_err!("Expected a packed array").into()),
+                };
+                let elt_ty = self.convert_type(ty)?;
+                let arr = self.convert_packed_addr_of(ctx, arr, mutbl)?;
+                let index = self.convert_expr(ctx.used(), index)?;
+                arr.and_then(|arr| {
+                    Ok(index.map(|index| {
+                        let elts = mk().cast_expr(arr, ptr_ty(elt_ty));
+                        let index = mk().cast_expr(index, mk().path_ty(vec!["isize"]));
+                        mk().method_call_expr(elts, "offset", vec![index])
+                    }))
+                })
+            }
+
+            _ => Err(format_err!("Expected a packed struct field").into()),
+        }
+    }
+
     // Compute the offset multiplier for variable length array indexing
     // Rust type: usize
     pub fn compute_size_of_expr(&self, type_id: CTypeId) -> Option<P<Expr>> {
@@ -3059,7 +3785,11 @@ impl<'c> Translation<'c> {
 
         trace!("Converting expr {:?}: {:?}", expr_id, self.ast_context[expr_id]);
 
-        if self.tcfg.translate_const_macros {
+        if let Some(param) = self.macro_arg_substitutions.borrow().get(&expr_id) {
+            return Ok(WithStmts::new_val(mk().path_expr(vec![param.clone()])));
+        }
+
+        if self.tcfg.translate_const_macros || self.tcfg.translate_fn_macros {
             if let Some(converted) = self.convert_macro_expansion(ctx, expr_id)? {
                 return Ok(converted);
             }
@@ -3072,11 +3802,27 @@ impl<'c> Translation<'c> {
             CExprKind::BadExpr => Err(TranslationError::generic(
                 "convert_expr: expression kind not supported",
             )),
+            // The Rust SIMD functions which shuffles correspond to only exist on x86
+            CExprKind::ShuffleVector(_, ref child_expr_ids)
+                if self.tcfg.scalar_simd_fallback && !self.ast_context.targets_x86() =>
+            {
+                self.convert_shuffle_vector_fallback(ctx, child_expr_ids)
+            }
             CExprKind::ShuffleVector(_, ref child_expr_ids) => self
                 .convert_shuffle_vector(ctx, child_expr_ids)
+                .or_else(|e| {
+                    if self.tcfg.scalar_simd_fallback {
+                        self.convert_shuffle_vector_fallback(ctx, child_expr_ids)
+                    } else {
+                        Err(e)
+                    }
+                })
                 .map_err(|e| {
                     TranslationError::new(self.ast_context.display_loc(src_loc), e.context(TranslationErrorKind::OldLLVMSimd))
                 }),
+            CExprKind::ConvertVector(ty, ref child_expr_ids) if self.tcfg.scalar_simd_fallback => {
+                self.convert_vector_fallback(ctx, ty, child_expr_ids)
+            }
             CExprKind::ConvertVector(..) => {
                 Err(TranslationError::generic("convert vector not supported"))
             }
@@ -3126,8 +3872,16 @@ impl<'c> Translation<'c> {
                     .get(&decl_id)
                     .ok_or_else(|| format_err!("name not declared: '{}'", varname))?;
 
+                // Constants of closed enums are variants, referred to through their enum
+                let is_closed_variant = match decl {
+                    CDeclKind::EnumConstant { .. } => {
+                        self.closed_enums.contains(&self.ast_context.parents[&decl_id])
+                    }
+                    _ => false,
+                };
+
                 // Import the referenced global decl into our submodule
-                if self.tcfg.reorganize_definitions {
+                if self.tcfg.reorganize_definitions && !is_closed_variant {
                     if let Some(cur_file) = self.cur_file.borrow().as_ref() {
                         self.add_import(*cur_file, decl_id, &rustname);
                         // match decl {
@@ -3137,7 +3891,11 @@ impl<'c> Translation<'c> {
                     }
                 }
 
-                let mut val = mk().path_expr(vec![rustname]);
+                let mut val = if is_closed_variant {
+                    mk().path_expr(self.closed_enum_variant_path(decl_id))
+                } else {
+                    mk().path_expr(vec![rustname])
+                };
 
                 // If the variable is volatile and used as something that isn't an LValue, this
                 // constitutes a volatile read.
@@ -3154,8 +3912,11 @@ impl<'c> Translation<'c> {
                 }
 
                 // Most references to the va_list should refer to the VaList
-                // type, not VaListImpl
-                if !ctx.expecting_valistimpl && self.ast_context.is_va_list(qual_ty.ctype) {
+                // type, not VaListImpl. `c2rust_varargs::VaList` plays both roles.
+                if !ctx.expecting_valistimpl
+                    && self.ast_context.is_va_list(qual_ty.ctype)
+                    && !self.function_context.borrow().va_args_slice
+                {
                     val = mk().method_call_expr(val, "as_va_list", Vec::<P<Expr>>::new());
                 }
 
@@ -3275,6 +4036,18 @@ impl<'c> Translation<'c> {
                     .get_qual_type()
                     .ok_or_else(|| format_err!("bad source type"))?;
 
+                // Arrays in packed structs can't be borrowed to call `as_ptr`, so compute the
+                // address of their first element instead
+                if kind == CastKind::ArrayToPointerDecay && self.ast_context.is_packed_place(expr) {
+                    let mutbl = match self.ast_context.get_pointee_qual_type(ty.ctype) {
+                        Some(pointee) if pointee.qualifiers.is_const => Mutability::Immutable,
+                        _ => Mutability::Mutable,
+                    };
+                    let target_ty = self.convert_type(ty.ctype)?;
+                    let addr = self.convert_packed_addr_of(ctx, expr, mutbl)?;
+                    return Ok(addr.map(|addr| mk().cast_expr(addr, target_ty)));
+                }
+
                 let val = if is_explicit {
                     let stmts = self.compute_variable_array_sizes(ctx, ty.ctype)?;
                     let mut val = self.convert_expr(ctx, expr)?;
@@ -3495,10 +4268,16 @@ impl<'c> Translation<'c> {
                     self.ast_context[func].kind.get_type()
                         .ok_or_else(|| format_err!("Invalid callee expression {:?}", func))?
                 ).map(|ty| &self.ast_context.resolve_type(ty.ctype).kind);
-                let is_variadic = match fn_ty {
-                    Some(CTypeKind::Function(_, _, is_variadic, _, _)) => *is_variadic,
-                    _ => false,
+                let (is_variadic, num_params) = match fn_ty {
+                    Some(CTypeKind::Function(_, params, is_variadic, _, _)) => {
+                        (*is_variadic, params.len())
+                    }
+                    _ => (false, 0),
                 };
+                let reference_args = self.callee_reference_params(func);
+                let slice_variadic_params = self.callee_slice_variadic_params(func);
+                let jump = self.call_jump(func);
+
                 let func = match self.ast_context[func].kind {
                     // Direct function call
                     CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _)
@@ -3509,6 +4288,16 @@ impl<'c> Translation<'c> {
                             _ => false,
                         } =>
                     {
+                        if let CExprKind::DeclRef(_, decl_id, _) = self.ast_context[fexp].kind {
+                            if let CDeclKind::Function { ref name, body: None, .. } = self.ast_context[decl_id].kind {
+                                if let Some(val) = self.convert_complex_libm_call(ctx, name, args)? {
+                                    return Ok(val);
+                                }
+                                if let Some(val) = self.convert_setjmp_call(ctx, expr_id, name, args)? {
+                                    return Ok(val);
+                                }
+                            }
+                        }
                         self.convert_expr(ctx.used(), fexp)?
                     }
 
@@ -3570,13 +4359,23 @@ impl<'c> Translation<'c> {
                     // We want to decay refs only when function is variadic
                     ctx.decay_ref = DecayRef::from(is_variadic);
 
-                    let args = self.convert_exprs(ctx.used(), args)?;
+                    let c_args = args;
+                    let args = self.convert_exprs(ctx.used(), c_args)?;
+                    let args = args.map(|args| self.convert_reference_args(&reference_args, args));
+                    let args = args.map(|args| match slice_variadic_params {
+                        Some(num_params) => self.convert_slice_variadic_args(num_params, args),
+                        None if is_variadic => {
+                            self.convert_long_double_var_args(num_params, c_args, args)
+                        }
+                        None => args,
+                    });
 
                     let res: Result<_, TranslationError> = Ok(
                         args.map(|args| mk().call_expr(func, args))
                     );
                     res
                 })?;
+                let call = self.convert_call_jump(jump, call);
 
                 self.convert_side_effects_expr(
                     ctx,
@@ -3661,21 +4460,33 @@ impl<'c> Translation<'c> {
 
             CExprKind::Predefined(_, val_id) => self.convert_expr(ctx, val_id),
 
+            CExprKind::ImaginaryLiteral(ty, val_id) => {
+                self.convert_imaginary_literal(ctx, ty, val_id)
+            }
+
             CExprKind::Statements(_, compound_stmt_id) => {
                 self.convert_statement_expression(ctx, compound_stmt_id)
             }
 
             CExprKind::VAArg(ty, val_id) => self.convert_vaarg(ctx, ty, val_id),
 
+            CExprKind::AddrLabel(ty, label_id) => {
+                // Label addresses are the integers that computed gotos dispatch on
+                let addr = mk().lit_expr(mk().int_lit(cfg::label_address(label_id), "usize"));
+                let ty = self.convert_type(ty.ctype)?;
+                Ok(WithStmts::new_val(mk().cast_expr(addr, ty)))
+            }
+
             CExprKind::Choose(_, _cond, lhs, rhs, is_cond_true) => {
+                // The condition is an integer constant expression, which Clang evaluates for us,
+                // so only the chosen expression is translated. The other one may not even have a
+                // type compatible with the context of the choice.
                 let chosen_expr = if is_cond_true {
                     self.convert_expr(ctx, lhs)?
                 } else {
                     self.convert_expr(ctx, rhs)?
                 };
 
-                // TODO: Support compile-time choice between lhs and rhs based on cond.
-
                 // From Clang Expr.h
                 // ChooseExpr - GNU builtin-in function __builtin_choose_expr.
                 // This AST node is similar to the conditional operator (?:) in C, with
@@ -3690,6 +4501,16 @@ impl<'c> Translation<'c> {
                 Ok(chosen_expr)
             }
 
+            // The controlling expression of a generic selection is not evaluated, and the
+            // selection is resolved from its type by Clang, so it is just the selected expression
+            CExprKind::GenericSelection(_, _, ref assocs, selected) => {
+                self.convert_expr(ctx, assocs[selected].1)
+            }
+
+            CExprKind::TypesCompatible(ty, _, _, compatible) => {
+                Ok(WithStmts::new_val(self.mk_int_lit(ty, compatible as u64, IntBase::Dec)?))
+            }
+
             CExprKind::Atomic{ref name, ptr, order, val1, order_fail, val2, weak, ..} => {
                 self.convert_atomic(ctx, name, ptr, order, val1, order_fail, val2, weak)
             }
@@ -3708,6 +4529,17 @@ impl<'c> Translation<'c> {
                 .first()
             {
                 trace!("  found macro expansion: {:?}", macro_id);
+                let is_fn_macro = match self.ast_context[*macro_id].kind {
+                    CDeclKind::MacroFunction { .. } => true,
+                    _ => false,
+                };
+
+                // Calls are not allowed in constant initializers, so leave
+                // function-like macros expanded there
+                if is_fn_macro && (ctx.is_const || ctx.is_static) {
+                    return Ok(None);
+                }
+
                 // Ensure that we've converted this macro and that it has a
                 // valid definition
                 let expansion = self.macro_expansions.borrow().get(macro_id).cloned();
@@ -3738,6 +4570,39 @@ impl<'c> Translation<'c> {
                     self.add_import(*cur_file, *macro_id, &rustname);
                 }
 
+                if let CDeclKind::MacroFunction {
+                    ref parameters,
+                    ref replacements,
+                    ref arguments,
+                    ..
+                } = self.ast_context[*macro_id].kind
+                {
+                    let args = match replacements.iter().position(|&r| r == expr_id) {
+                        Some(idx) => &arguments[idx],
+                        None => return Ok(None),
+                    };
+                    let call_args = (0..parameters.len())
+                        .map(|param| {
+                            let arg = args
+                                .iter()
+                                .find(|&&(p, _)| p == param)
+                                .map(|&(_, arg)| arg)
+                                .ok_or_else(|| format_err!("Missing macro argument"))?;
+                            self.convert_expr(ctx.used(), arg)
+                        })
+                        .collect::<Result<WithStmts<Vec<_>>, TranslationError>>()?;
+                    let call = call_args
+                        .map(|args| mk().call_expr(mk().path_expr(vec![rustname]), args));
+
+                    return self
+                        .convert_side_effects_expr(
+                            ctx,
+                            call,
+                            "Function call expression is not supposed to be used",
+                        )
+                        .map(Some);
+                }
+
                 let val = WithStmts::new_val(mk().path_expr(vec![rustname]));
 
                 let expr_kind = &self.ast_context[expr_id].kind;
@@ -3778,6 +4643,48 @@ impl<'c> Translation<'c> {
         }
     }
 
+    /// Bind the value of a statement expression whose statements were lowered into the
+    /// control-flow graph of the enclosing function, so that translating the statement
+    /// expression produces that value. `result` is the expression statement ending the statement
+    /// expression, if any.
+    pub fn convert_lowered_stmt_expr_value(
+        &self,
+        ctx: ExprContext,
+        compound_stmt_id: CStmtId,
+        result: Option<CExprId>,
+    ) -> Result<Vec<Stmt>, TranslationError> {
+        let unit = || mk().tuple_expr(vec![] as Vec<P<Expr>>);
+        let (stmts, val) = match result {
+            Some(expr_id) => {
+                let is_void = self.ast_context[expr_id]
+                    .kind
+                    .get_type()
+                    .map_or(true, |ty| match self.ast_context.resolve_type(ty).kind {
+                        CTypeKind::Void => true,
+                        _ => false,
+                    });
+                if is_void {
+                    (self.convert_expr(ctx.unused(), expr_id)?.into_stmts(), unit())
+                } else {
+                    let (mut stmts, val) = self.convert_expr(ctx.used(), expr_id)?.discard_unsafe();
+                    let name = self.renamer.borrow_mut().pick_name("stmt_expr_value");
+                    let local = mk().local(
+                        mk().ident_pat(&name),
+                        None as Option<P<Ty>>,
+                        Some(val),
+                    );
+                    stmts.push(mk().local_stmt(P(local)));
+                    (stmts, mk().ident_expr(name))
+                }
+            }
+            None => (vec![], unit()),
+        };
+        self.stmt_expr_values
+            .borrow_mut()
+            .insert(compound_stmt_id, val);
+        Ok(stmts)
+    }
+
     fn convert_statement_expression(
         &self,
         ctx: ExprContext,
@@ -3802,6 +4709,16 @@ impl<'c> Translation<'c> {
             None
         }
 
+        if let Some(val) = self.stmt_expr_values.borrow().get(&compound_stmt_id) {
+            return Ok(WithStmts::new_val(val.clone()));
+        }
+        if cfg::stmt_expr::jumps_out(&self.ast_context, compound_stmt_id) {
+            return Err(TranslationError::generic(
+                "Jumps out of a statement expression are only supported when it is evaluated \
+                 unconditionally, before the rest of its statement",
+            ));
+        }
+
         match self.ast_context[compound_stmt_id].kind {
             CStmtKind::Compound(ref substmt_ids) if !substmt_ids.is_empty() => {
                 let n = substmt_ids.len();
@@ -3888,6 +4805,10 @@ impl<'c> Translation<'c> {
 
         let kind = kind.unwrap_or_else(|| {
             match (source_ty_kind, target_ty_kind) {
+                (CTypeKind::Atomic(..), _) => CastKind::AtomicToNonAtomic,
+
+                (_, CTypeKind::Atomic(..)) => CastKind::NonAtomicToAtomic,
+
                 (CTypeKind::VariableArray(..), CTypeKind::Pointer(..))
                 | (CTypeKind::ConstantArray(..), CTypeKind::Pointer(..))
                 | (CTypeKind::IncompleteArray(..), CTypeKind::Pointer(..))
@@ -3929,7 +4850,17 @@ impl<'c> Translation<'c> {
                 (CTypeKind::Pointer(..), CTypeKind::Pointer(..))
                     => CastKind::BitCast,
 
-                // Ignoring Complex casts for now
+                (CTypeKind::Complex(..), CTypeKind::Bool)
+                    => CastKind::FloatingComplexToBoolean,
+
+                (CTypeKind::Complex(..), CTypeKind::Complex(..))
+                    => CastKind::FloatingComplexCast,
+
+                (CTypeKind::Complex(..), _) if target_ty_kind.is_floating_type()
+                    => CastKind::FloatingComplexToReal,
+
+                (_, CTypeKind::Complex(..)) if source_ty_kind.is_floating_type()
+                    => CastKind::FloatingRealToComplex,
 
                 _ => {
                     warn!(
@@ -3946,8 +4877,13 @@ impl<'c> Translation<'c> {
         match kind {
             CastKind::BitCast | CastKind::NoOp => {
                 val.and_then(|x| {
+                    // Vectors kept as arrays of lanes are reinterpreted rather than cast
+                    let is_lanes_cast = self.type_converter.borrow().translate_vector_lanes
+                        && self.ast_context.resolve_type(ty.ctype).kind.is_vector()
+                        && self.ast_context.resolve_type(source_ty.ctype).kind.is_vector();
                     if self.ast_context.is_function_pointer(ty.ctype)
                         || self.ast_context.is_function_pointer(source_ty.ctype)
+                        || is_lanes_cast
                     {
                         if ctx.is_static || ctx.is_const {
                             self.use_feature("const_transmute");
@@ -4118,6 +5054,7 @@ impl<'c> Translation<'c> {
 
             CastKind::IntegralToBoolean
             | CastKind::FloatingToBoolean
+            | CastKind::FloatingComplexToBoolean
             | CastKind::PointerToBoolean => {
                 if let Some(expr) = expr {
                     self.convert_condition(ctx, true, expr)
@@ -4139,13 +5076,40 @@ impl<'c> Translation<'c> {
             | CastKind::IntegralRealToComplex
             | CastKind::IntegralComplexCast
             | CastKind::IntegralComplexToFloatingComplex
-            | CastKind::IntegralComplexToBoolean => Err(TranslationError::generic(
-                "TODO casts with complex numbers not supported",
-            )),
+            | CastKind::IntegralComplexToBoolean => {
+                self.convert_complex_cast(kind, source_ty, ty, val, expr)
+            }
 
             CastKind::VectorSplat => Err(TranslationError::generic(
                 "TODO vector splat casts not supported",
             )),
+
+            CastKind::AtomicToNonAtomic => {
+                // Assignments and increments of atomics already evaluate to the plain value
+                let already_loaded = match expr.map(|e| &self.ast_context[e].kind) {
+                    Some(&CExprKind::Binary(_, op, ..)) => op.is_assignment(),
+                    Some(&CExprKind::Unary(_, op, ..)) => match op {
+                        c_ast::UnOp::PreIncrement
+                        | c_ast::UnOp::PreDecrement
+                        | c_ast::UnOp::PostIncrement
+                        | c_ast::UnOp::PostDecrement => true,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                if already_loaded {
+                    return Ok(val);
+                }
+                val.result_map(|x| self.convert_atomic_load(ty, x))
+            }
+
+            CastKind::NonAtomicToAtomic => {
+                let value_ty = match *target_ty_kind {
+                    CTypeKind::Atomic(value_ty) => value_ty,
+                    _ => return Err(TranslationError::generic("Expected an atomic cast target")),
+                };
+                val.result_map(|x| self.convert_atomic_new(value_ty, x))
+            }
         }
     }
 
@@ -4287,6 +5251,12 @@ impl<'c> Translation<'c> {
                .map(|val| vec_expr(val, count)))
         } else if let &CTypeKind::Vector(CQualTypeId { ctype, .. }, len) = resolved_ty {
             self.implicit_vector_default(ctype, len, is_static)
+        } else if let &CTypeKind::Atomic(value_ty) = resolved_ty {
+            self.implicit_default_expr(value_ty.ctype, is_static)?
+                .result_map(|val| self.convert_atomic_new(value_ty, val))
+        } else if let &CTypeKind::Complex(elt) = resolved_ty {
+            Ok(self.implicit_default_expr(elt, is_static)?
+                .map(|zero| self.mk_complex(zero.clone(), zero)))
         } else {
             Err(format_err!("Unsupported default initializer: {:?}", resolved_ty).into())
         }
@@ -4441,6 +5411,11 @@ impl<'c> Translation<'c> {
             } else {
                 mk().unary_expr(ast::UnOp::Not, val)
             }
+        } else if ty.is_complex() {
+            let zero = mk().lit_expr(mk().float_unsuffixed_lit("0."));
+            let zero = self.mk_complex(zero.clone(), zero);
+            let op = if target { BinOpKind::Ne } else { BinOpKind::Eq };
+            mk().binary_expr(op, val, zero)
         } else {
             // One simplification we can make at the cost of inspecting `val` more closely: if `val`
             // is already in the form `(x <op> y) as <ty>` where `<op>` is a Rust operator
@@ -4505,6 +5480,22 @@ impl<'c> Translation<'c> {
     fn insert_item(&self, mut item: P<Item>, decl: &CDecl) {
         let decl_file_id = self.ast_context.file_id(decl);
 
+        if self.tcfg.dedup_types && decl_file_id.map_or(false, |id| id != self.main_file) {
+            match decl.kind {
+                CDeclKind::Struct { .. }
+                | CDeclKind::Union { .. }
+                | CDeclKind::Enum { .. }
+                | CDeclKind::EnumConstant { .. }
+                | CDeclKind::Typedef { .. } => {
+                    // Shared types are imported from another module
+                    item.vis = dummy_spanned(VisibilityKind::Public);
+                    self.header_type_items.borrow_mut().push(item);
+                    return;
+                }
+                _ => {}
+            }
+        }
+
         if self.tcfg.reorganize_definitions {
             add_src_loc_attr(&mut item.attrs, &decl.loc.as_ref().map(|x| x.begin()));
             let mut item_stores = self.items.borrow_mut();
@@ -4594,8 +5585,12 @@ impl<'c> Translation<'c> {
             | VariableArray(ctype, _)
             | Reference(CQualTypeId { ctype, ..})
             | BlockPointer(CQualTypeId { ctype, .. })
-            | TypeOf(ctype)
-            | Complex(ctype) => {
+            | Atomic(CQualTypeId { ctype, .. })
+            | TypeOf(ctype) => {
+                self.import_type(*ctype, decl_file_id)
+            }
+            Complex(ctype) => {
+                self.use_crate(ExternCrate::NumComplex);
                 self.import_type(*ctype, decl_file_id)
             }
             Enum(decl_id) | Typedef(decl_id) | Union(decl_id) | Struct(decl_id) => {
@@ -4627,7 +5622,7 @@ impl<'c> Translation<'c> {
                 }
             }
             Vector(..) => {
-                // Handled in `import_simd_typedef`
+                // Handled in `import_simd_typedef` and `import_neon_decl`
             }
             TypeOfExpr(_) | BuiltinFn => {}
         }
@@ -4677,9 +5672,12 @@ impl<'c> Translation<'c> {
                 ..
             } => self.import_type(typ, decl_file_id),
 
-            CDeclKind::MacroObject { .. } => {
+            CDeclKind::MacroObject { .. } | CDeclKind::MacroFunction { .. } => {
                 if let Some(Some(expansion)) = self.macro_expansions.borrow().get(&decl_id) {
-                    self.import_type(expansion.ty, decl_file_id)
+                    self.import_type(expansion.ty, decl_file_id);
+                    for &ty in &expansion.param_tys {
+                        self.import_type(ty, decl_file_id);
+                    }
                 }
             }
 
@@ -52,6 +52,7 @@ impl<'c> Translation<'c> {
             .kind
             .get_qual_type()
             .ok_or_else(|| format_err!("bad reference type"))?;
+        let is_packed = self.ast_context.is_packed_place(reference);
         let reference = self.convert_expr(ctx.used(), reference)?;
         reference.and_then(|reference| {
             /// Check if something is a valid Rust lvalue. Inspired by `librustc::ty::expr_is_lval`.
@@ -90,6 +91,17 @@ impl<'c> Translation<'c> {
             } else if is_simple_lvalue(&*reference) {
                 Ok(WithStmts::new_val((reference.clone(), Some(read(reference)?))))
             } else {
+                // Fields of packed structs can't be borrowed, so we factor out the pointer
+                // through which the field is accessed instead of a reference to the field.
+                if is_packed {
+                    if let Some((compute_base, write)) = self.hoist_packed_base(&reference) {
+                        return Ok(WithStmts::new(
+                            compute_base,
+                            (write.clone(), Some(read(write)?)),
+                        ));
+                    }
+                }
+
                 // This is the case where we explicitly need to factor out possible side-effects.
 
                 let ptr_name = self.renamer.borrow_mut().fresh();
@@ -110,4 +122,40 @@ impl<'c> Translation<'c> {
             }
         })
     }
+
+    /// Given a field access through a dereference, such as `(*f()).a[g()]`, bind the
+    /// dereferenced pointer and any indices to fresh variables and return the bindings along with
+    /// the rewritten access `(*p).a[i]`. Returns `None` if the access does not go through a
+    /// dereference.
+    fn hoist_packed_base(&self, reference: &P<Expr>) -> Option<(Vec<Stmt>, P<Expr>)> {
+        let bind = |val: P<Expr>| {
+            let name = self.renamer.borrow_mut().fresh();
+            let stmt = mk().local_stmt(P(mk().local(
+                mk().ident_pat(&name),
+                None as Option<P<Ty>>,
+                Some(val),
+            )));
+            (stmt, mk().ident_expr(&name))
+        };
+
+        match reference.kind {
+            ExprKind::Paren(ref e) => self.hoist_packed_base(e),
+            ExprKind::Field(ref e, ident) => self
+                .hoist_packed_base(e)
+                .map(|(stmts, e)| (stmts, mk().field_expr(e, ident.name))),
+            ExprKind::Index(ref e, ref index) => {
+                self.hoist_packed_base(e).map(|(mut stmts, e)| {
+                    let (compute_index, index) = bind(index.clone());
+                    stmts.push(compute_index);
+                    (stmts, mk().index_expr(e, index))
+                })
+            }
+            ExprKind::Unary(ast::UnOp::Deref, ref ptr) => {
+                // let p = ptr;
+                let (compute_ptr, ptr) = bind(ptr.clone());
+                Some((vec![compute_ptr], mk().unary_expr(ast::UnOp::Deref, ptr)))
+            }
+            _ => None,
+        }
+    }
 }
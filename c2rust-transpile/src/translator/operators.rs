@@ -250,6 +250,17 @@ impl<'c> Translation<'c> {
         compute_type: Option<CQualTypeId>,
         result_type: Option<CQualTypeId>,
     ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        // Storing into an atomic object takes the plain value, not a freshly built atomic
+        let lhs_is_atomic = self.ast_context[lhs]
+            .kind
+            .get_type()
+            .map_or(false, |ty| self.ast_context.resolve_type(ty).kind.is_atomic());
+        let rhs = match self.ast_context[rhs].kind {
+            CExprKind::ImplicitCast(_, inner, CastKind::NonAtomicToAtomic, _, _)
+                if lhs_is_atomic => inner,
+            _ => rhs,
+        };
+
         let rhs_type_id = self
             .ast_context
             .index(rhs)
@@ -290,6 +301,20 @@ impl<'c> Translation<'c> {
             .get_qual_type()
             .ok_or_else(|| format_err!("bad initial lhs type"))?;
 
+        if let CTypeKind::Atomic(value_ty) = self.ast_context.resolve_type(initial_lhs_type_id.ctype).kind {
+            return self.convert_atomic_update(
+                ctx,
+                op,
+                lhs,
+                value_ty,
+                rhs_type_id,
+                rhs_translation,
+                compute_type,
+                result_type,
+                false,
+            );
+        }
+
         let bitfield_id = match initial_lhs {
             CExprKind::Member(_, _, decl_id, _, _) => {
                 let kind = &self.ast_context[*decl_id].kind;
@@ -578,7 +603,7 @@ impl<'c> Translation<'c> {
 
     /// Translate a non-assignment binary operator. It is expected that the `lhs` and `rhs`
     /// arguments be usable as rvalues.
-    fn convert_binary_operator(
+    pub(crate) fn convert_binary_operator(
         &self,
         ctx: ExprContext,
         op: c_ast::BinOp,
@@ -596,6 +621,13 @@ impl<'c> Translation<'c> {
             .kind
             .is_unsigned_integral_type();
 
+        let (lhs, rhs) = match op {
+            c_ast::BinOp::EqualEqual | c_ast::BinOp::NotEqual => {
+                self.convert_complex_comparands(lhs_type, rhs_type, lhs, rhs)
+            }
+            _ => (lhs, rhs),
+        };
+
         match op {
             c_ast::BinOp::Add => self.convert_addition(ctx, lhs_type, rhs_type, lhs, rhs),
             c_ast::BinOp::Subtract => self.convert_subtraction(ctx, ty, lhs_type, rhs_type, lhs, rhs),
@@ -827,6 +859,16 @@ impl<'c> Translation<'c> {
             .get_qual_type()
             .ok_or_else(|| format_err!("bad post inc type"))?;
 
+        if let CTypeKind::Atomic(value_ty) = self.ast_context.resolve_type(ty.ctype).kind {
+            let op = if up {
+                c_ast::BinOp::AssignAdd
+            } else {
+                c_ast::BinOp::AssignSubtract
+            };
+            let one = WithStmts::new_val(mk().lit_expr(mk().int_lit(1, LitIntType::Unsuffixed)));
+            return self.convert_atomic_update(ctx, op, arg, value_ty, value_ty, one, None, None, true);
+        }
+
         self.name_reference_write_read(ctx, arg)?
             .and_then(|(write, read)| {
                 let val_name = self.renamer.borrow_mut().fresh();
@@ -927,6 +969,16 @@ impl<'c> Translation<'c> {
                     _ => (),
                 };
 
+                // Fields of packed structs may be misaligned, so they can't be borrowed
+                if self.ast_context.is_packed_place(arg) {
+                    let mutbl = match self.ast_context.get_pointee_qual_type(ctype) {
+                        Some(pointee) if pointee.qualifiers.is_const => Mutability::Immutable,
+                        _ => Mutability::Mutable,
+                    };
+                    let addr = self.convert_packed_addr_of(ctx, arg, mutbl)?;
+                    return Ok(addr.map(|addr| mk().cast_expr(addr, ty)));
+                }
+
                 // In this translation, there are only pointers to functions and
                 // & becomes a no-op when applied to a function.
 
@@ -1025,6 +1077,10 @@ impl<'c> Translation<'c> {
                     Ok(val.map(neg_expr))
                 }
             }
+            // GNU extension: `~` on a complex value is its conjugate
+            c_ast::UnOp::Complement if resolved_ctype.kind.is_complex() => Ok(self
+                .convert_expr(ctx.used(), arg)?
+                .map(|a| mk().method_call_expr(a, "conj", vec![] as Vec<P<Expr>>))),
             c_ast::UnOp::Complement => Ok(self
                 .convert_expr(ctx.used(), arg)?
                 .map(|a| mk().unary_expr(ast::UnOp::Not, a))),
@@ -1037,7 +1093,10 @@ impl<'c> Translation<'c> {
                 let arg = self.convert_expr(ctx, arg)?;
                 Ok(arg)
             }
-            c_ast::UnOp::Real | c_ast::UnOp::Imag | c_ast::UnOp::Coawait => {
+            c_ast::UnOp::Real | c_ast::UnOp::Imag => {
+                self.convert_complex_part(ctx, name, cqual_type, arg)
+            }
+            c_ast::UnOp::Coawait => {
                 panic!("Unsupported extension operator")
             }
         }
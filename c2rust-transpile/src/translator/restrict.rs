@@ -0,0 +1,364 @@
+//! This module provides the opt-in translation of `restrict`-qualified pointer parameters into
+//! Rust references. A parameter qualifies if the function only ever dereferences it (with `*p`
+//! or `p->field`) or passes it on unchanged to another function, the function is only ever
+//! called directly, and every argument passed for it is known not to be NULL. Callers reborrow
+//! their pointer arguments as references, which are only mutable if the function writes through
+//! the parameter.
+
+use std::collections::HashSet;
+
+use super::*;
+
+/// How a `restrict` pointer parameter which may become a reference is used
+struct ParamUses {
+    /// The pointee is `const`-qualified
+    const_pointee: bool,
+    /// The function writes through the pointer, or takes the address of part of its pointee
+    written: bool,
+    /// The parameters of the direct callees the pointer is passed on to, or `None` for the
+    /// arguments of indirect calls
+    passed_to: Vec<Option<CParamId>>,
+}
+
+impl<'c> Translation<'c> {
+    /// Find the `restrict` pointer parameters which can be translated into references, along
+    /// with the mutability of those references.
+    pub(crate) fn find_reference_params(&self) -> HashMap<CParamId, Mutability> {
+        // Changing a function's signature would break callers in other translation units and any
+        // use of it as a function pointer, so only static functions which are always called
+        // directly are rewritten
+        let address_taken = self.address_taken_fns();
+
+        let mut candidates = HashMap::new();
+        let mut calls = vec![];
+        for &decl_id in &self.ast_context.c_decls_top {
+            for expr_id in DFExpr::new(&self.ast_context, decl_id.into()).flat_map(SomeId::expr) {
+                if let CExprKind::Call(_, func, ref args) = self.ast_context[expr_id].kind {
+                    if let Some(callee) = self.direct_callee(func) {
+                        calls.push((callee, args));
+                    }
+                }
+            }
+
+            let (parameters, body) = match self.ast_context[decl_id].kind {
+                CDeclKind::Function {
+                    is_global: false,
+                    ref parameters,
+                    body: Some(body),
+                    ..
+                } if !address_taken.contains(&decl_id) => (parameters, body),
+                _ => continue,
+            };
+            for &param in parameters {
+                if let Some(uses) = self.reference_param_uses(param, body) {
+                    candidates.insert(param, uses);
+                }
+            }
+        }
+
+        // References can't be NULL, so every argument has to be the address of an object or
+        // another parameter that becomes a reference
+        loop {
+            let mut changed = false;
+            for &(callee, args) in &calls {
+                let parameters = match self.ast_context[callee].kind {
+                    CDeclKind::Function { ref parameters, .. } => parameters,
+                    _ => continue,
+                };
+                for (param, &arg) in parameters.iter().zip(args) {
+                    if candidates.contains_key(param) && !self.is_non_null_arg(arg, &candidates) {
+                        candidates.remove(param);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        // A reference is mutable if the function writes through it, or passes it on to a
+        // mutable reference or a raw pointer that may be written through
+        let mut mutable: HashSet<CParamId> = candidates
+            .iter()
+            .filter(|&(_, uses)| {
+                !uses.const_pointee
+                    && (uses.written
+                        || uses.passed_to.iter().any(|callee_param| {
+                            callee_param.map_or(true, |p| !candidates.contains_key(&p))
+                        }))
+            })
+            .map(|(&param, _)| param)
+            .collect();
+        loop {
+            let newly_mutable: Vec<CParamId> = candidates
+                .iter()
+                .filter(|&(param, uses)| {
+                    !uses.const_pointee
+                        && !mutable.contains(param)
+                        && uses.passed_to.iter().any(|callee_param| {
+                            callee_param.map_or(false, |p| mutable.contains(&p))
+                        })
+                })
+                .map(|(&param, _)| param)
+                .collect();
+            if newly_mutable.is_empty() {
+                break;
+            }
+            mutable.extend(newly_mutable);
+        }
+
+        candidates
+            .keys()
+            .map(|&param| {
+                let mutbl = if mutable.contains(&param) {
+                    Mutability::Mutable
+                } else {
+                    Mutability::Immutable
+                };
+                (param, mutbl)
+            })
+            .collect()
+    }
+
+    /// Is this argument known not to be NULL: the address of an object, an array, or a
+    /// parameter which becomes a reference?
+    fn is_non_null_arg(&self, arg: CExprId, candidates: &HashMap<CParamId, ParamUses>) -> bool {
+        match self.ast_context[arg].kind {
+            CExprKind::Paren(_, e) => self.is_non_null_arg(e, candidates),
+            CExprKind::Unary(_, c_ast::UnOp::AddressOf, _, _)
+            | CExprKind::ImplicitCast(_, _, CastKind::ArrayToPointerDecay, _, _) => true,
+            CExprKind::ImplicitCast(_, e, CastKind::LValueToRValue, _, _) => {
+                match self.ast_context[e].kind {
+                    CExprKind::DeclRef(_, decl_id, _) => candidates.contains_key(&decl_id),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Find the functions which are referenced other than as the callee of a direct call, e.g.,
+    /// to take their address.
+    pub(crate) fn address_taken_fns(&self) -> HashSet<CDeclId> {
+        let mut direct_callees = HashSet::new();
+        let mut fn_refs = vec![];
+        for &decl_id in &self.ast_context.c_decls_top {
+            for expr_id in DFExpr::new(&self.ast_context, decl_id.into()).flat_map(SomeId::expr) {
+                match self.ast_context[expr_id].kind {
+                    CExprKind::Call(_, func, _) => match self.ast_context[func].kind {
+                        CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _) => {
+                            direct_callees.insert(fexp);
+                        }
+                        _ => {}
+                    },
+                    CExprKind::DeclRef(_, decl_id, _) => {
+                        if let CDeclKind::Function { .. } = self.ast_context[decl_id].kind {
+                            fn_refs.push((expr_id, decl_id));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        fn_refs
+            .into_iter()
+            .filter(|(expr_id, _)| !direct_callees.contains(expr_id))
+            .map(|(_, decl_id)| decl_id)
+            .collect()
+    }
+
+    /// Decide whether a parameter of the function with the given body may be translated into a
+    /// reference, and if so, how the function uses it.
+    fn reference_param_uses(&self, param: CParamId, body: CStmtId) -> Option<ParamUses> {
+        let typ = match self.ast_context[param].kind {
+            CDeclKind::Variable { typ, .. } if typ.qualifiers.is_restrict => typ,
+            _ => return None,
+        };
+        let pointee = self.ast_context.get_pointee_qual_type(typ.ctype)?;
+
+        // The pointee has to be a complete, sized type
+        match self.ast_context.resolve_type(pointee.ctype).kind {
+            CTypeKind::Void
+            | CTypeKind::Function(..)
+            | CTypeKind::IncompleteArray(..)
+            | CTypeKind::VariableArray(..) => return None,
+            CTypeKind::Struct(record) | CTypeKind::Union(record) => {
+                match self.ast_context[record].kind {
+                    CDeclKind::Struct { fields: None, .. }
+                    | CDeclKind::Union { fields: None, .. } => return None,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        // Every use of the parameter has to be a read of its value that is immediately
+        // dereferenced or passed on as a non-variadic argument.
+        let is_param_read = |expr_id: CExprId| match self.ast_context[expr_id].kind {
+            CExprKind::ImplicitCast(_, e, CastKind::LValueToRValue, _, _) => {
+                match self.ast_context[e].kind {
+                    CExprKind::DeclRef(_, decl_id, _) if decl_id == param => Some(e),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let mut uses = 0;
+        let mut allowed_uses = HashSet::new();
+        let mut written = false;
+        let mut passed_to = vec![];
+        for expr_id in DFExpr::new(&self.ast_context, body.into()).flat_map(SomeId::expr) {
+            // Places whose address is taken may be written through that address later
+            let written_place = match self.ast_context[expr_id].kind {
+                CExprKind::Binary(_, op, lhs, _, _, _) if op.is_assignment() => Some(lhs),
+                CExprKind::Unary(_, op, e, _) => match op {
+                    c_ast::UnOp::PreIncrement
+                    | c_ast::UnOp::PreDecrement
+                    | c_ast::UnOp::PostIncrement
+                    | c_ast::UnOp::PostDecrement
+                    | c_ast::UnOp::AddressOf => Some(e),
+                    _ => None,
+                },
+                CExprKind::ImplicitCast(_, e, CastKind::ArrayToPointerDecay, _, _) => Some(e),
+                _ => None,
+            };
+            if let Some(place) = written_place {
+                written |= self.place_pointer(place).and_then(is_param_read).is_some();
+            }
+
+            match self.ast_context[expr_id].kind {
+                CExprKind::DeclRef(_, decl_id, _) if decl_id == param => uses += 1,
+                CExprKind::Unary(_, c_ast::UnOp::Deref, e, _)
+                | CExprKind::Member(_, e, _, MemberKind::Arrow, _) => {
+                    allowed_uses.extend(is_param_read(e));
+                }
+                CExprKind::Call(_, func, ref args) => {
+                    let fixed_args = self.ast_context[func]
+                        .kind
+                        .get_type()
+                        .and_then(|ty| self.ast_context.get_pointee_qual_type(ty))
+                        .map(|ty| match self.ast_context.resolve_type(ty.ctype).kind {
+                            CTypeKind::Function(_, ref params, _, _, _) => params.len(),
+                            _ => 0,
+                        })
+                        .unwrap_or(0);
+                    let callee_params = self.direct_callee(func).and_then(|callee| {
+                        match self.ast_context[callee].kind {
+                            CDeclKind::Function { ref parameters, .. } => Some(parameters),
+                            _ => None,
+                        }
+                    });
+                    for (i, &arg) in args.iter().enumerate().take(fixed_args) {
+                        if let Some(read) = is_param_read(arg) {
+                            allowed_uses.insert(read);
+                            passed_to.push(callee_params.and_then(|ps| ps.get(i).cloned()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if uses != allowed_uses.len() {
+            return None;
+        }
+
+        Some(ParamUses {
+            const_pointee: pointee.qualifiers.is_const,
+            written,
+            passed_to,
+        })
+    }
+
+    /// Get the pointer a place is reached through, if it is part of the pointee of a pointer:
+    /// `p` for `*p`, `p->field`, or `p->array[i].field`
+    fn place_pointer(&self, place: CExprId) -> Option<CExprId> {
+        match self.ast_context[place].kind {
+            CExprKind::Paren(_, e)
+            | CExprKind::Member(_, e, _, MemberKind::Dot, _)
+            | CExprKind::ImplicitCast(_, e, CastKind::ArrayToPointerDecay, _, _) => {
+                self.place_pointer(e)
+            }
+            CExprKind::Unary(_, c_ast::UnOp::Deref, e, _)
+            | CExprKind::Member(_, e, _, MemberKind::Arrow, _) => Some(e),
+            CExprKind::ArraySubscript(_, lhs, rhs, _) => {
+                let is_decayed_array = |e: CExprId| match self.ast_context[e].kind {
+                    CExprKind::ImplicitCast(_, _, CastKind::ArrayToPointerDecay, _, _) => true,
+                    _ => false,
+                };
+                if is_decayed_array(lhs) {
+                    self.place_pointer(lhs)
+                } else if is_decayed_array(rhs) {
+                    self.place_pointer(rhs)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the reference type for a parameter translated into a reference, if it is one
+    pub(crate) fn convert_reference_param_type(
+        &self,
+        param: CParamId,
+        typ: CQualTypeId,
+    ) -> Result<Option<P<Ty>>, TranslationError> {
+        let mutbl = match self.reference_params.get(&param) {
+            Some(&mutbl) => mutbl,
+            None => return Ok(None),
+        };
+        let pointee = self
+            .ast_context
+            .get_pointee_qual_type(typ.ctype)
+            .ok_or_else(|| format_err!("restrict parameter is not a pointer"))?;
+        let pointee = self.convert_type(pointee.ctype)?;
+        Ok(Some(mk().set_mutbl(mutbl).ref_ty(pointee)))
+    }
+
+    /// For a direct call, get the mutability of the reference each parameter of the callee is
+    /// translated into, if any
+    pub(crate) fn callee_reference_params(&self, func: CExprId) -> Vec<Option<Mutability>> {
+        let decl_id = match self.direct_callee(func) {
+            Some(decl_id) => decl_id,
+            None => return vec![],
+        };
+        match self.ast_context[decl_id].kind {
+            CDeclKind::Function { ref parameters, .. } => parameters
+                .iter()
+                .map(|p| self.reference_params.get(p).cloned())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Get the function called by a direct call with the given callee expression
+    pub(crate) fn direct_callee(&self, func: CExprId) -> Option<CDeclId> {
+        let fexp = match self.ast_context[func].kind {
+            CExprKind::ImplicitCast(_, fexp, CastKind::FunctionToPointerDecay, _, _) => fexp,
+            _ => return None,
+        };
+        match self.ast_context[fexp].kind {
+            CExprKind::DeclRef(_, decl_id, _) => Some(decl_id),
+            _ => None,
+        }
+    }
+
+    /// Reborrow the pointer arguments of a call as references where the callee takes
+    /// references: `&mut *arg`
+    pub(crate) fn convert_reference_args(
+        &self,
+        reference_params: &[Option<Mutability>],
+        args: Vec<P<Expr>>,
+    ) -> Vec<P<Expr>> {
+        args.into_iter()
+            .enumerate()
+            .map(|(i, arg)| match reference_params.get(i) {
+                Some(&Some(mutbl)) => mk()
+                    .set_mutbl(mutbl)
+                    .addr_of_expr(mk().unary_expr(ast::UnOp::Deref, arg)),
+                _ => arg,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,457 @@
+//! This module provides an opt-in translation of `setjmp`/`longjmp` which never unwinds.
+//!
+//! We only support `setjmp` as the condition of an `if` statement, compared against zero. The
+//! branch taken when `setjmp` returns directly is run in a closure by
+//! `c2rust_setjmp::catch_longjmp`. `longjmp` records a pending jump identified by its `jmp_buf`,
+//! and every call which may start a jump is followed by a check which returns early while the
+//! jump is pending, until control is back in the matching `catch_longjmp`. The branch taken after
+//! a `longjmp` stays in the function's control-flow graph. The runtime support is emitted into
+//! the translated crate as the `c2rust_setjmp` module (see `build_files`).
+
+use super::*;
+
+/// Functions (and the macros expanding to them) which save the calling environment
+static SETJMP_FNS: &[&str] = &["setjmp", "_setjmp", "__sigsetjmp", "sigsetjmp", "__builtin_setjmp"];
+
+/// Functions (and the macros expanding to them) which restore a saved calling environment
+static LONGJMP_FNS: &[&str] = &[
+    "longjmp",
+    "_longjmp",
+    "siglongjmp",
+    "__longjmp_chk",
+    "__builtin_longjmp",
+];
+
+/// An `if` statement whose condition is a call to `setjmp` compared against zero
+pub(crate) struct SetjmpIf {
+    /// Expression for the saved environment (`jmp_buf`) passed to `setjmp`
+    env: CExprId,
+    /// Branch taken when `setjmp` returns directly
+    direct_branch: Option<CStmtId>,
+    /// Branch taken when `setjmp` returns through a `longjmp`
+    pub longjmp_branch: Option<CStmtId>,
+}
+
+/// Whether a call may start a `longjmp` that the caller has to return for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CallJump {
+    Never,
+    Maybe,
+    /// The callee only ever returns to start a jump, e.g., `longjmp` itself
+    Always,
+}
+
+impl<'c> Translation<'c> {
+    /// Find the functions which may return with a jump pending: those which call `longjmp`, and
+    /// those which call such a function. If there are any, calls through function pointers are
+    /// assumed to start a jump as well.
+    pub(crate) fn find_longjmp_fns(&self) -> IndexSet<CDeclId> {
+        let mut callees = IndexMap::new();
+        let mut calls_longjmp = IndexSet::new();
+        let mut calls_indirect = IndexSet::new();
+        for &decl_id in &self.ast_context.c_decls_top {
+            match self.ast_context[decl_id].kind {
+                CDeclKind::Function { body: Some(_), .. } => {}
+                _ => continue,
+            }
+            let mut direct = vec![];
+            for expr_id in DFExpr::new(&self.ast_context, decl_id.into()).flat_map(SomeId::expr) {
+                let func = match self.ast_context[expr_id].kind {
+                    CExprKind::Call(_, func, _) => func,
+                    _ => continue,
+                };
+                match self.direct_callee(func) {
+                    Some(callee) if self.is_longjmp_fn(callee) => {
+                        calls_longjmp.insert(decl_id);
+                    }
+                    Some(callee) => direct.push(callee),
+                    None if self.is_builtin_callee(func) => {}
+                    None => {
+                        calls_indirect.insert(decl_id);
+                    }
+                }
+            }
+            callees.insert(decl_id, direct);
+        }
+
+        let mut longjmp_fns = calls_longjmp;
+        if !longjmp_fns.is_empty() {
+            longjmp_fns.extend(calls_indirect);
+        }
+        loop {
+            let callers = callees
+                .iter()
+                .filter(|(caller, direct)| {
+                    !longjmp_fns.contains(*caller)
+                        && direct.iter().any(|callee| longjmp_fns.contains(callee))
+                })
+                .map(|(&caller, _)| caller)
+                .collect::<Vec<_>>();
+            if callers.is_empty() {
+                return longjmp_fns;
+            }
+            longjmp_fns.extend(callers);
+        }
+    }
+
+    fn is_longjmp_fn(&self, decl_id: CDeclId) -> bool {
+        match self.ast_context[decl_id].kind {
+            CDeclKind::Function {
+                ref name,
+                body: None,
+                ..
+            } => LONGJMP_FNS.contains(&name.as_str()),
+            _ => false,
+        }
+    }
+
+    fn is_builtin_callee(&self, func: CExprId) -> bool {
+        match self.ast_context[func].kind {
+            CExprKind::ImplicitCast(_, _, CastKind::BuiltinFnToFnPtr, _, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Decide whether the call with the given callee expression may start a jump
+    pub(crate) fn call_jump(&self, func: CExprId) -> CallJump {
+        if self.longjmp_fns.is_empty() {
+            return CallJump::Never;
+        }
+        let callee = match self.direct_callee(func) {
+            Some(callee) => callee,
+            None if self.is_builtin_callee(func) => return CallJump::Never,
+            None => return CallJump::Maybe,
+        };
+        if !self.longjmp_fns.contains(&callee) {
+            return CallJump::Never;
+        }
+        let is_noreturn = match self.ast_context[callee].kind {
+            CDeclKind::Function { typ, .. } => match self.ast_context.resolve_type(typ).kind {
+                CTypeKind::Function(_, _, _, is_noreturn, _) => is_noreturn,
+                _ => false,
+            },
+            _ => false,
+        };
+        if is_noreturn {
+            CallJump::Always
+        } else {
+            CallJump::Maybe
+        }
+    }
+
+    /// Follow a call which may start a jump with an early return while the jump is pending:
+    /// `{ let ret = f(); if c2rust_setjmp::jumping() { return ...; } ret }`, or
+    /// `{ f(); return ...; }` if it always starts one.
+    pub(crate) fn convert_call_jump(
+        &self,
+        jump: CallJump,
+        call: WithStmts<P<Expr>>,
+    ) -> WithStmts<P<Expr>> {
+        if jump == CallJump::Never {
+            return call;
+        }
+        self.use_crate(ExternCrate::C2RustSetjmp);
+
+        let ret = self.pending_jump_return();
+        call.map(|call| {
+            let stmts = if jump == CallJump::Always {
+                vec![mk().semi_stmt(call), mk().expr_stmt(ret)]
+            } else {
+                let local = mk().local(mk().ident_pat("ret"), None as Option<P<Ty>>, Some(call));
+                let jumping = mk().call_expr(
+                    mk().path_expr(vec!["c2rust_setjmp", "jumping"]),
+                    vec![] as Vec<P<Expr>>,
+                );
+                let check = mk().ifte_expr(
+                    jumping,
+                    mk().block(vec![mk().semi_stmt(ret)]),
+                    None as Option<P<Expr>>,
+                );
+                vec![
+                    mk().local_stmt(P(local)),
+                    mk().semi_stmt(check),
+                    mk().expr_stmt(mk().ident_expr("ret")),
+                ]
+            };
+            mk().block_expr(mk().block(stmts))
+        })
+    }
+
+    /// Return from the function, or from the closure of a `setjmp` branch, while a jump is
+    /// pending. The value returned is never used.
+    fn pending_jump_return(&self) -> P<Expr> {
+        let fun = self.function_context.borrow();
+        if fun.in_setjmp_closure || !fun.returns_value {
+            return mk().return_expr(None as Option<P<Expr>>);
+        }
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+        let zeroed = mk().call_expr(
+            mk().path_expr(vec!["", std_or_core, "mem", "zeroed"]),
+            vec![] as Vec<P<Expr>>,
+        );
+        mk().return_expr(Some(zeroed))
+    }
+
+    fn strip_parens_and_implicit_casts(&self, mut expr: CExprId) -> CExprId {
+        loop {
+            match self.ast_context[expr].kind {
+                CExprKind::Paren(_, e) | CExprKind::ImplicitCast(_, e, _, _, _) => expr = e,
+                _ => return expr,
+            }
+        }
+    }
+
+    /// Name of the function called directly by this expression, if it is a call
+    fn direct_callee_name(&self, expr: CExprId) -> Option<&str> {
+        let func = match self.ast_context[expr].kind {
+            CExprKind::Call(_, func, _) => self.strip_parens_and_implicit_casts(func),
+            _ => return None,
+        };
+        match self.ast_context[func].kind {
+            CExprKind::DeclRef(_, decl_id, _) => match self.ast_context[decl_id].kind {
+                CDeclKind::Function { ref name, .. } => Some(name.as_str()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// If this expression is a call to `setjmp`, get its `jmp_buf` argument
+    fn match_setjmp_call(&self, expr: CExprId) -> Option<CExprId> {
+        let expr = self.strip_parens_and_implicit_casts(expr);
+        match self.direct_callee_name(expr) {
+            Some(name) if SETJMP_FNS.contains(&name) => {}
+            _ => return None,
+        }
+        match self.ast_context[expr].kind {
+            CExprKind::Call(_, _, ref args) => args.first().cloned(),
+            _ => None,
+        }
+    }
+
+    fn contains_setjmp_call(&self, expr: CExprId) -> bool {
+        DFExpr::new(&self.ast_context, expr.into())
+            .flat_map(SomeId::expr)
+            .any(|e| self.match_setjmp_call(e).is_some())
+    }
+
+    /// Recognize `if` statements conditioned on `setjmp(env)`, `!setjmp(env)`, `setjmp(env) == 0`,
+    /// or `setjmp(env) != 0`. Any other use of `setjmp` in the condition is an error.
+    pub(crate) fn match_setjmp_if(
+        &self,
+        scrutinee: CExprId,
+        true_variant: CStmtId,
+        false_variant: Option<CStmtId>,
+    ) -> Result<Option<SetjmpIf>, TranslationError> {
+        if !self.contains_setjmp_call(scrutinee) {
+            return Ok(None);
+        }
+
+        let cond = self.strip_parens_and_implicit_casts(scrutinee);
+        let is_zero = |e: CExprId| {
+            let e = self.strip_parens_and_implicit_casts(e);
+            match self.ast_context[e].kind {
+                CExprKind::Literal(_, CLiteral::Integer(0, _)) => true,
+                _ => false,
+            }
+        };
+
+        // Find the `jmp_buf` and whether the condition holds when `setjmp` returns directly
+        let matched = match self.ast_context[cond].kind {
+            CExprKind::Unary(_, c_ast::UnOp::Not, arg, _) => {
+                self.match_setjmp_call(arg).map(|env| (env, true))
+            }
+            CExprKind::Binary(_, op, lhs, rhs, _, _) => {
+                let env = match (self.match_setjmp_call(lhs), self.match_setjmp_call(rhs)) {
+                    (Some(env), None) if is_zero(rhs) => Some(env),
+                    (None, Some(env)) if is_zero(lhs) => Some(env),
+                    _ => None,
+                };
+                match op {
+                    c_ast::BinOp::EqualEqual => env.map(|env| (env, true)),
+                    c_ast::BinOp::NotEqual => env.map(|env| (env, false)),
+                    _ => None,
+                }
+            }
+            _ => self.match_setjmp_call(cond).map(|env| (env, false)),
+        };
+
+        let (env, direct_is_true) = matched.ok_or_else(|| {
+            format_translation_err!(
+                self.ast_context.display_loc(&self.ast_context[scrutinee].loc),
+                "Unsupported setjmp condition: setjmp's result may only be tested against 0",
+            )
+        })?;
+
+        let (direct_branch, longjmp_branch) = if direct_is_true {
+            (Some(true_variant), false_variant)
+        } else {
+            (false_variant, Some(true_variant))
+        };
+
+        if let Some(branch) = direct_branch {
+            self.check_setjmp_direct_branch(branch, false, false)?;
+        }
+
+        Ok(Some(SetjmpIf {
+            env,
+            direct_branch,
+            longjmp_branch,
+        }))
+    }
+
+    /// The branch taken when `setjmp` returns directly is translated into a closure, so control
+    /// flow must not enter or leave it other than by falling through.
+    fn check_setjmp_direct_branch(
+        &self,
+        stmt_id: CStmtId,
+        in_loop: bool,
+        in_switch: bool,
+    ) -> Result<(), TranslationError> {
+        let escape = match self.ast_context[stmt_id].kind {
+            CStmtKind::Return(..) => Some("return"),
+            CStmtKind::Goto(..) | CStmtKind::IndirectGoto(..) => Some("goto"),
+            CStmtKind::Label(..) => Some("a label"),
+            CStmtKind::Break if !in_loop && !in_switch => Some("break"),
+            CStmtKind::Continue if !in_loop => Some("continue"),
+            CStmtKind::Case(..) | CStmtKind::CaseRange(..) | CStmtKind::Default(..)
+                if !in_switch =>
+            {
+                Some("a case label")
+            }
+            _ => None,
+        };
+        if let Some(escape) = escape {
+            return Err(format_translation_err!(
+                self.ast_context.display_loc(&self.ast_context[stmt_id].loc),
+                "Unsupported setjmp: {} in the branch taken when setjmp returns 0",
+                escape,
+            ));
+        }
+
+        let (in_loop, in_switch) = match self.ast_context[stmt_id].kind {
+            CStmtKind::While { .. } | CStmtKind::DoWhile { .. } | CStmtKind::ForLoop { .. } => {
+                (true, in_switch)
+            }
+            CStmtKind::Switch { .. } => (in_loop, true),
+            _ => (in_loop, in_switch),
+        };
+
+        for child in self.sub_stmts(stmt_id) {
+            self.check_setjmp_direct_branch(child, in_loop, in_switch)?;
+        }
+        Ok(())
+    }
+
+    /// Immediate sub-statements of a statement
+    fn sub_stmts(&self, stmt_id: CStmtId) -> Vec<CStmtId> {
+        match self.ast_context[stmt_id].kind {
+            CStmtKind::Compound(ref stmts) => stmts.clone(),
+            CStmtKind::Label(s)
+            | CStmtKind::Case(_, s, _)
+            | CStmtKind::CaseRange(_, _, s, _, _)
+            | CStmtKind::Default(s) => vec![s],
+            CStmtKind::If {
+                true_variant,
+                false_variant,
+                ..
+            } => Some(true_variant).into_iter().chain(false_variant).collect(),
+            CStmtKind::Switch { body, .. }
+            | CStmtKind::While { body, .. }
+            | CStmtKind::DoWhile { body, .. } => vec![body],
+            CStmtKind::ForLoop { init, body, .. } => init.into_iter().chain(Some(body)).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Translate the condition of an `if` statement recognized by `match_setjmp_if`. The result
+    /// is true when `setjmp` returned through a `longjmp`.
+    pub(crate) fn convert_setjmp_condition(
+        &self,
+        ctx: ExprContext,
+        setjmp: &SetjmpIf,
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        self.use_crate(ExternCrate::C2RustSetjmp);
+
+        let in_closure = self.function_context.borrow().in_setjmp_closure;
+        self.function_context.borrow_mut().in_setjmp_closure = true;
+        let body = match setjmp.direct_branch {
+            Some(branch) => self.convert_function_body(
+                ctx,
+                "<setjmp>",
+                &[branch],
+                cfg::ImplicitReturnType::Void,
+            ),
+            None => Ok(vec![]),
+        };
+        self.function_context.borrow_mut().in_setjmp_closure = in_closure;
+        let body = body?;
+        let closure = mk().closure_expr(
+            CaptureBy::Ref,
+            Movability::Movable,
+            mk().fn_decl(vec![], FunctionRetTy::Default(DUMMY_SP)),
+            mk().block_expr(mk().block(body)),
+        );
+
+        // A jump to another `jmp_buf` may still be pending after `catch_longjmp` returns
+        let jump = match setjmp.direct_branch {
+            Some(branch) if self.may_start_jump(branch) => CallJump::Maybe,
+            _ => CallJump::Never,
+        };
+
+        let env = self.convert_expr(ctx.used(), setjmp.env)?;
+        let catch = env.map(|env| {
+            mk().call_expr(
+                mk().path_expr(vec!["c2rust_setjmp", "catch_longjmp"]),
+                vec![env, closure],
+            )
+        });
+        Ok(self.convert_call_jump(jump, catch).map(|catch| {
+            let zero = mk().lit_expr(mk().int_lit(0, LitIntType::Unsuffixed));
+            mk().binary_expr(BinOpKind::Ne, catch, zero)
+        }))
+    }
+
+    /// Does the statement contain a call which may start a jump?
+    fn may_start_jump(&self, stmt_id: CStmtId) -> bool {
+        DFExpr::new(&self.ast_context, stmt_id.into())
+            .flat_map(SomeId::expr)
+            .any(|e| match self.ast_context[e].kind {
+                CExprKind::Call(_, func, _) => self.call_jump(func) != CallJump::Never,
+                _ => false,
+            })
+    }
+
+    /// Translate calls to `longjmp` into starting a jump back to the matching `setjmp` and
+    /// returning. Returns `None` if this isn't a call to `setjmp` or `longjmp`. Calls to `setjmp`
+    /// that were not recognized as part of an `if` condition are errors.
+    pub(crate) fn convert_setjmp_call(
+        &self,
+        ctx: ExprContext,
+        expr_id: CExprId,
+        name: &str,
+        args: &[CExprId],
+    ) -> Result<Option<WithStmts<P<Expr>>>, TranslationError> {
+        if !self.tcfg.translate_setjmp {
+            return Ok(None);
+        }
+
+        if SETJMP_FNS.contains(&name) {
+            return Err(format_translation_err!(
+                self.ast_context.display_loc(&self.ast_context[expr_id].loc),
+                "Unsupported setjmp: setjmp may only be used as the condition of an if statement",
+            ));
+        }
+        if !LONGJMP_FNS.contains(&name) {
+            return Ok(None);
+        }
+
+        self.use_crate(ExternCrate::C2RustSetjmp);
+        let args = self.convert_exprs(ctx.used(), args)?;
+        let call =
+            args.map(|args| mk().call_expr(mk().path_expr(vec!["c2rust_setjmp", "longjmp"]), args));
+        let call = self.convert_call_jump(CallJump::Always, call);
+        self.convert_side_effects_expr(ctx, call, "longjmp call is not supposed to be used")
+            .map(Some)
+    }
+}
@@ -8,6 +8,8 @@ use crate::c_ast::CExprKind::{Binary, Call, Conditional, ExplicitCast, ImplicitC
 use crate::c_ast::CLiteral::Integer;
 use crate::c_ast::CTypeKind::{Char, Double, Float, Int, LongLong, Short};
 use crate::c_ast::CastKind::{BitCast, IntegralCast};
+use crate::c_ast::BuiltinVaListKind;
+use std::ffi::OsStr;
 
 /// As of rustc 1.29, rust is known to be missing some SIMD functions.
 /// See https://github.com/rust-lang-nursery/stdsimd/issues/579
@@ -67,7 +69,153 @@ static SIMD_X86_64_ONLY: &[&str] = &[
     "_mm_crc32_u64",
 ];
 
+/// `__builtin_ia32_*` builtins without a Rust equivalent which `--scalar-simd-fallback` translates
+/// into scalar code on the lanes of their operands
+static SIMD_SCALAR_FALLBACKS: &[&str] = &[
+    "__builtin_ia32_vec_ext_v4hi",
+    "__builtin_ia32_vec_ext_v8hi",
+    "__builtin_ia32_vec_ext_v16hi",
+    "__builtin_ia32_vec_ext_v32qi",
+    "__builtin_ia32_vec_ext_v4di",
+    "__builtin_ia32_vec_set_v4hi",
+    "__builtin_ia32_vec_set_v8hi",
+    "__builtin_ia32_vec_set_v4si",
+    "__builtin_ia32_vec_set_v16hi",
+    "__builtin_ia32_vec_set_v32qi",
+    "__builtin_ia32_vec_set_v8si",
+    "__builtin_ia32_vec_set_v4di",
+    "__builtin_ia32_psrldqi128_byteshift",
+    "__builtin_ia32_pslldqi256_byteshift",
+    "__builtin_ia32_psrldqi256_byteshift",
+    "__builtin_ia32_blendps256",
+    "__builtin_ia32_blendpd256",
+    "__builtin_ia32_pblendw256",
+    "__builtin_ia32_pblendd128",
+    "__builtin_ia32_pblendd256",
+];
+
+/// Element type suffixes of NEON intrinsics, indexed by the element type in the type flags that
+/// clang passes to overloaded `__builtin_neon_*` builtins (`NeonTypeFlags` in clang)
+static NEON_TYPE_SUFFIXES: [&str; 11] = [
+    "s8", "s16", "s32", "s64", "p8", "p16", "p64", "p128", "f16", "f32", "f64",
+];
+
+/// Flag set in the NEON type flags for unsigned element types
+const NEON_UNSIGNED_FLAG: u64 = 0x10;
+
+/// The NEON intrinsics of `core::arch::aarch64` as of our pinned nightly. Most of `arm_neon.h`,
+/// including loads, stores, lane accesses, and shifts, doesn't have a counterpart yet.
+static NEON_INTRINSICS: &[&str] = &[
+    "vadd_f32", "vadd_f64", "vadd_s16", "vadd_s32", "vadd_s8", "vadd_u16", "vadd_u32", "vadd_u8",
+    "vaddd_s64", "vaddd_u64", "vaddl_s16", "vaddl_s32", "vaddl_s8", "vaddl_u16", "vaddl_u32",
+    "vaddl_u8", "vaddq_f32", "vaddq_f64", "vaddq_s16", "vaddq_s32", "vaddq_s64", "vaddq_s8",
+    "vaddq_u16", "vaddq_u32", "vaddq_u64", "vaddq_u8", "vmovl_s16", "vmovl_s32", "vmovl_s8",
+    "vmovl_u16", "vmovl_u32", "vmovl_u8", "vmovn_s16", "vmovn_s32", "vmovn_s64", "vmovn_u16",
+    "vmovn_u32", "vmovn_u64", "vpmax_f32", "vpmax_s16", "vpmax_s32", "vpmax_s8", "vpmax_u16",
+    "vpmax_u32", "vpmax_u8", "vpmin_f32", "vpmin_s16", "vpmin_s32", "vpmin_s8", "vpmin_u16",
+    "vpmin_u32", "vpmin_u8", "vrsqrte_f32",
+];
+
 impl<'c> Translation<'c> {
+    /// Is the translation targeting AArch64? The only target information exported by the AST
+    /// exporter is the kind of `va_list`, which is specific to the AArch64 ABI.
+    pub fn targets_aarch64(&self) -> bool {
+        match self.ast_context.va_list_kind {
+            BuiltinVaListKind::AArch64ABIBuiltinVaList => true,
+            _ => false,
+        }
+    }
+
+    /// Determine if a declaration is one of the NEON vector typedefs or intrinsics of
+    /// `arm_neon.h`. If so, a use statement for its `core::arch::aarch64` counterpart is
+    /// generated, `true` is returned, and no further translation should be done.
+    pub fn import_neon_decl(
+        &self,
+        decl_id: CDeclId,
+        name: &str,
+    ) -> Result<bool, TranslationError> {
+        if !self.is_neon_decl(decl_id) {
+            return Ok(false);
+        }
+
+        if let CDeclKind::Function { .. } = self.ast_context[decl_id].kind {
+            check_neon_intrinsic(name)?;
+        }
+        self.import_neon_item(name);
+        Ok(true)
+    }
+
+    /// Is this declaration a NEON vector typedef or intrinsic of `arm_neon.h`? Scalar typedefs
+    /// like `float32_t` and the structs of several vectors, like `int8x8x2_t`, have no
+    /// counterpart with the same layout and are translated normally.
+    fn is_neon_decl(&self, decl_id: CDeclId) -> bool {
+        let decl = &self.ast_context[decl_id];
+        let from_arm_neon = self
+            .ast_context
+            .get_source_path(decl)
+            .map_or(false, |path| {
+                path.file_name() == Some(OsStr::new("arm_neon.h"))
+            });
+        if !from_arm_neon {
+            return false;
+        }
+
+        match decl.kind {
+            CDeclKind::Function { .. } => true,
+            CDeclKind::Typedef { typ, .. } => match self.ast_context.resolve_type(typ.ctype).kind {
+                CTypeKind::Vector(..) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Does this function body use NEON types or intrinsics? Those only exist on AArch64, so
+    /// the translated function has to be restricted to it too.
+    pub fn uses_neon(&self, body: CStmtId) -> bool {
+        let is_neon_type = |mut ctype: CTypeId| loop {
+            match self.ast_context[ctype].kind {
+                CTypeKind::Typedef(decl_id) if self.is_neon_decl(decl_id) => return true,
+                CTypeKind::Typedef(decl_id) => match self.ast_context[decl_id].kind {
+                    CDeclKind::Typedef { typ, .. } => ctype = typ.ctype,
+                    _ => return false,
+                },
+                CTypeKind::Elaborated(ty) | CTypeKind::Paren(ty) => ctype = ty,
+                _ => return false,
+            }
+        };
+
+        DFExpr::new(&self.ast_context, body.into())
+            .flat_map(SomeId::expr)
+            .any(|expr_id| match self.ast_context[expr_id].kind {
+                CExprKind::DeclRef(_, decl_id, _) if self.is_neon_decl(decl_id) => true,
+                CExprKind::DeclRef(_, decl_id, _) => match self.ast_context[decl_id].kind {
+                    CDeclKind::Function { ref name, .. } => name.starts_with("__builtin_neon_"),
+                    _ => false,
+                },
+                ref kind => kind.get_type().map_or(false, is_neon_type),
+            })
+    }
+
+    /// Generate a use statement for a NEON type or intrinsic of `core::arch::aarch64`
+    fn import_neon_item(&self, name: &str) {
+        // NEON intrinsics are still behind a feature gate
+        self.use_feature("stdsimd");
+
+        self.with_cur_file_item_store(|item_store| {
+            let aarch64_attr = mk()
+                .call_attr("cfg", vec!["target_arch = \"aarch64\""])
+                .pub_();
+            let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" }.to_string();
+
+            item_store.add_use_with_attr(
+                vec![std_or_core, "arch".into(), "aarch64".into()],
+                name,
+                aarch64_attr,
+            );
+        });
+    }
+
     /// Given the name of a typedef check if its one of the SIMD types.
     /// This function returns `true` when the name of the type is one that
     /// it knows how to implement and no further translation should be done.
@@ -239,6 +387,125 @@ impl<'c> Translation<'c> {
         })
     }
 
+    /// Generate a call to a NEON intrinsic from a `__builtin_neon_*` builtin. Most intrinsics of
+    /// clang's `arm_neon.h` are macros around these builtins. The builtins whose name ends in `_v`
+    /// are overloaded: their vector operands are cast to a generic vector type, and a trailing
+    /// constant encodes the type the intrinsic operates on.
+    pub fn convert_neon_builtin(
+        &self,
+        ctx: ExprContext,
+        builtin_name: &str,
+        args: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let name = builtin_name.trim_start_matches("__builtin_neon_");
+        let (fn_name, args) = if name.ends_with("_v") {
+            let (type_flags, operands) = args
+                .split_last()
+                .ok_or_else(|| format_err!("Missing type flags in {}", builtin_name))?;
+            let operands: Vec<CExprId> = operands
+                .iter()
+                .map(|&arg| self.strip_neon_vector_cast(arg))
+                .collect();
+
+            // The suffix of an intrinsic names the type of its first vector operand, which the
+            // type flags only encode for some of the builtins. Loads have no vector operand.
+            let suffix = match operands
+                .iter()
+                .filter_map(|&arg| self.neon_type_suffix(arg))
+                .next()
+            {
+                Some(suffix) => suffix,
+                None => match *self.ast_context.resolve_expr_value(*type_flags) {
+                    CExprKind::Literal(_, Integer(flags, _)) => {
+                        let suffix = NEON_TYPE_SUFFIXES
+                            .get((flags & 0xf) as usize)
+                            .ok_or_else(|| format_err!("Unknown NEON type flags: {}", flags))?;
+                        if flags & NEON_UNSIGNED_FLAG != 0 {
+                            suffix.replacen("s", "u", 1)
+                        } else {
+                            suffix.to_string()
+                        }
+                    }
+                    ref e => Err(format_err!("Unknown NEON type flags: {:?}", e))?,
+                },
+            };
+
+            (format!("{}_{}", &name[..name.len() - 2], suffix), operands)
+        } else {
+            let args = args
+                .iter()
+                .map(|&arg| self.strip_neon_vector_cast(arg))
+                .collect();
+            (name.to_string(), args)
+        };
+
+        check_neon_intrinsic(&fn_name)?;
+        self.import_neon_item(&fn_name);
+
+        let param_translation = self.convert_exprs(ctx.used(), &args)?;
+        param_translation.and_then(|call_params| {
+            let call = mk().call_expr(mk().ident_expr(&fn_name), call_params);
+
+            if ctx.is_used() {
+                Ok(WithStmts::new_val(call))
+            } else {
+                Ok(WithStmts::new(
+                    vec![mk().expr_stmt(call)],
+                    self.panic_or_err("No value for unused NEON builtin return"),
+                ))
+            }
+        })
+    }
+
+    /// Strip the cast of a NEON vector operand to the generic vector type of a builtin
+    fn strip_neon_vector_cast(&self, expr_id: CExprId) -> CExprId {
+        match self.ast_context[expr_id].kind {
+            ExplicitCast(qty, inner_id, BitCast, _, _) => {
+                match self.ast_context.resolve_type(qty.ctype).kind {
+                    CTypeKind::Vector(..) => inner_id,
+                    _ => expr_id,
+                }
+            }
+            _ => expr_id,
+        }
+    }
+
+    /// Get the intrinsic suffix of the element type of a NEON vector expression from the name of
+    /// its typedef, e.g. `u16` for `uint16x8_t`
+    fn neon_type_suffix(&self, expr_id: CExprId) -> Option<String> {
+        let mut ctype = self.ast_context[expr_id].kind.get_type()?;
+        let name = loop {
+            match self.ast_context[ctype].kind {
+                CTypeKind::Typedef(decl_id) => match self.ast_context[decl_id].kind {
+                    CDeclKind::Typedef { ref name, typ, .. } => {
+                        if name.ends_with("_t") && name.contains('x') {
+                            break name;
+                        }
+                        ctype = typ.ctype;
+                    }
+                    _ => return None,
+                },
+                CTypeKind::Elaborated(ty) | CTypeKind::Paren(ty) => ctype = ty,
+                _ => return None,
+            }
+        };
+
+        // `<element type>x<lanes>_t`
+        let elem = &name[..name.find('x')?];
+        let (prefix, bits) = if elem.starts_with("uint") {
+            ("u", &elem[4..])
+        } else if elem.starts_with("int") {
+            ("s", &elem[3..])
+        } else if elem.starts_with("float") {
+            ("f", &elem[5..])
+        } else if elem.starts_with("poly") {
+            ("p", &elem[4..])
+        } else {
+            return None;
+        };
+        Some(format!("{}{}", prefix, bits))
+    }
+
     /// Generate a zero value to be used for initialization of a given vector type. The type
     /// is specified with the underlying element type and the number of elements in the vector.
     pub fn implicit_vector_default(
@@ -247,6 +514,15 @@ impl<'c> Translation<'c> {
         len: usize,
         is_static: bool,
     ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        // Only x86 has intrinsics to build vectors from values, so vectors of other targets are
+        // built by transmuting bytes
+        if !self.ast_context.targets_x86() {
+            let bytes = len * self.element_size(ctype)?;
+            return Ok(WithStmts::new_unsafe_val(
+                self.transmute_vector_bytes(bytes),
+            ));
+        }
+
         // NOTE: This is only for x86/_64, and so support for other architectures
         // might need some sort of disambiguation to be exported
         let (fn_name, bytes) = match (&self.ast_context[ctype].kind, len) {
@@ -272,16 +548,9 @@ impl<'c> Translation<'c> {
         if is_static {
             self.use_feature("const_transmute");
 
-            let zero_expr = mk().lit_expr(mk().int_lit(0, "u8"));
-            let n_bytes_expr = mk().lit_expr(mk().int_lit(bytes, ""));
-            let expr = mk().repeat_expr(zero_expr, n_bytes_expr);
-
-            Ok(WithStmts::new_unsafe_val(transmute_expr(
-                mk().infer_ty(),
-                mk().infer_ty(),
-                expr,
-                self.tcfg.emit_no_std,
-            )))
+            Ok(WithStmts::new_unsafe_val(
+                self.transmute_vector_bytes(bytes),
+            ))
         } else {
             self.import_simd_function(fn_name)
                 .expect("None of these fns should be unsupported in rust");
@@ -290,6 +559,36 @@ impl<'c> Translation<'c> {
         }
     }
 
+    /// Transmute zeroed bytes into a vector
+    fn transmute_vector_bytes(&self, bytes: usize) -> P<Expr> {
+        let zero_expr = mk().lit_expr(mk().int_lit(0, "u8"));
+        let n_bytes_expr = mk().lit_expr(mk().int_lit(bytes as u128, ""));
+        let expr = mk().repeat_expr(zero_expr, n_bytes_expr);
+
+        transmute_expr(
+            mk().infer_ty(),
+            mk().infer_ty(),
+            expr,
+            self.tcfg.emit_no_std,
+        )
+    }
+
+    /// Size in bytes of the elements of a vector
+    fn element_size(&self, ctype: CTypeId) -> Result<usize, TranslationError> {
+        match self.ast_context.resolve_type(ctype).kind {
+            CTypeKind::Char | CTypeKind::SChar | CTypeKind::UChar => Ok(1),
+            CTypeKind::Short | CTypeKind::UShort | CTypeKind::Half => Ok(2),
+            CTypeKind::Int | CTypeKind::UInt | CTypeKind::Float => Ok(4),
+            CTypeKind::Long
+            | CTypeKind::ULong
+            | CTypeKind::LongLong
+            | CTypeKind::ULongLong
+            | CTypeKind::Double => Ok(8),
+            CTypeKind::Int128 | CTypeKind::UInt128 => Ok(16),
+            ref kind => Err(format_err!("Unsupported vector element: {:?}", kind))?,
+        }
+    }
+
     /// Translate a list initializer corresponding to a vector type.
     pub fn vector_list_initializer(
         &self,
@@ -301,8 +600,8 @@ impl<'c> Translation<'c> {
         let param_translation = self.convert_exprs(ctx, ids)?;
         param_translation.and_then(|mut params| {
             // When used in a static, we cannot call the standard functions since they
-            // are not const and so we are forced to transmute
-            let call = if ctx.is_static {
+            // are not const and so we are forced to transmute. Only x86 has such functions.
+            let call = if ctx.is_static || !self.ast_context.targets_x86() {
                 let tuple = mk().tuple_expr(params);
                 let transmute = transmute_expr(
                     mk().infer_ty(),
@@ -311,7 +610,9 @@ impl<'c> Translation<'c> {
                     self.tcfg.emit_no_std,
                 );
 
-                self.use_feature("const_transmute");
+                if ctx.is_static {
+                    self.use_feature("const_transmute");
+                }
 
                 transmute
             } else {
@@ -560,7 +861,10 @@ impl<'c> Translation<'c> {
         use self::CastKind::BuiltinFnToFnPtr;
 
         match self.ast_context[expr_id].kind {
-            CExprKind::ShuffleVector(..) => is_explicit && kind == CastKind::BitCast,
+            // The scalar fallback of other targets gives the result its own vector type
+            CExprKind::ShuffleVector(..) | CExprKind::ConvertVector(..) => {
+                is_explicit && kind == CastKind::BitCast && self.ast_context.targets_x86()
+            }
             CExprKind::Call(_, fn_id, _) => {
                 let fn_expr = &self.ast_context[fn_id].kind;
 
@@ -571,7 +875,13 @@ impl<'c> Translation<'c> {
                         let decl = &self.ast_context[*decl_id].kind;
 
                         if let CDeclKind::Function { ref name, .. } = decl {
-                            return name.starts_with("__builtin_ia32_");
+                            // The scalar fallback of builtins returning a lane gives it the type
+                            // of the builtin, which the cast converts as usual
+                            if self.has_simd_scalar_fallback(name) {
+                                return self.vector_lanes(expr_id).is_some();
+                            }
+                            return name.starts_with("__builtin_ia32_")
+                                || name.starts_with("__builtin_neon_");
                         }
                     }
                 }
@@ -581,4 +891,356 @@ impl<'c> Translation<'c> {
             _ => false,
         }
     }
+
+    /// Does `--scalar-simd-fallback` translate this builtin into scalar code?
+    fn has_simd_scalar_fallback(&self, builtin_name: &str) -> bool {
+        self.tcfg.scalar_simd_fallback && SIMD_SCALAR_FALLBACKS.contains(&builtin_name)
+    }
+
+    /// The element type and number of lanes of an expression of vector type
+    fn vector_lanes(&self, expr_id: CExprId) -> Option<(CTypeId, usize)> {
+        let ty = self.ast_context[expr_id].kind.get_type()?;
+        match self.ast_context.resolve_type(ty).kind {
+            CTypeKind::Vector(CQualTypeId { ctype, .. }, len) => Some((ctype, len)),
+            _ => None,
+        }
+    }
+
+    /// Strip the bit casts between vector types around an operand. The scalar fallback
+    /// reinterprets its operands as lanes of the type it expects anyway.
+    fn strip_vector_casts(&self, expr_id: CExprId) -> CExprId {
+        match self.ast_context[expr_id].kind {
+            ExplicitCast(_, inner, BitCast, _, _)
+            | ExplicitCast(_, inner, CastKind::NoOp, _, _)
+            | ImplicitCast(_, inner, BitCast, _, _)
+            | CExprKind::Paren(_, inner)
+                if self.vector_lanes(inner).is_some() =>
+            {
+                self.strip_vector_casts(inner)
+            }
+            _ => expr_id,
+        }
+    }
+
+    /// The element type, translated element type, and number of lanes of a vector operand
+    fn operand_lanes(&self, expr_id: CExprId) -> Result<(CTypeId, P<Ty>, usize), TranslationError> {
+        let (elem, len) = self
+            .vector_lanes(expr_id)
+            .ok_or_else(|| format_err!("Expected an operand of vector type"))?;
+        Ok((elem, self.convert_type(elem)?, len))
+    }
+
+    /// The vector type of the given lanes, which the scalar fallback reinterprets its results as.
+    /// On x86, that is the x86 vector type which the casts around the builtins it translates
+    /// expect. Other targets keep vectors as arrays of lanes.
+    fn lanes_vector_ty(&self, elem: CTypeId, len: usize) -> Result<P<Ty>, TranslationError> {
+        if !self.ast_context.targets_x86() {
+            return Ok(mk().array_ty(self.convert_type(elem)?, lane_index(len)));
+        }
+
+        let size = self.element_size(elem)? * len;
+        let name = match (&self.ast_context.resolve_type(elem).kind, size) {
+            (_, 8) => "__m64",
+            (Float, 16) => "__m128",
+            (Double, 16) => "__m128d",
+            (_, 16) => "__m128i",
+            (Float, 32) => "__m256",
+            (Double, 32) => "__m256d",
+            (_, 32) => "__m256i",
+            (_, size) => Err(format_err!("Unsupported vector size: {}", size))?,
+        };
+        self.import_simd_typedef(name);
+        Ok(mk().ident_ty(name))
+    }
+
+    /// Translate a vector operand and bind it to a fresh variable, reinterpreted as an array of
+    /// `len` lanes of type `lane_ty`. Returns the statements and the name of the variable.
+    fn bind_lanes(
+        &self,
+        ctx: ExprContext,
+        expr_id: CExprId,
+        name: &str,
+        lane_ty: P<Ty>,
+        len: usize,
+        mutable: bool,
+    ) -> Result<(Vec<Stmt>, String), TranslationError> {
+        let operand = self.strip_vector_casts(expr_id);
+        let (mut stmts, val) = self.convert_expr(ctx.used(), operand)?.discard_unsafe();
+
+        let name = self.renamer.borrow_mut().pick_name(name);
+        let pat = if mutable {
+            mk().mutbl().ident_pat(&name)
+        } else {
+            mk().ident_pat(&name)
+        };
+        let lanes_ty = mk().array_ty(lane_ty, lane_index(len));
+        let lanes = transmute_expr(mk().infer_ty(), mk().infer_ty(), val, self.tcfg.emit_no_std);
+        stmts.push(mk().local_stmt(P(mk().local(pat, Some(lanes_ty), Some(lanes)))));
+
+        Ok((stmts, name))
+    }
+
+    /// Translate a scalar operand and bind it to a fresh variable of type `ty`. Returns the
+    /// statements and the name of the variable.
+    fn bind_scalar(
+        &self,
+        ctx: ExprContext,
+        expr_id: CExprId,
+        name: &str,
+        ty: &str,
+    ) -> Result<(Vec<Stmt>, String), TranslationError> {
+        let (mut stmts, val) = self.convert_expr(ctx.used(), expr_id)?.discard_unsafe();
+
+        let name = self.renamer.borrow_mut().pick_name(name);
+        let ty = mk().path_ty(vec![ty]);
+        let val = mk().cast_expr(val, ty.clone());
+        stmts.push(mk().local_stmt(P(mk().local(mk().ident_pat(&name), Some(ty), Some(val)))));
+
+        Ok((stmts, name))
+    }
+
+    /// Reinterpret an array of lanes as a vector of type `vector_ty`
+    fn vector_from_lanes(
+        &self,
+        ctx: ExprContext,
+        stmts: Vec<Stmt>,
+        vector_ty: P<Ty>,
+        lanes: Vec<P<Expr>>,
+    ) -> WithStmts<P<Expr>> {
+        let mut val = if ctx.is_used() {
+            let lanes = mk().array_expr(lanes);
+            let vector = transmute_expr(mk().infer_ty(), vector_ty, lanes, self.tcfg.emit_no_std);
+            WithStmts::new(stmts, vector)
+        } else {
+            WithStmts::new(stmts, self.panic_or_err("No value for unused vector"))
+        };
+        val.set_unsafe();
+        val
+    }
+
+    /// Translate a shuffle of two vectors into scalar code, for shuffles which don't correspond
+    /// to a Rust SIMD function
+    pub fn convert_shuffle_vector_fallback(
+        &self,
+        ctx: ExprContext,
+        child_expr_ids: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elem, lane_ty, len) = self.operand_lanes(child_expr_ids[0])?;
+        let (mut stmts, lhs) =
+            self.bind_lanes(ctx, child_expr_ids[0], "lhs", lane_ty.clone(), len, false)?;
+        let (rhs_stmts, rhs) =
+            self.bind_lanes(ctx, child_expr_ids[1], "rhs", lane_ty.clone(), len, false)?;
+        stmts.extend(rhs_stmts);
+
+        // The indices select among the lanes of both vectors
+        let all_lanes = [&lhs, &rhs]
+            .iter()
+            .flat_map(|name| {
+                let name = name.as_str();
+                (0..len).map(move |i| mk().index_expr(mk().ident_expr(name), lane_index(i)))
+            })
+            .collect();
+        let lanes = self.renamer.borrow_mut().pick_name("lanes");
+        let lanes_ty = mk().array_ty(lane_ty, lane_index(2 * len));
+        stmts.push(mk().local_stmt(P(mk().local(
+            mk().ident_pat(&lanes),
+            Some(lanes_ty),
+            Some(mk().array_expr(all_lanes)),
+        ))));
+
+        let (index_stmts, indices) = self
+            .convert_exprs(ctx.used(), &child_expr_ids[2..])?
+            .discard_unsafe();
+        stmts.extend(index_stmts);
+        let result: Vec<P<Expr>> = indices
+            .into_iter()
+            .map(|index| {
+                // An index of -1 leaves the lane undefined, so it may select any lane
+                let index = mk().cast_expr(index, mk().path_ty(vec!["usize"]));
+                let index = mk().binary_expr(BinOpKind::Rem, index, lane_index(2 * len));
+                mk().index_expr(mk().ident_expr(&lanes), index)
+            })
+            .collect();
+
+        let vector_ty = self.lanes_vector_ty(elem, result.len())?;
+        Ok(self.vector_from_lanes(ctx, stmts, vector_ty, result))
+    }
+
+    /// Translate `__builtin_convertvector` into scalar conversions of each lane
+    pub fn convert_vector_fallback(
+        &self,
+        ctx: ExprContext,
+        ty: CQualTypeId,
+        child_expr_ids: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let elem = match self.ast_context.resolve_type(ty.ctype).kind {
+            CTypeKind::Vector(CQualTypeId { ctype, .. }, _) => ctype,
+            ref kind => Err(format_err!("Unexpected vector conversion to {:?}", kind))?,
+        };
+        let target_ty = self.convert_type(elem)?;
+
+        let (_, lane_ty, len) = self.operand_lanes(child_expr_ids[0])?;
+        let (stmts, src) = self.bind_lanes(ctx, child_expr_ids[0], "src", lane_ty, len, false)?;
+        let result = (0..len)
+            .map(|i| {
+                let lane = mk().index_expr(mk().ident_expr(&src), lane_index(i));
+                mk().cast_expr(lane, target_ty.clone())
+            })
+            .collect();
+
+        let vector_ty = self.lanes_vector_ty(elem, len)?;
+        Ok(self.vector_from_lanes(ctx, stmts, vector_ty, result))
+    }
+
+    /// Translate a `__builtin_ia32_*` builtin without a Rust equivalent into scalar code on the
+    /// lanes of its operands. Returns `None` if the builtin has no scalar fallback, or it isn't
+    /// enabled.
+    pub fn convert_simd_builtin_fallback(
+        &self,
+        ctx: ExprContext,
+        builtin_name: &str,
+        args: &[CExprId],
+    ) -> Result<Option<WithStmts<P<Expr>>>, TranslationError> {
+        if !self.has_simd_scalar_fallback(builtin_name) {
+            return Ok(None);
+        }
+
+        let op = builtin_name.trim_start_matches("__builtin_ia32_");
+        let val = if op.starts_with("vec_ext_") {
+            self.vector_extract_fallback(ctx, args)?
+        } else if op.starts_with("vec_set_") {
+            self.vector_insert_fallback(ctx, args)?
+        } else if op.ends_with("_byteshift") {
+            self.byte_shift_fallback(ctx, op.starts_with("pslldq"), args)?
+        } else {
+            self.blend_fallback(ctx, args)?
+        };
+
+        Ok(Some(val))
+    }
+
+    /// Extract lane `args[1]` of vector `args[0]`
+    fn vector_extract_fallback(
+        &self,
+        ctx: ExprContext,
+        args: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (_, lane_ty, len) = self.operand_lanes(args[0])?;
+        let (mut stmts, lanes) = self.bind_lanes(ctx, args[0], "lanes", lane_ty, len, false)?;
+        let (index_stmts, index) = self.bind_scalar(ctx, args[1], "index", "usize")?;
+        stmts.extend(index_stmts);
+
+        let index = mk().binary_expr(BinOpKind::Rem, mk().ident_expr(&index), lane_index(len));
+        let mut val = WithStmts::new(stmts, mk().index_expr(mk().ident_expr(&lanes), index));
+        val.set_unsafe();
+        Ok(val)
+    }
+
+    /// Replace lane `args[2]` of vector `args[0]` with `args[1]`
+    fn vector_insert_fallback(
+        &self,
+        ctx: ExprContext,
+        args: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elem, lane_ty, len) = self.operand_lanes(args[0])?;
+        let (mut stmts, lanes) = self.bind_lanes(ctx, args[0], "lanes", lane_ty, len, true)?;
+        let (lane_stmts, lane) = self.convert_expr(ctx.used(), args[1])?.discard_unsafe();
+        stmts.extend(lane_stmts);
+        let (index_stmts, index) = self.bind_scalar(ctx, args[2], "index", "usize")?;
+        stmts.extend(index_stmts);
+
+        let index = mk().binary_expr(BinOpKind::Rem, mk().ident_expr(&index), lane_index(len));
+        let target = mk().index_expr(mk().ident_expr(&lanes), index);
+        stmts.push(mk().semi_stmt(mk().assign_expr(target, lane)));
+
+        let result = (0..len)
+            .map(|i| mk().index_expr(mk().ident_expr(&lanes), lane_index(i)))
+            .collect();
+        let vector_ty = self.lanes_vector_ty(elem, len)?;
+        Ok(self.vector_from_lanes(ctx, stmts, vector_ty, result))
+    }
+
+    /// Shift each 128-bit lane of vector `args[0]` by `args[1]` bytes, shifting in zeros
+    fn byte_shift_fallback(
+        &self,
+        ctx: ExprContext,
+        left: bool,
+        args: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elem, _, len) = self.operand_lanes(args[0])?;
+        let bytes = self.element_size(elem)? * len;
+        let u8_ty = mk().path_ty(vec!["u8"]);
+        let (mut stmts, src) = self.bind_lanes(ctx, args[0], "bytes", u8_ty, bytes, false)?;
+        let (shift_stmts, shift) = self.bind_scalar(ctx, args[1], "shift", "usize")?;
+        stmts.extend(shift_stmts);
+
+        let result = (0..bytes)
+            .map(|i| {
+                let byte = i % 16;
+                let shift = mk().ident_expr(&shift);
+                // Byte `i` comes from byte `i - shift` or `i + shift` of the same 128-bit lane
+                let (in_range, op) = if left {
+                    let in_range = mk().binary_expr(BinOpKind::Le, shift.clone(), lane_index(byte));
+                    (in_range, BinOpKind::Sub)
+                } else {
+                    let in_range =
+                        mk().binary_expr(BinOpKind::Lt, shift.clone(), lane_index(16 - byte));
+                    (in_range, BinOpKind::Add)
+                };
+                let index = mk().binary_expr(op, lane_index(i), shift);
+                let shifted = mk().index_expr(mk().ident_expr(&src), index);
+                let shifted = mk().block(vec![mk().expr_stmt(shifted)]);
+                let zero = mk().lit_expr(mk().int_lit(0, ""));
+                mk().ifte_expr(in_range, shifted, Some(zero))
+            })
+            .collect();
+
+        let vector_ty = self.lanes_vector_ty(elem, len)?;
+        Ok(self.vector_from_lanes(ctx, stmts, vector_ty, result))
+    }
+
+    /// Select each lane from vector `args[1]` if the corresponding bit of `args[2]` is set, and
+    /// from vector `args[0]` otherwise. Vectors of more than 8 lanes reuse the bits for each
+    /// group of 8 lanes.
+    fn blend_fallback(
+        &self,
+        ctx: ExprContext,
+        args: &[CExprId],
+    ) -> Result<WithStmts<P<Expr>>, TranslationError> {
+        let (elem, lane_ty, len) = self.operand_lanes(args[0])?;
+        let (mut stmts, lhs) = self.bind_lanes(ctx, args[0], "lhs", lane_ty.clone(), len, false)?;
+        let (rhs_stmts, rhs) = self.bind_lanes(ctx, args[1], "rhs", lane_ty, len, false)?;
+        stmts.extend(rhs_stmts);
+        let (mask_stmts, mask) = self.bind_scalar(ctx, args[2], "mask", "u32")?;
+        stmts.extend(mask_stmts);
+
+        let result = (0..len)
+            .map(|i| {
+                let bit =
+                    mk().binary_expr(BinOpKind::Shr, mk().ident_expr(&mask), lane_index(i % 8));
+                let bit = mk().binary_expr(BinOpKind::BitAnd, bit, lane_index(1));
+                let is_set = mk().binary_expr(BinOpKind::Ne, bit, lane_index(0));
+                let lane = |name: &str| mk().index_expr(mk().ident_expr(name), lane_index(i));
+                let rhs_block = mk().block(vec![mk().expr_stmt(lane(rhs.as_str()))]);
+                mk().ifte_expr(is_set, rhs_block, Some(lane(lhs.as_str())))
+            })
+            .collect();
+
+        let vector_ty = self.lanes_vector_ty(elem, len)?;
+        Ok(self.vector_from_lanes(ctx, stmts, vector_ty, result))
+    }
+}
+
+/// An unsuffixed integer literal, for lane indices and counts
+fn lane_index(i: usize) -> P<Expr> {
+    mk().lit_expr(mk().int_lit(i as u128, ""))
+}
+
+/// Check that a NEON intrinsic exists in `core::arch::aarch64`
+fn check_neon_intrinsic(name: &str) -> Result<(), TranslationError> {
+    if NEON_INTRINSICS.contains(&name) {
+        Ok(())
+    } else {
+        Err(format_err!("NEON intrinsic {} doesn't currently have a rust counterpart", name).into())
+    }
 }
@@ -1,23 +1,25 @@
 #![deny(missing_docs)]
 //! This module provides translation for bitfield structs and operations on them. Generated code
-//! requires the use of the c2rust-bitfields crate.
+//! requires the use of the c2rust-bitfields crate. It also provides slice accessors for
+//! flexible array members.
 
 use std::collections::HashSet;
 use std::ops::Index;
 
 use super::TranslationError;
-use crate::c_ast::{BinOp, CDeclId, CDeclKind, CExprId, CRecordId, CTypeId};
+use crate::c_ast::{BinOp, CDeclId, CDeclKind, CExprId, CRecordId, CTypeId, CTypeKind};
 use crate::translator::{ExprContext, Translation, PADDING_SUFFIX};
 use crate::with_stmts::WithStmts;
 use c2rust_ast_builder::mk;
 use c2rust_ast_printer::pprust;
 use syntax::ast::{
-    self, AttrStyle, BinOpKind, Expr, ExprKind, Lit, LitIntType, LitKind, MetaItemKind,
-    NestedMetaItem, StmtKind, StrStyle, StructField, Ty, TyKind,
+    self, AttrStyle, BinOpKind, Expr, ExprKind, FunctionRetTy, Item, Lit, LitIntType, LitKind,
+    MetaItemKind, Mutability, NestedMetaItem, SelfKind, StmtKind, StrStyle, StructField, Ty,
+    TyKind,
 };
 use syntax::ptr::P;
 use syntax::source_map::symbol::Symbol;
-use syntax_pos::DUMMY_SP;
+use syntax_pos::{Span, DUMMY_SP};
 
 use itertools::EitherOrBoth::{Both, Right};
 use itertools::Itertools;
@@ -652,6 +654,98 @@ impl<'a> Translation<'a> {
             .map(|fields| mk().struct_expr(name.as_str(), fields)))
     }
 
+    /// Flexible array members (`T data[]`, as well as the GNU `T data[0]` and
+    /// older `T data[1]` idioms) translate to arrays of zero or one element,
+    /// so this method generates accessors that view the trailing storage as a slice:
+    ///
+    /// ```ignore
+    /// impl S {
+    ///     pub unsafe fn data(&self, len: usize) -> &[T] { ... }
+    ///     pub unsafe fn data_mut(&mut self, len: usize) -> &mut [T] { ... }
+    /// }
+    /// ```
+    ///
+    /// Returns `None` if the last field of the struct is not a flexible array member.
+    pub fn convert_flexible_array_accessors(
+        &self,
+        struct_id: CRecordId,
+        struct_name: &str,
+        field_ids: &[CDeclId],
+        span: Span,
+    ) -> Result<Option<P<Item>>, TranslationError> {
+        let field_id = match field_ids.last() {
+            Some(field_id)
+                if self
+                    .potential_flexible_array_members
+                    .borrow()
+                    .contains(field_id) =>
+            {
+                *field_id
+            }
+            _ => return Ok(None),
+        };
+        let (elt, offset) = match self.ast_context[field_id].kind {
+            CDeclKind::Field {
+                typ,
+                platform_bit_offset,
+                ..
+            } => match self.ast_context.resolve_type(typ.ctype).kind {
+                CTypeKind::IncompleteArray(elt) | CTypeKind::ConstantArray(elt, _) => {
+                    (elt, platform_bit_offset / 8)
+                }
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let field_name = self
+            .type_converter
+            .borrow()
+            .resolve_field_name(Some(struct_id), field_id)
+            .ok_or_else(|| format_err!("Could not find flexible array member name"))?;
+        let elt_ty = self.convert_type(elt)?;
+
+        let std_or_core = if self.tcfg.emit_no_std { "core" } else { "std" };
+        let accessor = |mutbl: Mutability| {
+            let (method_name, from_raw_parts) = match mutbl {
+                Mutability::Immutable => (field_name.clone(), "from_raw_parts"),
+                Mutability::Mutable => (format!("{}_mut", field_name), "from_raw_parts_mut"),
+            };
+
+            // The elements extend past the array field, so the pointer to them has to be
+            // derived from `self` rather than from a reference to the field:
+            // ::std::slice::from_raw_parts(
+            //     (self as *const Self as *const u8).offset(OFFSET) as *const T,
+            //     len,
+            // )
+            let ptr_ty = |ty: P<Ty>| mk().set_mutbl(mutbl).ptr_ty(ty);
+            let this = mk().cast_expr(mk().ident_expr("self"), ptr_ty(mk().path_ty(vec!["Self"])));
+            let bytes = mk().cast_expr(this, ptr_ty(mk().path_ty(vec!["u8"])));
+            let offset = mk().lit_expr(mk().int_lit(offset.into(), LitIntType::Unsuffixed));
+            let elts = mk().method_call_expr(bytes, "offset", vec![offset]);
+            let ptr = mk().cast_expr(elts, ptr_ty(elt_ty.clone()));
+            let slice = mk().call_expr(
+                mk().path_expr(vec!["", std_or_core, "slice", from_raw_parts]),
+                vec![ptr, mk().ident_expr("len")],
+            );
+            let body = mk().block(vec![mk().expr_stmt(slice)]);
+
+            let self_arg = mk().self_arg(SelfKind::Region(None, mutbl));
+            let len_arg = mk().arg(mk().path_ty(vec!["usize"]), mk().ident_pat("len"));
+            let ret_ty = mk().set_mutbl(mutbl).ref_ty(mk().slice_ty(elt_ty.clone()));
+            let decl = mk().fn_decl(vec![self_arg, len_arg], FunctionRetTy::Ty(ret_ty));
+            mk().pub_().unsafe_().fn_impl_item(method_name, decl, body)
+        };
+
+        let accessors = vec![
+            accessor(Mutability::Immutable),
+            accessor(Mutability::Mutable),
+        ];
+        Ok(Some(
+            mk().span(span)
+                .impl_item(mk().path_ty(vec![struct_name]), accessors),
+        ))
+    }
+
     /// This method handles conversion of assignment operators on bitfields.
     /// Regular fields would look like this:
     /// A) bf.a = 1;
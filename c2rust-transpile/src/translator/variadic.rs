@@ -113,6 +113,23 @@ impl<'c> Translation<'c> {
         if self.tcfg.translate_valist {
             let val = self.convert_expr(ctx.used(), val_id)?;
 
+            // `VaListImpl::arg` can't read an x87 `long double` into an `f128`
+            if self.ast_context.resolve_type(ty.ctype).kind == CTypeKind::LongDouble {
+                self.use_crate(ExternCrate::C2RustLongDouble);
+                return val.and_then(|val| {
+                    let va_arg = mk().path_expr(vec!["c2rust_long_double", "va_arg"]);
+                    let val = mk().call_expr(va_arg, vec![val]);
+                    if ctx.is_unused() {
+                        Ok(WithStmts::new(
+                            vec![mk().semi_stmt(val)],
+                            self.panic_or_err("convert_vaarg unused"),
+                        ))
+                    } else {
+                        Ok(WithStmts::new_val(val))
+                    }
+                });
+            }
+
             // The current implementation of the C-variadics feature doesn't allow us to
             // return `Option<fn(...) -> _>` from `VaList::arg`, so we detect function pointers
             // and construct the corresponding unsafe type `* mut fn(...) -> _`.
@@ -182,12 +199,15 @@ impl<'c> Translation<'c> {
         }
     }
 
-    /// Update the current function context by i) enabling the C variadics feature, ii) naming the
-    /// Rust function argument that corresponds to the elipsis in the original C function, and iii)
-    /// building a list of variable declarations to be translated into `VaListImpl`s. Returns the
-    /// name of the `VaList` function argument for convenience.
-    pub fn register_va_decls(&self, body: CStmtId) -> String {
-        self.use_feature("c_variadic");
+    /// Update the current function context by i) enabling the C variadics feature, unless the
+    /// variadic arguments are passed as a slice, ii) naming the Rust function argument that
+    /// corresponds to the elipsis in the original C function, and iii) building a list of variable
+    /// declarations to be translated into `VaListImpl`s. Returns the name of the `VaList`
+    /// function argument for convenience.
+    pub fn register_va_decls(&self, body: CStmtId, va_args_slice: bool) -> String {
+        if !va_args_slice {
+            self.use_feature("c_variadic");
+        }
 
         let va_list_arg_name = self.renamer.borrow_mut().pick_name("args");
 
@@ -211,7 +231,173 @@ impl<'c> Translation<'c> {
         let mut fn_ctx = self.function_context.borrow_mut();
         fn_ctx.va_list_arg_name = Some(va_list_arg_name.clone());
         fn_ctx.va_list_decl_ids = Some(va_list_decl_ids);
+        fn_ctx.va_args_slice = va_args_slice;
 
         va_list_arg_name
     }
+
+    /// Find the variadic functions to translate into functions taking their variadic arguments
+    /// as a slice of `c2rust_varargs::VarArg`s, which unlike C-variadic definitions don't need
+    /// nightly Rust. Every caller has to build that slice, so these are the `static` functions
+    /// which are only ever called directly, with variadic arguments that fit in a `VarArg`.
+    pub(crate) fn find_slice_variadic_fns(&self) -> IndexSet<CDeclId> {
+        let address_taken = self.address_taken_fns();
+        let candidates: IndexSet<CDeclId> = self
+            .ast_context
+            .c_decls_top
+            .iter()
+            .cloned()
+            .filter(|&decl_id| match self.ast_context[decl_id].kind {
+                CDeclKind::Function {
+                    is_global: false,
+                    typ,
+                    body: Some(body),
+                    ..
+                } => {
+                    let is_variadic = match self.ast_context.resolve_type(typ).kind {
+                        CTypeKind::Function(_, _, is_variadic, _, _) => is_variadic,
+                        _ => false,
+                    };
+                    is_variadic && !address_taken.contains(&decl_id) && self.va_lists_are_local(body)
+                }
+                _ => false,
+            })
+            .collect();
+
+        let mut rejected = IndexSet::new();
+        for &decl_id in &self.ast_context.c_decls_top {
+            for expr_id in DFExpr::new(&self.ast_context, decl_id.into()).flat_map(SomeId::expr) {
+                let (func, args) = match self.ast_context[expr_id].kind {
+                    CExprKind::Call(_, func, ref args) => (func, args),
+                    _ => continue,
+                };
+                let callee = match self.direct_callee(func) {
+                    Some(callee) if candidates.contains(&callee) => callee,
+                    _ => continue,
+                };
+                let num_params = match self.ast_context[callee].kind {
+                    CDeclKind::Function { ref parameters, .. } => parameters.len(),
+                    _ => continue,
+                };
+                let fits = args.iter().skip(num_params).all(|&arg| {
+                    self.ast_context[arg]
+                        .kind
+                        .get_type()
+                        .map_or(false, |ty| self.is_var_arg_type(ty))
+                });
+                if !fits {
+                    rejected.insert(callee);
+                }
+            }
+        }
+
+        candidates.difference(&rejected).cloned().collect()
+    }
+
+    /// Whether each use of a `va_list` in the function body is a `va_start`, `va_copy`, `va_end`
+    /// or a `va_arg` of a type that fits in a `VarArg`, i.e., no `va_list` leaves the function.
+    fn va_lists_are_local(&self, body: CStmtId) -> bool {
+        // `va_list`s which are arrays decay to pointers when used
+        let strip_cast = |e: CExprId| match self.ast_context[e].kind {
+            CExprKind::ImplicitCast(_, e, _, _, _) => e,
+            _ => e,
+        };
+
+        let mut va_list_refs = vec![];
+        let mut allowed_refs = IndexSet::new();
+        for expr_id in DFExpr::new(&self.ast_context, body.into()).flat_map(SomeId::expr) {
+            match self.ast_context[expr_id].kind {
+                CExprKind::DeclRef(ty, _, _) if self.ast_context.is_va_list(ty.ctype) => {
+                    va_list_refs.push(expr_id);
+                }
+                CExprKind::VAArg(ty, e) => {
+                    if !self.is_var_arg_type(ty.ctype) {
+                        return false;
+                    }
+                    allowed_refs.insert(strip_cast(e));
+                }
+                CExprKind::Call(_, _, ref args) if self.match_vapart(expr_id).is_some() => {
+                    allowed_refs.extend(args.iter().map(|&e| strip_cast(e)));
+                }
+                _ => {}
+            }
+        }
+        va_list_refs.iter().all(|e| allowed_refs.contains(e))
+    }
+
+    /// Whether values of a C type can be passed in a `c2rust_varargs::VarArg`
+    fn is_var_arg_type(&self, typ: CTypeId) -> bool {
+        match self.ast_context.resolve_type(typ).kind {
+            CTypeKind::Bool | CTypeKind::Int128 | CTypeKind::UInt128 => false,
+            CTypeKind::Double => true,
+            CTypeKind::Pointer(..) => !self.ast_context.is_function_pointer(typ),
+            ref k => k.is_integral_type() || k.is_enum(),
+        }
+    }
+
+    /// For a direct call to a function taking its variadic arguments as a slice, get the number
+    /// of fixed parameters of that function
+    pub(crate) fn callee_slice_variadic_params(&self, func: CExprId) -> Option<usize> {
+        let decl_id = self.direct_callee(func)?;
+        if !self.slice_variadic_fns.contains(&decl_id) {
+            return None;
+        }
+        match self.ast_context[decl_id].kind {
+            CDeclKind::Function { ref parameters, .. } => Some(parameters.len()),
+            _ => None,
+        }
+    }
+
+    /// Pass the arguments of a call after the first `num_params` as a slice of `VarArg`s:
+    /// `f(a, &[VarArg::from(b), VarArg::from(c)])`
+    pub(crate) fn convert_slice_variadic_args(
+        &self,
+        num_params: usize,
+        mut args: Vec<P<Expr>>,
+    ) -> Vec<P<Expr>> {
+        self.use_crate(ExternCrate::C2RustVarargs);
+        let var_args = args
+            .split_off(num_params.min(args.len()))
+            .into_iter()
+            .map(|arg| {
+                let from = mk().path_expr(vec!["c2rust_varargs", "VarArg", "from"]);
+                mk().call_expr(from, vec![arg])
+            })
+            .collect();
+        args.push(mk().addr_of_expr(mk().array_expr(var_args)));
+        args
+    }
+
+    /// Pass the `long double` arguments of a call after the first `num_params` the way C passes
+    /// them, which is not how `f128` is passed: `f(a, VaLongDouble::from(b))`
+    pub(crate) fn convert_long_double_var_args(
+        &self,
+        num_params: usize,
+        c_args: &[CExprId],
+        args: Vec<P<Expr>>,
+    ) -> Vec<P<Expr>> {
+        args.into_iter()
+            .zip(c_args)
+            .enumerate()
+            .map(|(i, (arg, &c_arg))| {
+                let is_long_double = i >= num_params
+                    && self.ast_context[c_arg].kind.get_type().map_or(false, |ty| {
+                        self.ast_context.resolve_type(ty).kind == CTypeKind::LongDouble
+                    });
+                if is_long_double {
+                    self.use_crate(ExternCrate::C2RustLongDouble);
+                    let from = mk().path_expr(vec!["c2rust_long_double", "VaLongDouble", "from"]);
+                    mk().call_expr(from, vec![arg])
+                } else {
+                    arg
+                }
+            })
+            .collect()
+    }
+
+    /// The type of the parameter holding the variadic arguments passed as a slice
+    pub(crate) fn var_args_slice_ty(&self) -> P<Ty> {
+        self.use_crate(ExternCrate::C2RustVarargs);
+        mk().ref_ty(mk().slice_ty(mk().path_ty(vec!["c2rust_varargs", "VarArg"])))
+    }
 }
@@ -0,0 +1,66 @@
+//! The templates are x86-64 assembly
+#![cfg(target_arch = "x86_64")]
+
+mod common;
+
+use c2rust_transpile::transpile;
+use common::{config, TestDir};
+
+const ASM: &str = r#"
+int add(int a, int b) {
+    int sum;
+    __asm__("movl %1, %0\n\taddl %2, %0" : "=&r"(sum) : "r"(a), "r"(b));
+    return sum;
+}
+
+void clobber(void) {
+    __asm__ volatile("cpuid" : : : "ebx", "memory");
+}
+"#;
+
+/// Translate `ASM`, returning the translation without whitespace
+fn translate_asm(name: &str, new_asm_syntax: bool) -> String {
+    let dir = TestDir::new(name);
+    dir.write("asm.c", ASM);
+    let cc_db = dir.compile_commands(&["asm.c"]);
+
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.new_asm_syntax = new_asm_syntax;
+    transpile(tcfg, &cc_db, &[]);
+
+    let rs = dir.read("out/src/asm.rs");
+    assert!(rs.contains("#![feature(asm)]"), "{}", rs);
+    rs.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn old_asm_syntax() {
+    let rs = translate_asm("asm-old", false);
+
+    // The template and the constraints are passed to LLVM as they are
+    assert!(
+        rs.contains(r#"asm!("movl$1,$0\n\taddl$2,$0":"=&r"(sum):"r"(a),"r"(b):)"#),
+        "{}",
+        rs
+    );
+    assert!(
+        rs.contains(r#"asm!("cpuid":::"bx","memory":"volatile")"#),
+        "{}",
+        rs
+    );
+}
+
+#[test]
+fn new_asm_syntax() {
+    let rs = translate_asm("asm-new", true);
+
+    assert!(rs.contains(r#"asm!("movl{1},{0}\n\taddl{2},{0}",out(reg)sum,in(reg)a,in(reg)b,options(att_syntax,pure,nomem))"#), "{}", rs);
+    assert!(
+        rs.contains(concat!(
+            r#"asm!("movq%rbx,{c2rust_rbx:r}\n\tcpuid\n\tmovq{c2rust_rbx:r},%rbx","#,
+            r#"c2rust_rbx=out(reg)_,options(att_syntax))"#
+        )),
+        "{}",
+        rs
+    );
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use std::fs;
+
+use serde_json::Value;
+
+use c2rust_transpile::transpile;
+use common::{config, TestDir};
+
+/// The paths of the entries in a cache directory
+fn cache_entries(dir: &TestDir) -> Vec<String> {
+    let mut entries = fs::read_dir(dir.path().join("cache"))
+        .unwrap()
+        .map(|entry| format!("cache/{}", entry.unwrap().file_name().to_string_lossy()))
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn cache_miss_then_hit() {
+    let dir = TestDir::new("cache");
+    dir.write("cached.c", "int answer(void) { return 42; }\n");
+    let cc_db = dir.compile_commands(&["cached.c"]);
+    let tcfg = || {
+        let mut tcfg = config(&dir.path().join("out"));
+        tcfg.cache_dir = Some(dir.path().join("cache"));
+        tcfg
+    };
+
+    // The first run misses and stores the translation
+    transpile(tcfg(), &cc_db, &[]);
+    let translated = dir.read("out/src/cached.rs");
+    assert!(translated.contains("fn answer("));
+    let entries = cache_entries(&dir);
+    assert_eq!(entries.len(), 1);
+
+    // Mark the cached translation, so we can tell if the second run reuses it
+    let mut entry: Value = serde_json::from_str(&dir.read(&entries[0])).unwrap();
+    assert_eq!(entry["translation"], translated.as_str());
+    entry["translation"] = Value::from("// from the cache\n");
+    dir.write(&entries[0], &entry.to_string());
+
+    // The second run hits
+    transpile(tcfg(), &cc_db, &[]);
+    assert_eq!(dir.read("out/src/cached.rs"), "// from the cache\n");
+    assert_eq!(cache_entries(&dir), entries);
+
+    // Changing the source misses again
+    dir.write("cached.c", "int answer(void) { return 43; }\n");
+    transpile(tcfg(), &cc_db, &[]);
+    assert!(dir.read("out/src/cached.rs").contains("fn answer("));
+    assert_eq!(cache_entries(&dir).len(), 2);
+}
+
+#[test]
+fn unusable_cache_dir() {
+    let dir = TestDir::new("cache-unusable");
+    dir.write("uncached.c", "int answer(void) { return 42; }\n");
+    let cc_db = dir.compile_commands(&["uncached.c"]);
+
+    // The cache directory can't be created inside a file, so we translate without it
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.cache_dir = Some(dir.write("not-a-dir", "").join("cache"));
+    transpile(tcfg, &cc_db, &[]);
+
+    assert!(dir.read("out/src/uncached.rs").contains("fn answer("));
+}
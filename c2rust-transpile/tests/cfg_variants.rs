@@ -0,0 +1,103 @@
+mod common;
+
+use serde_json::Value;
+
+use c2rust_transpile::{transpile, CfgVariant};
+use common::{config, TestDir};
+
+#[test]
+fn cfg_variants_merge_translations() {
+    let dir = TestDir::new("cfg-variants");
+    dir.write(
+        "variants.c",
+        r#"
+#ifdef WIDE
+long twice(long x) { return x * 2; }
+#else
+int twice(int x) { return x * 2; }
+#endif
+
+int answer(void) { return 42; }
+"#,
+    );
+    let cc_db = dir.compile_commands(&["variants.c"]);
+
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.report = Some(dir.path().join("report.json"));
+    tcfg.cfg_variants = vec![
+        CfgVariant {
+            feature: "wide".to_string(),
+            clang_args: vec!["-DWIDE".to_string()],
+        },
+        CfgVariant {
+            feature: "narrow".to_string(),
+            clang_args: vec![],
+        },
+    ];
+    transpile(tcfg, &cc_db, &[]);
+
+    // Items which differ between the configurations are kept once for each of them
+    let translated = dir.read("out/src/variants.rs");
+    assert!(translated.contains("#[cfg(feature = \"wide\")]"));
+    assert!(translated.contains("#[cfg(feature = \"narrow\")]"));
+    assert_eq!(translated.matches("fn twice(").count(), 2);
+    assert_eq!(translated.matches("fn answer(").count(), 1);
+
+    // Each declaration is reported once
+    let report: Value = serde_json::from_str(&dir.read("report.json")).unwrap();
+    let decls = report["units"][0]["decls"].as_array().unwrap();
+    for name in &["twice", "answer"] {
+        let reports = decls.iter().filter(|decl| decl["name"] == *name).count();
+        assert_eq!(reports, 1, "{} reported {} times", name, reports);
+    }
+}
+
+#[test]
+fn cfg_variants_merge_anonymous_types() {
+    let dir = TestDir::new("cfg-variants-unnamed");
+    dir.write(
+        "unnamed.c",
+        r#"
+#ifdef EXTRA
+struct { int a; } extra;
+
+int count(int n, ...) { return n; }
+#endif
+
+// The origin
+struct { int x, y; } origin;
+
+int origin_x(void) { return origin.x; }
+"#,
+    );
+    let cc_db = dir.compile_commands(&["unnamed.c"]);
+
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.cfg_variants = vec![
+        CfgVariant {
+            feature: "plain".to_string(),
+            clang_args: vec![],
+        },
+        CfgVariant {
+            feature: "extra".to_string(),
+            clang_args: vec!["-DEXTRA".to_string()],
+        },
+    ];
+    transpile(tcfg, &cc_db, &[]);
+
+    // The anonymous type of `origin` keeps its name although another one comes first under
+    // `extra`, so everything using it merges
+    let translated = dir.read("out/src/unnamed.rs");
+    assert_eq!(translated.matches("pub struct C2RustUnnamed {").count(), 1);
+    assert_eq!(
+        translated.matches("pub struct C2RustUnnamed_0 {").count(),
+        1
+    );
+    assert_eq!(translated.matches("fn origin_x(").count(), 1);
+    assert!(!translated.contains("feature = \"plain\""));
+    assert_eq!(translated.matches("#[cfg(feature = \"extra\")]").count(), 3);
+
+    // Comments are kept once, and the header enables the features of every configuration
+    assert_eq!(translated.matches("// The origin").count(), 1);
+    assert!(translated.contains("c_variadic"));
+}
@@ -0,0 +1,126 @@
+//! Helpers to run the transpiler on C sources written to a scratch directory
+
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use c2rust_transpile::{ReplaceMode, TranspilerConfig};
+use serde_json::json;
+
+/// A scratch directory holding C sources and their compilation database, removed when dropped
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("c2rust-transpile-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TestDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write a file relative to the scratch directory
+    pub fn write(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.path.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Read a file relative to the scratch directory
+    pub fn read(&self, name: &str) -> String {
+        let path = self.path.join(name);
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+
+    /// Write a `compile_commands.json` that compiles each of the given C files
+    pub fn compile_commands(&self, files: &[&str]) -> PathBuf {
+        let cmds = files
+            .iter()
+            .map(|file| {
+                json!({
+                    "directory": self.path,
+                    "command": format!("cc -c {}", file),
+                    "file": file,
+                })
+            })
+            .collect::<Vec<_>>();
+        let path = self.path.join("compile_commands.json");
+        fs::write(&path, serde_json::to_string_pretty(&cmds).unwrap()).unwrap();
+        path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// The configuration `c2rust transpile` uses by default, writing the translated files to
+/// `output_dir/src`
+pub fn config(output_dir: &Path) -> TranspilerConfig {
+    TranspilerConfig {
+        dump_untyped_context: false,
+        dump_typed_context: false,
+        pretty_typed_context: false,
+        dump_function_cfgs: false,
+        json_function_cfgs: false,
+        dump_cfg_liveness: false,
+        dump_structures: false,
+        verbose: false,
+        debug_ast_exporter: false,
+
+        incremental_relooper: true,
+        fail_on_multiple: false,
+        filter: None,
+        debug_relooper_labels: false,
+        cross_checks: false,
+        cross_check_backend: "zstd-logging".to_string(),
+        cross_check_configs: vec![],
+        prefix_function_names: None,
+        translate_asm: true,
+        new_asm_syntax: false,
+        use_c_loop_info: true,
+        use_c_multiple_info: true,
+        simplify_structures: true,
+        panic_on_translator_failure: false,
+        emit_modules: false,
+        fail_on_error: false,
+        replace_unsupported_decls: ReplaceMode::Extern,
+        translate_valist: true,
+        overwrite_existing: true,
+        reduce_type_annotations: false,
+        reorganize_definitions: false,
+        enabled_warnings: HashSet::new(),
+        emit_no_std: false,
+        output_dir: Some(output_dir.to_path_buf()),
+        report: None,
+        translate_const_macros: false,
+        translate_fn_macros: false,
+        translate_complex: false,
+        translate_setjmp: false,
+        translate_restrict: false,
+        stable_variadics: false,
+        scalar_simd_fallback: false,
+        translate_closed_enums: false,
+        dedup_types: false,
+        cfg_variants: vec![],
+        disable_refactoring: true,
+        jobs: 1,
+        cache_dir: None,
+        log_level: log::LevelFilter::Warn,
+
+        emit_build_files: false,
+        binaries: vec![],
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use c2rust_transpile::transpile;
+use common::{config, TestDir};
+
+#[test]
+fn dedup_shares_identical_header_types() {
+    let dir = TestDir::new("dedup");
+    dir.write(
+        "include/point.h",
+        r#"
+typedef int coord;
+
+struct point {
+    coord x;
+    coord y;
+};
+"#,
+    );
+    // Translated differently depending on the includer's definition of CONFIG_VALUE
+    dir.write(
+        "include/config.h",
+        r#"
+struct config {
+    CONFIG_VALUE value;
+};
+"#,
+    );
+    dir.write(
+        "a.c",
+        r#"
+#define CONFIG_VALUE int
+#include "include/point.h"
+#include "include/config.h"
+
+coord sum(struct point p) { return p.x + p.y; }
+
+int get_a(struct config c) { return c.value; }
+"#,
+    );
+    dir.write(
+        "b.c",
+        r#"
+#define CONFIG_VALUE long
+#include "include/point.h"
+#include "include/config.h"
+
+coord diff(struct point p) { return p.x - p.y; }
+
+long get_b(struct config c) { return c.value; }
+"#,
+    );
+    let cc_db = dir.compile_commands(&["a.c", "b.c"]);
+
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.dedup_types = true;
+    tcfg.emit_build_files = true;
+    tcfg.emit_modules = true;
+    transpile(tcfg, &cc_db, &[]);
+
+    // Identical types are defined once, in the shared module, and imported by each unit
+    let types = dir.read("out/src/types.rs");
+    assert_eq!(types.matches("pub struct point").count(), 1);
+    assert_eq!(types.matches("pub type coord").count(), 1);
+    assert!(!types.contains("pub struct config"));
+
+    for unit in &["out/src/a.rs", "out/src/b.rs"] {
+        let translated = dir.read(unit);
+        assert!(!translated.contains("pub struct point"), "{}", unit);
+        assert!(!translated.contains("pub type coord"), "{}", unit);
+        assert!(translated.contains("::types::point;"), "{}", unit);
+        assert!(translated.contains("::types::coord;"), "{}", unit);
+    }
+
+    // Types with the same name that differ between units are kept in each of them
+    let a = dir.read("out/src/a.rs");
+    let b = dir.read("out/src/b.rs");
+    assert!(a.contains("pub struct config"));
+    assert!(a.contains("pub value: libc::c_int"));
+    assert!(b.contains("pub struct config"));
+    assert!(b.contains("pub value: libc::c_long"));
+}
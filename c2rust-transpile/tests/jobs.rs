@@ -0,0 +1,69 @@
+mod common;
+
+use c2rust_transpile::transpile;
+use common::{config, TestDir};
+use serde_json::json;
+
+const UNITS: usize = 8;
+
+/// Write units alternating between two directories, whose compile commands use paths relative
+/// to their directories, and whose headers define different values
+fn write_units(dir: &TestDir) {
+    let mut cmds = vec![];
+    for &(sub_dir, value) in &[("a", 1234), ("b", 5678)] {
+        dir.write(
+            &format!("{}/include/value.h", sub_dir),
+            &format!("#define VALUE {}\n", value),
+        );
+    }
+    for i in 0..UNITS {
+        let sub_dir = if i % 2 == 0 { "a" } else { "b" };
+        let file = format!("unit{}.c", i);
+        dir.write(
+            &format!("{}/{}", sub_dir, file),
+            &format!(
+                "#include <value.h>\n\nint unit{}(void) {{ return VALUE; }}\n",
+                i
+            ),
+        );
+        cmds.push(json!({
+            "directory": dir.path().join(sub_dir),
+            "command": format!("cc -I include -c {}", file),
+            "file": file,
+        }));
+    }
+    dir.write(
+        "compile_commands.json",
+        &serde_json::to_string_pretty(&cmds).unwrap(),
+    );
+}
+
+/// Translate the units with the given number of jobs, returning the translation of each unit
+fn translate(dir: &TestDir, out: &str, jobs: usize) -> Vec<String> {
+    let mut tcfg = config(&dir.path().join(out));
+    tcfg.jobs = jobs;
+    transpile(tcfg, &dir.path().join("compile_commands.json"), &[]);
+
+    (0..UNITS)
+        .map(|i| {
+            let sub_dir = if i % 2 == 0 { "a" } else { "b" };
+            dir.read(&format!("{}/src/{}/unit{}.rs", out, sub_dir, i))
+        })
+        .collect()
+}
+
+#[test]
+fn parallel_translation_matches_sequential() {
+    let dir = TestDir::new("jobs");
+    write_units(&dir);
+
+    let sequential = translate(&dir, "out1", 1);
+    let parallel = translate(&dir, "out4", 4);
+    assert_eq!(sequential, parallel);
+
+    // Each unit found the header of its own directory
+    for (i, rs) in parallel.iter().enumerate() {
+        let value = if i % 2 == 0 { "1234" } else { "5678" };
+        assert!(rs.contains(value), "unit{}: {}", i, rs);
+    }
+}
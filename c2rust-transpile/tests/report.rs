@@ -0,0 +1,69 @@
+mod common;
+
+use serde_json::Value;
+
+use c2rust_transpile::transpile;
+use common::{config, TestDir};
+
+#[test]
+fn report_records_decl_outcomes() {
+    let dir = TestDir::new("report");
+    dir.write(
+        "report.c",
+        r#"
+int translated(void) { return 1; }
+
+int variadic(int n, ...) { return n; }
+
+void *unsupported(void) { return __builtin_frame_address(0); }
+
+static void *unsupported_static(void) { return __builtin_frame_address(0); }
+"#,
+    );
+    let cc_db = dir.compile_commands(&["report.c"]);
+
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.report = Some(dir.path().join("report.json"));
+    transpile(tcfg, &cc_db, &[]);
+
+    let report: Value = serde_json::from_str(&dir.read("report.json")).unwrap();
+    let units = report["units"].as_array().unwrap();
+    assert_eq!(units.len(), 1);
+    assert_eq!(units[0]["outcome"], "translated");
+    assert!(units[0]["file"].as_str().unwrap().ends_with("report.c"));
+
+    let decl = |name: &str| {
+        units[0]["decls"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|decl| decl["name"] == name)
+            .unwrap_or_else(|| panic!("no report for {}", name))
+            .clone()
+    };
+
+    let translated = decl("translated");
+    assert_eq!(translated["outcome"], "translated");
+    assert!(translated["error"].is_null());
+    assert!(translated["span"].as_str().unwrap().contains("report.c"));
+
+    let variadic = decl("variadic");
+    assert_eq!(variadic["outcome"], "translated");
+    assert_eq!(variadic["features"], serde_json::json!(["c_variadic"]));
+
+    // Other translation units can still call the C definition through an `extern` block
+    let unsupported = decl("unsupported");
+    assert_eq!(unsupported["outcome"], "replaced_with_extern");
+    assert_eq!(unsupported["error"]["kind"], "Generic");
+    assert!(unsupported["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("__builtin_frame_address"));
+    let rs = dir.read("out/src/report.rs");
+    assert!(rs.contains("fn unsupported() -> *mut libc::c_void;"), "{}", rs);
+
+    // Static functions can't be linked to
+    let unsupported_static = decl("unsupported_static");
+    assert_eq!(unsupported_static["outcome"], "skipped");
+    assert_eq!(unsupported_static["error"]["kind"], "Generic");
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use c2rust_transpile::transpile;
+use common::{config, TestDir};
+use serde_json::json;
+
+const VECTORS: &str = r#"
+typedef int v4si __attribute__((vector_size(16)));
+typedef float v4sf __attribute__((vector_size(16)));
+
+v4si interleave(v4si a, v4si b) {
+    return __builtin_shufflevector(a, b, 1, 4, 3, 6);
+}
+
+v4sf to_float(v4si a) {
+    return __builtin_convertvector(a, v4sf);
+}
+"#;
+
+#[test]
+fn scalar_fallback_keeps_lanes_off_x86() {
+    let dir = TestDir::new("simd-lanes");
+    dir.write("vectors.c", VECTORS);
+    let cmds = vec![json!({
+        "directory": dir.path(),
+        "command": "cc -target aarch64-linux-gnu -c vectors.c",
+        "file": "vectors.c",
+    })];
+    let cc_db = dir.write(
+        "compile_commands.json",
+        &serde_json::to_string_pretty(&cmds).unwrap(),
+    );
+
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.scalar_simd_fallback = true;
+    transpile(tcfg, &cc_db, &[]);
+
+    let rs = dir.read("out/src/vectors.rs");
+    assert!(!rs.contains("x86"), "{}", rs);
+    let rs: String = rs.chars().filter(|c| !c.is_whitespace()).collect();
+    assert!(rs.contains("pubtypev4si=[libc::c_int;4];"), "{}", rs);
+    assert!(rs.contains("pubtypev4sf=[libc::c_float;4];"), "{}", rs);
+    assert!(
+        rs.contains("::std::mem::transmute::<_,[libc::c_float;4]>("),
+        "{}",
+        rs
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use c2rust_transpile::transpile;
+use common::{config, TestDir};
+
+const SUM: &str = r#"
+#include <stdarg.h>
+
+static int sum(int n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    int total = 0;
+    for (int i = 0; i < n; i++)
+        total += va_arg(ap, int);
+    va_end(ap);
+    return total;
+}
+
+int sum3(int a, int b, int c) {
+    return sum(3, a, b, c);
+}
+"#;
+
+#[test]
+fn stable_variadics_runtime_is_no_std() {
+    let dir = TestDir::new("varargs");
+    dir.write("sum.c", SUM);
+    let cc_db = dir.compile_commands(&["sum.c"]);
+
+    let mut tcfg = config(&dir.path().join("out"));
+    tcfg.stable_variadics = true;
+    tcfg.emit_no_std = true;
+    tcfg.emit_build_files = true;
+    transpile(tcfg, &cc_db, &[]);
+
+    let rs = dir.read("out/src/sum.rs");
+    assert!(rs.contains("c2rust_varargs::VarArg"), "{}", rs);
+    let runtime = dir.read("out/c2rust_varargs.rs");
+    assert!(!runtime.contains("use std::"), "{}", runtime);
+}
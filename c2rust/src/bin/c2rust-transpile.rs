@@ -8,7 +8,7 @@ use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use c2rust_transpile::{Diagnostic, ReplaceMode, TranspilerConfig};
+use c2rust_transpile::{CfgVariant, Diagnostic, ReplaceMode, TranspilerConfig};
 
 fn main() {
     let yaml = load_yaml!("../transpile.yaml");
@@ -80,6 +80,7 @@ fn main() {
         // option. Defaulting to enabled now, can add an option to disable if
         // needed.
         translate_asm: true,
+        new_asm_syntax: matches.is_present("new-asm-syntax"),
 
         // We used to guard varargs with a command-line option before nightly
         // support landed. We may still want to disable this option to target
@@ -87,7 +88,26 @@ fn main() {
         translate_valist: true,
 
         translate_const_macros: matches.is_present("translate-const-macros"),
+        translate_fn_macros: matches.is_present("translate-fn-macros"),
+        translate_complex: matches.is_present("translate-complex"),
+        translate_setjmp: matches.is_present("translate-setjmp"),
+        translate_restrict: matches.is_present("translate-restrict"),
+        stable_variadics: matches.is_present("stable-variadics"),
+        scalar_simd_fallback: matches.is_present("scalar-simd-fallback"),
+        translate_closed_enums: matches.is_present("translate-closed-enums"),
+        dedup_types: matches.is_present("dedup-types"),
+        cfg_variants: matches
+            .values_of("cfg-variant")
+            .map(|values| {
+                values
+                    .map(parse_cfg_variant)
+                    .collect::<Result<Vec<_>, clap::Error>>()
+            })
+            .unwrap_or_else(|| Ok(vec![]))
+            .unwrap_or_else(|e| e.exit()),
         disable_refactoring: matches.is_present("disable-refactoring"),
+        jobs: value_t!(matches, "jobs", usize).unwrap_or_else(|e| e.exit()),
+        cache_dir: matches.value_of("cache-dir").map(PathBuf::from),
 
         use_c_loop_info: !matches.is_present("ignore-c-loop-info"),
         use_c_multiple_info: !matches.is_present("ignore-c-multiple-info"),
@@ -98,6 +118,7 @@ fn main() {
         emit_modules: matches.is_present("emit-modules"),
         emit_build_files: matches.is_present("emit-build-files"),
         output_dir: matches.value_of("output-dir").map(PathBuf::from),
+        report: matches.value_of("report").map(PathBuf::from),
         binaries: matches
             .values_of("binary")
             .map(|values| values.map(String::from).collect())
@@ -114,8 +135,8 @@ fn main() {
         enabled_warnings,
         log_level,
     };
-    // binaries imply emit-build-files
-    if !tcfg.binaries.is_empty() {
+    // binaries and the shared types module imply emit-build-files
+    if !tcfg.binaries.is_empty() || tcfg.dedup_types {
         tcfg.emit_build_files = true
     };
     // emit-build-files implies emit-modules
@@ -125,3 +146,23 @@ fn main() {
 
     c2rust_transpile::transpile(tcfg, &cc_json_path, &extra_args);
 }
+
+/// Parse a `FEATURE=ARGS` preprocessor configuration, e.g., `linux=-DLINUX -D_GNU_SOURCE`
+fn parse_cfg_variant(s: &str) -> Result<CfgVariant, clap::Error> {
+    let mut parts = s.splitn(2, '=');
+    let feature = parts.next().unwrap();
+    let args = parts.next().unwrap_or("");
+    if feature.is_empty() {
+        return Err(clap::Error::with_description(
+            &format!(
+                "Invalid value for '--cfg-variant': expected FEATURE=ARGS, got '{}'",
+                s
+            ),
+            clap::ErrorKind::ValueValidation,
+        ));
+    }
+    Ok(CfgVariant {
+        feature: feature.to_string(),
+        clang_args: args.split_whitespace().map(String::from).collect(),
+    })
+}
@@ -67,18 +67,28 @@ class RustFile:
 
 
 class RustMod:
-    def __init__(self, name: str, visibility: RustVisibility = None) -> None:
+    def __init__(self, name: str, visibility: RustVisibility = None,
+                 path: Optional[str] = None) -> None:
         self.name = name
         self.visibility = visibility or RustVisibility.Private
+        self.path = path
 
     def __str__(self) -> str:
-        return "{}mod {};\n".format(self.visibility.value, self.name)
+        buffer = ""
+
+        if self.path:
+            buffer += "#[path = \"{}\"]\n".format(self.path)
+
+        buffer += "{}mod {};\n".format(self.visibility.value, self.name)
+
+        return buffer
 
     def __hash__(self) -> int:
-        return hash((self.visibility, self.name))
+        return hash((self.visibility, self.name, self.path))
 
     def __eq__(self, other: "RustMod") -> bool:
-        return self.name == other.name and self.visibility == other.visibility
+        return (self.name == other.name and self.visibility == other.visibility and
+                self.path == other.path)
 
 
 class RustUse:
@@ -2,6 +2,7 @@
 
 import errno
 import os
+import platform
 import sys
 import logging
 import argparse
@@ -71,6 +72,13 @@ class CFile:
         self.disable_incremental_relooper = "disable_incremental_relooper" in flags
         self.disallow_current_block = "disallow_current_block" in flags
         self.translate_const_macros = "translate_const_macros" in flags
+        self.translate_fn_macros = "translate_fn_macros" in flags
+        self.translate_complex = "translate_complex" in flags
+        self.translate_setjmp = "translate_setjmp" in flags
+        self.translate_restrict = "translate_restrict" in flags
+        self.stable_variadics = "stable_variadics" in flags
+        self.scalar_simd_fallback = "scalar_simd_fallback" in flags
+        self.translate_closed_enums = "translate_closed_enums" in flags
         self.reorganize_definitions = "reorganize_definitions" in flags
         self.emit_build_files = "emit_build_files" in flags
 
@@ -98,6 +106,20 @@ class CFile:
             args.append("--fail-on-multiple")
         if self.translate_const_macros:
             args.append("--translate-const-macros")
+        if self.translate_fn_macros:
+            args.append("--translate-fn-macros")
+        if self.translate_complex:
+            args.append("--translate-complex")
+        if self.translate_setjmp:
+            args.append("--translate-setjmp")
+        if self.translate_restrict:
+            args.append("--translate-restrict")
+        if self.stable_variadics:
+            args.append("--stable-variadics")
+        if self.scalar_simd_fallback:
+            args.append("--scalar-simd-fallback")
+        if self.translate_closed_enums:
+            args.append("--translate-closed-enums")
         if self.reorganize_definitions:
             args.append("--reorganize-definitions")
         if self.emit_build_files:
@@ -180,6 +202,16 @@ def build_static_library(c_files: Iterable[CFile],
     return CStaticLibrary(output_path + "/libtest.a", "test", obj_files)
 
 
+def target_arch_matches(flags: Set[str]) -> bool:
+    """
+    Check `target_arch_<arch>` flags, which restrict a file to some
+    architectures, against the architecture of this machine.
+    """
+    archs = {flag[12:] for flag in flags if flag.startswith("target_arch_")}
+
+    return not archs or platform.machine() in archs
+
+
 class TestFunction:
     def __init__(self, name: str, flags: Set[str] = set()) -> None:
         self.name = name
@@ -197,6 +229,7 @@ class TestFile(RustFile):
         self.test_functions = test_functions or []
         self.pass_expected = "xfail" not in flags
         self.extern_crates = {flag[13:] for flag in flags if flag.startswith("extern_crate_")}
+        self.runtime_modules = {flag[8:] for flag in flags if flag.startswith("runtime_")}
         self.features = {flag[8:] for flag in flags if flag.startswith("feature_")}
 
 
@@ -234,7 +267,8 @@ class TestDirectory:
                       files.search(filename)):
                     rs_test_file = self._read_rust_test_file(path)
 
-                    self.rs_test_files.append(rs_test_file)
+                    if rs_test_file:
+                        self.rs_test_files.append(rs_test_file)
 
     def _read_c_file(self, path: str) -> Optional[CFile]:
         file_config = None
@@ -250,9 +284,12 @@ class TestDirectory:
         if "skip_translation" in file_flags:
             return
 
+        if not target_arch_matches(file_flags):
+            return
+
         return CFile(self.logLevel, path, file_flags)
 
-    def _read_rust_test_file(self, path: str) -> TestFile:
+    def _read_rust_test_file(self, path: str) -> Optional[TestFile]:
         with open(path, 'r', encoding="utf-8") as file:
             file_buffer = file.read()
 
@@ -263,6 +300,9 @@ class TestDirectory:
             flags_str = file_config.group(0)[3:]
             file_flags = {flag.strip() for flag in flags_str.split(',')}
 
+        if not target_arch_matches(file_flags):
+            return
+
         found_tests = re.findall(
             r"(//(.*))?\n\s*pub fn (test_\w+)\(\)", file_buffer)
         test_fns = []
@@ -408,6 +448,12 @@ class TestDirectory:
             rust_file_builder.add_features(test_file.features)
             rust_file_builder.add_extern_crates(test_file.extern_crates)
 
+            # Runtime support modules that the transpiler emits into translated crates
+            for runtime_module in test_file.runtime_modules:
+                path = os.path.join(c.TRANSPILE_CRATE_DIR, "src", "build_files",
+                                    runtime_module + ".rs")
+                rust_file_builder.add_mod(RustMod(runtime_module, RustVisibility.Public, path))
+
             _, file_name = os.path.split(test_file.path)
             extensionless_file_name, _ = os.path.splitext(file_name)
 
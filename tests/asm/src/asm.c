@@ -0,0 +1,33 @@
+//! target_arch_x86_64
+
+void registers(unsigned buffer_size, int buffer[]) {
+    int a = 40, b = 2, sum, diff = 10, r;
+
+    // An early clobbered output, and inputs in any register
+    __asm__("movl %1, %0\n\taddl %2, %0" : "=&r"(sum) : "r"(a), "r"(b));
+    // A read-write output
+    __asm__("subl %1, %0" : "+r"(diff) : "r"(b));
+    // A specific register, which the template refers to directly
+    __asm__("movl $7, %%eax" : "=a"(r));
+
+    if (buffer_size < 3)
+        return;
+    buffer[0] = sum;
+    buffer[1] = diff;
+    buffer[2] = r;
+}
+
+void tied_and_memory(unsigned buffer_size, long buffer[]) {
+    int narrow = 41, mem = 5;
+    long wide, loaded;
+
+    // An input tied to a wider output
+    __asm__("addq $1, %0" : "=r"(wide) : "0"(narrow));
+    // A memory input, and a clobbered register
+    __asm__("movl %1, %%ecx\n\tmovslq %%ecx, %0" : "=r"(loaded) : "m"(mem) : "ecx");
+
+    if (buffer_size < 2)
+        return;
+    buffer[0] = wide;
+    buffer[1] = loaded;
+}
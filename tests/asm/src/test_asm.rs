@@ -0,0 +1,45 @@
+//! target_arch_x86_64, extern_crate_c2rust_asm_casts
+
+extern crate libc;
+
+use asm::{rust_registers, rust_tied_and_memory};
+use self::libc::{c_int, c_long, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn registers(_: c_uint, _: *mut c_int);
+    #[no_mangle]
+    fn tied_and_memory(_: c_uint, _: *mut c_long);
+}
+
+const REGISTERS_BUFFER_SIZE: usize = 3;
+const TIED_BUFFER_SIZE: usize = 2;
+
+pub fn test_registers() {
+    let mut buffer = [0; REGISTERS_BUFFER_SIZE];
+    let mut rust_buffer = [0; REGISTERS_BUFFER_SIZE];
+    let expected_buffer = [42, 8, 7];
+
+    unsafe {
+        registers(REGISTERS_BUFFER_SIZE as u32, buffer.as_mut_ptr());
+        rust_registers(REGISTERS_BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
+
+pub fn test_tied_and_memory() {
+    let mut buffer = [0; TIED_BUFFER_SIZE];
+    let mut rust_buffer = [0; TIED_BUFFER_SIZE];
+    let expected_buffer = [42, 5];
+
+    unsafe {
+        tied_and_memory(TIED_BUFFER_SIZE as u32, buffer.as_mut_ptr());
+        rust_tied_and_memory(TIED_BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
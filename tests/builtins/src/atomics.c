@@ -66,3 +66,24 @@ void new_atomics(const unsigned buffer_size, int buffer[const])
     __atomic_store_n(&x, 0, __ATOMIC_RELAXED);
     buffer[i++] = x;
 }
+
+void dynamic_atomics(const unsigned buffer_size, int buffer[const])
+{
+    int i = 0, x = 34, expected = 34;
+    int orders[] = { __ATOMIC_RELAXED, __ATOMIC_ACQUIRE, __ATOMIC_SEQ_CST };
+    int weak = 0;
+
+    for (int o = 0; o < 3; o++) {
+        int order = orders[o];
+        buffer[i++] = __atomic_load_n(&x, order);
+        __atomic_store_n(&x, x + 3, order == __ATOMIC_ACQUIRE ? __ATOMIC_RELEASE : order);
+        buffer[i++] = x;
+        buffer[i++] = __atomic_exchange_n(&x, 50 + o, order);
+        buffer[i++] = __atomic_fetch_add(&x, 7, order);
+        buffer[i++] = __atomic_sub_fetch(&x, 2, order);
+        expected = x;
+        buffer[i++] = __atomic_compare_exchange_n(&x, &expected, 99, weak, order, __ATOMIC_RELAXED);
+        buffer[i++] = x;
+        buffer[i++] = expected;
+    }
+}
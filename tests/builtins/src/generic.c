@@ -0,0 +1,49 @@
+#include <math.h>
+
+// Type-generic macros in the style of `tgmath.h`
+#define type_name(x) _Generic((x), \
+    float: 1,                      \
+    double: 2,                     \
+    long double: 3,                \
+    const char *: 4,               \
+    default: 0)
+
+#define generic_fabs(x) _Generic((x), float: fabsf, default: fabs)(x)
+
+#define is_float(x) __builtin_types_compatible_p(__typeof__(x), float)
+
+#define generic_sqrt(x) \
+    __builtin_choose_expr(is_float(x), sqrtf(x), sqrt(x))
+
+void generic_selection(unsigned buffer_size, int buffer[]) {
+    float f = -2.25f;
+    double d = 6.25;
+    long double ld = 1.0L;
+    int i = 7;
+    const char *s = "str";
+    int arr[2] = {0, 1};
+
+    int results[] = {
+        type_name(f),
+        type_name(d),
+        type_name(ld),
+        type_name(i),
+        type_name(s),
+        type_name(f + i),
+        type_name(i++),
+        i,
+        (int)(generic_fabs(f) * 4),
+        (int)generic_fabs(-d),
+        (int)(generic_sqrt(d) * 2),
+        (int)(generic_sqrt(-f) * 2),
+        is_float(f),
+        is_float(d),
+        __builtin_types_compatible_p(int[2], __typeof__(arr)),
+        __builtin_types_compatible_p(const int, int),
+        __builtin_types_compatible_p(int *, long *),
+    };
+
+    for (unsigned n = 0; n < buffer_size && n < sizeof(results) / sizeof(results[0]); n++) {
+        buffer[n] = results[n];
+    }
+}
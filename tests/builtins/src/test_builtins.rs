@@ -1,9 +1,10 @@
 //! feature_core_intrinsics, extern_crate_core
 extern crate libc;
 
-use atomics::{rust_atomics_entry, rust_new_atomics};
+use atomics::{rust_atomics_entry, rust_dynamic_atomics, rust_new_atomics};
 use mem_x_fns::{rust_mem_x, rust_assume_aligned};
 use math::{rust_ffs, rust_ffsl, rust_ffsll, rust_isfinite, rust_isnan, rust_isinf_sign};
+use generic::rust_generic_selection;
 use self::libc::{c_int, c_uint, c_char, c_long, c_longlong, c_double};
 
 #[link(name = "test")]
@@ -13,6 +14,8 @@ extern "C" {
     #[no_mangle]
     fn new_atomics(_: c_uint, _: *mut c_int);
     #[no_mangle]
+    fn dynamic_atomics(_: c_uint, _: *mut c_int);
+    #[no_mangle]
     fn mem_x(_: *const c_char, _: *mut c_char);
     #[no_mangle]
     fn ffs(_: c_int) -> c_int;
@@ -26,6 +29,8 @@ extern "C" {
     fn isnan(_: c_double) -> c_int;
     #[no_mangle]
     fn isinf_sign(_: c_double) -> c_int;
+    #[no_mangle]
+    fn generic_selection(_: c_uint, _: *mut c_int);
 }
 
 const BUFFER_SIZE: usize = 1024;
@@ -60,6 +65,20 @@ pub fn test_new_atomics() {
     }
 }
 
+pub fn test_dynamic_atomics() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+
+    unsafe {
+       dynamic_atomics(BUFFER_SIZE as u32, buffer.as_mut_ptr());
+       rust_dynamic_atomics(BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    for index in 0..BUFFER_SIZE {
+        assert_eq!(buffer[index], rust_buffer[index]);
+    }
+}
+
 pub fn test_mem_fns() {
     let const_string = "I am ten!\0";
     let mut buffer = [0; BUFFER_SIZE2];
@@ -162,3 +181,17 @@ pub fn test_assume_aligned() {
         assert_eq!(rust_assume_aligned(null), null);
     }
 }
+
+pub fn test_generic_selection() {
+    let mut buffer = [0; BUFFER_SIZE2 * 2];
+    let mut rust_buffer = [0; BUFFER_SIZE2 * 2];
+    let expected_buffer = [1, 2, 3, 0, 4, 1, 0, 7, 9, 6, 5, 3, 1, 0, 1, 1, 0];
+
+    unsafe {
+        generic_selection(buffer.len() as u32, buffer.as_mut_ptr());
+        rust_generic_selection(rust_buffer.len() as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(&buffer[..expected_buffer.len()], &expected_buffer[..]);
+}
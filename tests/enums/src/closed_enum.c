@@ -0,0 +1,85 @@
+//! translate_closed_enums
+
+#include <string.h>
+
+enum state { IDLE, RUNNING, DONE };
+
+// Values computed with arithmetic keep this enum an integer type
+enum flags { FLAG_NONE, FLAG_A, FLAG_B };
+
+struct machine {
+    enum state state;
+    int steps;
+};
+
+// Enums shared with code outside the translation unit stay integer types, since that code
+// may store any value in them
+enum mode { MODE_OFF, MODE_ON };
+
+struct settings {
+    enum mode mode;
+};
+
+int mode_value(enum mode m) {
+    return m;
+}
+
+int settings_mode(const struct settings *s) {
+    return s->mode;
+}
+
+// Filling a struct with bytes may store any value in its enum fields
+enum color { RED, GREEN, BLUE };
+
+struct pixel {
+    enum color color;
+    int alpha;
+};
+
+static int filled_color(void) {
+    struct pixel px;
+    memset(&px, 0xff, sizeof px);
+    return px.color;
+}
+
+static enum state next_state(enum state s) {
+    switch (s) {
+    case IDLE:
+        return RUNNING;
+    case RUNNING:
+    case DONE:
+        return DONE;
+    }
+    return IDLE;
+}
+
+void closed_enum_test(const unsigned buffer_size, int buffer[]) {
+    if (buffer_size < 7) {
+        return;
+    }
+
+    struct machine m = { IDLE, 0 };
+    static enum state zeroed;
+
+    while (m.state != DONE) {
+        buffer[m.steps] = m.state;
+        m.state = next_state(m.state);
+        m.steps++;
+    }
+    buffer[2] = m.steps;
+    buffer[3] = zeroed == IDLE;
+
+    enum flags f = FLAG_A;
+    f = f | FLAG_B;
+    buffer[4] = f;
+
+    switch (m.state) {
+    case IDLE:
+        buffer[5] = 1;
+        break;
+    default:
+        buffer[5] = 2;
+    }
+
+    buffer[6] = filled_color();
+}
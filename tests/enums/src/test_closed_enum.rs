@@ -0,0 +1,42 @@
+extern crate libc;
+
+use closed_enum::{rust_closed_enum_test, rust_mode_value, rust_settings_mode, settings, state};
+use self::libc::{c_int, c_uint};
+use std::convert::TryFrom;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn closed_enum_test(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 7;
+
+pub fn test_variants() {
+    assert_eq!(state::RUNNING as u32, 1);
+    assert_eq!(state::try_from(2), Ok(state::DONE));
+    assert_eq!(state::try_from(3), Err(3));
+}
+
+pub fn test_closed_enum() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [0, 1, 2, 1, 3, 2, -1];
+
+    unsafe {
+        closed_enum_test(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_closed_enum_test(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
+
+pub fn test_shared_enums() {
+    let shared = settings { mode: 7 };
+
+    unsafe {
+        assert_eq!(rust_mode_value(5), 5);
+        assert_eq!(rust_settings_mode(&shared), 7);
+    }
+}
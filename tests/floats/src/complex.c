@@ -0,0 +1,57 @@
+//! translate_complex
+
+#include <complex.h>
+
+static double complex global_z = 2.0i;
+
+static double complex scale(double complex z, double k) {
+    return z * k;
+}
+
+void complex_arith(unsigned buffer_size, double buffer[]) {
+    double complex a = 3.0 + 4.0i;
+    double complex b = global_z + 1.0;
+    double complex c;
+
+    if (buffer_size < 12) return;
+
+    c = a + b;
+    buffer[0] = creal(c);
+    buffer[1] = cimag(c);
+
+    c = a * b - 1.0;
+    buffer[2] = __real__ c;
+    buffer[3] = __imag__ c;
+
+    c = a / 2.0i;
+    buffer[4] = creal(c);
+    buffer[5] = cimag(c);
+
+    c = scale(~a, 2.0);
+    buffer[6] = creal(c);
+    buffer[7] = cimag(c);
+
+    __real__ c = 5.0;
+    __imag__ c += 1.0;
+    buffer[8] = creal(c);
+    buffer[9] = cimag(c);
+
+    buffer[10] = cabs(a);
+    buffer[11] = (a == 3.0 + 4.0i) + (b != 1.0) + !c;
+}
+
+void complex_casts(unsigned buffer_size, double buffer[]) {
+    float complex f = 1.5f - 2.5fi;
+    double complex d = f;
+    double complex e = 7.0;
+    double r = d;
+
+    if (buffer_size < 6) return;
+
+    buffer[0] = creal(d);
+    buffer[1] = cimag(d);
+    buffer[2] = creal(e);
+    buffer[3] = cimag(e);
+    buffer[4] = r;
+    buffer[5] = crealf(conjf(f)) + cimagf(conjf(f));
+}
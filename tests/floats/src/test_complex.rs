@@ -0,0 +1,45 @@
+//! extern_crate_num_complex
+
+extern crate libc;
+
+use complex::{rust_complex_arith, rust_complex_casts};
+use self::libc::{c_double, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn complex_arith(_: c_uint, _: *mut c_double);
+    #[no_mangle]
+    fn complex_casts(_: c_uint, _: *mut c_double);
+}
+
+const ARITH_BUFFER_SIZE: usize = 12;
+const CASTS_BUFFER_SIZE: usize = 6;
+
+pub fn test_complex_arith() {
+    let mut buffer = [0.; ARITH_BUFFER_SIZE];
+    let mut rust_buffer = [0.; ARITH_BUFFER_SIZE];
+    let expected_buffer = [4., 6., -6., 10., 2., -1.5, 6., -8., 5., -7., 5., 2.];
+
+    unsafe {
+        complex_arith(ARITH_BUFFER_SIZE as u32, buffer.as_mut_ptr());
+        rust_complex_arith(ARITH_BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
+
+pub fn test_complex_casts() {
+    let mut buffer = [0.; CASTS_BUFFER_SIZE];
+    let mut rust_buffer = [0.; CASTS_BUFFER_SIZE];
+    let expected_buffer = [1.5, -2.5, 7., 0., 1.5, 4.];
+
+    unsafe {
+        complex_casts(CASTS_BUFFER_SIZE as u32, buffer.as_mut_ptr());
+        rust_complex_casts(CASTS_BUFFER_SIZE as u32, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
@@ -0,0 +1,47 @@
+//! allow_current_block
+
+enum { OP_HALT, OP_PUSH, OP_ADD, OP_MUL, OP_DUP };
+
+// A small stack machine using threaded dispatch
+int run_program(const int *code) {
+    static void *dispatch[] = {&&halt, &&push, &&add, &&mul, &&dup};
+    int stack[16];
+    int sp = 0;
+
+#define NEXT goto *dispatch[*code++]
+
+    NEXT;
+
+push:
+    stack[sp++] = *code++;
+    NEXT;
+
+add:
+    sp--;
+    stack[sp - 1] += stack[sp];
+    NEXT;
+
+mul:
+    sp--;
+    stack[sp - 1] *= stack[sp];
+    NEXT;
+
+dup:
+    stack[sp] = stack[sp - 1];
+    sp++;
+    NEXT;
+
+halt:
+    return sp > 0 ? stack[sp - 1] : -1;
+}
+
+int label_in_variable(int x) {
+    void *target = x > 0 ? &&positive : &&not_positive;
+    goto *target;
+
+positive:
+    return 1;
+
+not_positive:
+    return 0;
+}
@@ -18,3 +18,44 @@ lbl: ;
 
   return x + y;
 }
+
+#define TRY(e) ({ int _r = (e); if (_r < 0) goto out; _r; })
+
+static int check(int v, int n) {
+  return n < 20 && v % 7 == 0 ? -1 : v;
+}
+
+int stmt_expr_jumps(int n) {
+  int sum = 0;
+  int i;
+
+  for (i = 0; i < n; i++) {
+    int v = ({ if (i % 3 == 0) continue; if (i > 10) break; i * 2; });
+
+    if (TRY(check(v, n)) > 10)
+      sum += v;
+    else
+      sum -= 1;
+  }
+
+  return sum;
+
+out:
+  return -sum;
+}
+
+int stmt_expr_heads(int n) {
+  int a = n, b = TRY(check(a, n)) + a;
+  int i = 2;
+
+  while (TRY(check(i, n)) < b)
+    i += 3;
+
+  for (; i > 0; i -= TRY(check(i + 2, n)) - i)
+    b++;
+
+  return b;
+
+out:
+  return -a;
+}
@@ -0,0 +1,24 @@
+// Fallthrough into a case that only ends in a `break` should still translate to a single
+// `match`.
+int switch_fallthrough(int x) {
+    int y = 0;
+
+    switch (x) {
+        case 0:
+            y += 10;
+        case 1:
+        case 2:
+            y += 1;
+            break;
+        case 3:
+            if (y == 0)
+                y += 100;
+        case 4:
+            y += 4;
+            break;
+        default:
+            y = -1;
+    }
+
+    return y;
+}
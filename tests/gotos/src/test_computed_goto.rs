@@ -0,0 +1,30 @@
+extern crate libc;
+
+use computed_goto::{rust_label_in_variable, rust_run_program};
+use self::libc::c_int;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn run_program(_: *const c_int) -> c_int;
+    #[no_mangle]
+    fn label_in_variable(_: c_int) -> c_int;
+}
+
+pub fn test_threaded_dispatch() {
+    // (2 + 3) * (2 + 3)
+    let program = [1, 2, 1, 3, 2, 4, 3, 0];
+
+    unsafe {
+        assert_eq!(run_program(program.as_ptr()), 25);
+        assert_eq!(rust_run_program(program.as_ptr()), 25);
+    }
+}
+
+pub fn test_label_in_variable() {
+    for &x in &[-4, 0, 7] {
+        unsafe {
+            assert_eq!(label_in_variable(x), rust_label_in_variable(x));
+        }
+    }
+}
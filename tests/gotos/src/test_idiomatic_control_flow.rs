@@ -2,6 +2,7 @@ extern crate libc;
 
 use idiomatic_switch::rust_idiomatic_switch;
 use idiomatic_nested_loops::rust_break_multiple;
+use switch_fallthrough::rust_switch_fallthrough;
 
 pub fn test_idiomatic_switch() {
     unsafe {
@@ -12,6 +13,17 @@ pub fn test_idiomatic_switch() {
     }
 }
 
+pub fn test_switch_fallthrough() {
+    unsafe {
+        assert_eq!(rust_switch_fallthrough(0), 11);
+        assert_eq!(rust_switch_fallthrough(1), 1);
+        assert_eq!(rust_switch_fallthrough(2), 1);
+        assert_eq!(rust_switch_fallthrough(3), 104);
+        assert_eq!(rust_switch_fallthrough(4), 4);
+        assert_eq!(rust_switch_fallthrough(5), -1);
+    }
+}
+
 pub fn test_break_multiple_loops() {
     unsafe {
         assert_eq!(rust_break_multiple(0), 4);
@@ -1,6 +1,6 @@
 extern crate libc;
 
-use stmt_expr::rust_stmt_expr_func;
+use stmt_expr::{rust_stmt_expr_func, rust_stmt_expr_heads, rust_stmt_expr_jumps};
 
 use self::libc::c_int;
 
@@ -12,3 +12,22 @@ pub fn test_stmt_expr_relooper() {
         assert_eq!(rust_stmt_expr_func(3), 29);
     }
 }
+
+pub fn test_stmt_expr_jumps() {
+    unsafe {
+        assert_eq!(rust_stmt_expr_jumps(0), 0);
+        assert_eq!(rust_stmt_expr_jumps(6), -4);
+        assert_eq!(rust_stmt_expr_jumps(15), 4);
+        assert_eq!(rust_stmt_expr_jumps(30), 46);
+    }
+}
+
+pub fn test_stmt_expr_heads() {
+    unsafe {
+        assert_eq!(rust_stmt_expr_heads(1), 3);
+        assert_eq!(rust_stmt_expr_heads(2), -2);
+        assert_eq!(rust_stmt_expr_heads(7), -7);
+        assert_eq!(rust_stmt_expr_heads(12), -12);
+        assert_eq!(rust_stmt_expr_heads(30), 91);
+    }
+}
@@ -0,0 +1,69 @@
+//! stable_variadics
+
+#include <stdarg.h>
+
+static int sum(int count, ...) {
+    va_list ap;
+    int total = 0;
+
+    va_start(ap, count);
+    for (int i = 0; i < count; i++)
+        total += va_arg(ap, int);
+    va_end(ap);
+
+    return total;
+}
+
+// Reads each argument twice, through a copy of the va_list
+static long sum_twice(int count, ...) {
+    va_list ap, aq;
+    long total = 0;
+
+    va_start(ap, count);
+    va_copy(aq, ap);
+    for (int i = 0; i < count; i++)
+        total += va_arg(ap, long);
+    for (int i = 0; i < count; i++)
+        total += va_arg(aq, long);
+    va_end(aq);
+    va_end(ap);
+
+    return total;
+}
+
+static void store(int *out, const char *fmt, ...) {
+    va_list ap;
+
+    va_start(ap, fmt);
+    for (; *fmt; fmt++, out++) {
+        switch (*fmt) {
+        case 'i':
+            *out = va_arg(ap, int);
+            break;
+        case 'u':
+            *out = (int)va_arg(ap, unsigned);
+            break;
+        case 'd':
+            *out = (int)(va_arg(ap, double) * 10);
+            break;
+        case 'p':
+            *out = *va_arg(ap, int *);
+            break;
+        }
+    }
+    va_end(ap);
+}
+
+void slice_varargs(unsigned buffer_size, int buffer[]) {
+    int seven = 7;
+    char c = 3;
+    float f = 0.5f;
+
+    if (buffer_size < 7)
+        return;
+
+    buffer[0] = sum(0);
+    buffer[1] = sum(3, 1, 2, c);
+    buffer[2] = (int)sum_twice(2, 10L, 20L);
+    store(&buffer[3], "iudp", -4, 5u, f, &seven);
+}
@@ -0,0 +1,28 @@
+//! runtime_c2rust_varargs
+
+extern crate libc;
+
+use slice_varargs::rust_slice_varargs;
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn slice_varargs(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 7;
+
+pub fn test_slice_varargs() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [0, 6, 60, -4, 5, 5, 7];
+
+    unsafe {
+        slice_varargs(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_slice_varargs(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
@@ -0,0 +1,30 @@
+#include <stdarg.h>
+#include <stdio.h>
+#include <string.h>
+
+static long double sum(int count, ...) {
+    va_list ap;
+    long double total = 0;
+
+    va_start(ap, count);
+    for (int i = 0; i < count; i++) {
+        total += va_arg(ap, long double);
+        total += va_arg(ap, int);
+    }
+    va_end(ap);
+
+    return total;
+}
+
+void long_double_varargs(unsigned buffer_size, int buffer[]) {
+    char formatted[32];
+    long double x = 2.5L;
+
+    if (buffer_size < 3)
+        return;
+
+    buffer[0] = (int) (sum(2, 1.5L, 2, 0.25L, 4) * 2);
+    snprintf(formatted, sizeof(formatted), "%d %.3Lf %d", 1, x, 3);
+    buffer[1] = strcmp(formatted, "1 2.500 3") == 0;
+    buffer[2] = (int) sum(1, -2.75L, -4);
+}
@@ -0,0 +1,28 @@
+//! extern_crate_c2rust_long_double
+
+extern crate libc;
+
+use long_double_varargs::rust_long_double_varargs;
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn long_double_varargs(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 3;
+
+pub fn test_long_double_varargs() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [15, 1, -6];
+
+    unsafe {
+        long_double_varargs(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_long_double_varargs(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
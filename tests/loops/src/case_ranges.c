@@ -0,0 +1,29 @@
+// GNU case ranges, as used in lexers
+static int classify(int c) {
+    switch (c) {
+        case '0' ... '9':
+            return 1;
+        case 'a' ... 'z':
+        case 'A' ... 'Z':
+        case '_':
+            return 2;
+        case -10 ... -1:
+            return 3;
+        case 200 ... 255:
+            return 4;
+        case 300:
+            c = 1;
+        // Empty, so only reachable by falling through
+        case 310 ... 305:
+            return 4 + c;
+        default:
+            return 0;
+    }
+}
+
+void case_ranges(unsigned buffer_size, int buffer[]) {
+    const int inputs[] = {'0', '5', '9', 'a', 'q', 'Z', '_', '-', -10, -1, 0, 200, 255, 256, 300, 307};
+
+    for (unsigned i = 0; i < buffer_size && i < sizeof(inputs) / sizeof(inputs[0]); i++)
+        buffer[i] = classify(inputs[i]);
+}
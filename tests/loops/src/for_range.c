@@ -0,0 +1,41 @@
+// Loops counting up to a bound that the body doesn't modify translate to `for` loops over ranges
+void for_range(unsigned buffer_size, int buffer[]) {
+    int i = 0;
+
+    for (unsigned j = 0; j < 4; j++)
+        buffer[i++] = j;
+
+    for (int j = -2; j <= 2; ++j) {
+        if (j == 0)
+            continue;
+        buffer[i++] = j;
+    }
+
+    int n = 5;
+    for (int j = 0; j < n; j += 1) {
+        switch (j) {
+            case 1:
+                continue;
+            case 3:
+                buffer[i++] = 30;
+                break;
+            default:
+                buffer[i++] = j;
+        }
+    }
+
+    for (long j = 0; j < 3; j++) {
+        for (long k = j; k < 10; k++) {
+            if (k == 2)
+                break;
+            buffer[i++] = (int)(10 * j + k);
+        }
+    }
+
+    // The body modifies the induction variable and the bound
+    for (int j = 0; j < n; j++) {
+        buffer[i++] = j;
+        j++;
+        n--;
+    }
+}
@@ -0,0 +1,26 @@
+extern crate libc;
+
+use for_range::rust_for_range;
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn for_range(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 18;
+
+pub fn test_for_range() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [0, 1, 2, 3, -2, -1, 1, 2, 0, 2, 30, 4, 0, 1, 11, 0, 2, 0];
+
+    unsafe {
+        for_range(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_for_range(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
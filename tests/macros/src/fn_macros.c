@@ -0,0 +1,32 @@
+//! translate_fn_macros
+
+#define SQUARE(x) ((x) * (x))
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+#define CLEAR(p) (*(p) = 0)
+
+static int counter = 0;
+#define BUMP() (counter += 1)
+
+int square_sum(int a, int b) {
+  return SQUARE(a) + SQUARE(b) + SQUARE(a + b);
+}
+
+int max_of_three(int a, int b, int c) {
+  return MAX(MAX(a, b), c);
+}
+
+static int next_value(int *p) {
+  return (*p)++;
+}
+
+// Arguments with side effects must stay expanded since the macro evaluates
+// them more than once
+int square_next(int *p) {
+  return SQUARE(next_value(p));
+}
+
+int clear_and_bump(int *p) {
+  CLEAR(p);
+  BUMP();
+  return BUMP() + *p;
+}
@@ -0,0 +1,35 @@
+extern crate libc;
+
+use fn_macros::{rust_clear_and_bump, rust_max_of_three, rust_square_next, rust_square_sum};
+use self::libc::c_int;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn square_sum(a: c_int, b: c_int) -> c_int;
+    #[no_mangle]
+    fn max_of_three(a: c_int, b: c_int, c: c_int) -> c_int;
+    #[no_mangle]
+    fn square_next(p: *mut c_int) -> c_int;
+}
+
+pub fn test_fn_macros() {
+    for &(a, b, c) in &[(1, 2, 3), (-4, 7, 0), (9, 9, -9)] {
+        unsafe {
+            assert_eq!(square_sum(a, b), rust_square_sum(a, b));
+            assert_eq!(max_of_three(a, b, c), rust_max_of_three(a, b, c));
+        }
+    }
+
+    let mut c_val: c_int = 3;
+    let mut rust_val: c_int = 3;
+    unsafe {
+        assert_eq!(square_next(&mut c_val), rust_square_next(&mut rust_val));
+    }
+    assert_eq!(c_val, rust_val);
+
+    let mut x: c_int = 42;
+    let ret = unsafe { rust_clear_and_bump(&mut x) };
+    assert_eq!(x, 0);
+    assert_eq!(ret, 2);
+}
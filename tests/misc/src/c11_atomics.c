@@ -0,0 +1,49 @@
+#include <stddef.h>
+
+struct counter {
+    _Atomic int hits;
+    int misses;
+};
+
+static _Atomic unsigned long total = 7;
+
+void c11_atomics(const unsigned buffer_size, int buffer[const])
+{
+    int i = 0;
+    _Atomic int x = 34;
+    _Atomic char c = 'a';
+    _Atomic(int*) p = NULL;
+    struct counter ctr = { 0, 0 };
+    int arr[4] = { 10, 20, 30, 40 };
+
+    buffer[i++] = x;
+    x = 55;                 buffer[i++] = x;
+    x += 12;                buffer[i++] = x;
+    x -= 100;               buffer[i++] = x;
+    x |= 0x100;             buffer[i++] = x;
+    x &= 0x1F0;             buffer[i++] = x;
+    x ^= 0xA5;              buffer[i++] = x;
+    x *= 3;                 buffer[i++] = x;
+    x <<= 2;                buffer[i++] = x;
+    buffer[i++] = x++;      buffer[i++] = x;
+    buffer[i++] = ++x;      buffer[i++] = x;
+    buffer[i++] = x--;      buffer[i++] = x;
+    buffer[i++] = (x = 9) + 1;
+    buffer[i++] = (x += 4) * 2;
+
+    c += 300;               buffer[i++] = c;
+    c++;                    buffer[i++] = c;
+
+    p = &arr[1];            buffer[i++] = *p;
+    p += 2;                 buffer[i++] = *p;
+    p--;                    buffer[i++] = *p;
+
+    ctr.hits += 5;
+    ctr.hits++;
+    ctr.misses = ctr.hits * 2;
+    buffer[i++] = ctr.hits;
+    buffer[i++] = ctr.misses;
+
+    total += x;
+    buffer[i++] = (int)total;
+}
@@ -0,0 +1,63 @@
+//! translate_setjmp
+
+#include <setjmp.h>
+
+static jmp_buf env;
+
+static void maybe_jump(int val) {
+    if (val > 2)
+        longjmp(env, val);
+}
+
+static _Noreturn void fail(int val) {
+    longjmp(env, val);
+}
+
+static int checked_div(int a, int b) {
+    if (b == 0)
+        fail(7);
+    return a / b;
+}
+
+static int jump_with_zero(void) {
+    jmp_buf local;
+
+    if (!setjmp(local))
+        longjmp(local, 0);
+    else
+        return 1;
+
+    return 0;
+}
+
+void setjmp_test(unsigned buffer_size, int buffer[]) {
+    int i;
+
+    if (buffer_size < 8)
+        return;
+
+    for (i = 0; i < 5; i++) {
+        if (setjmp(env) == 0) {
+            int j;
+            // Loops inside the direct branch may break out of themselves
+            for (j = 0; j < 10; j++) {
+                if (j == i)
+                    break;
+            }
+            maybe_jump(j);
+            buffer[i] = j;
+        } else {
+            buffer[i] = -i;
+        }
+    }
+
+    buffer[5] = jump_with_zero();
+
+    // Jumps out of noreturn functions and past the frames of their callers
+    if (setjmp(env) == 0) {
+        buffer[6] = checked_div(6, 3);
+        buffer[7] = checked_div(1, 0);
+    } else {
+        buffer[7] = -7;
+    }
+}
@@ -0,0 +1,26 @@
+extern crate libc;
+
+use c11_atomics::rust_c11_atomics;
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn c11_atomics(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 30;
+
+pub fn test_c11_atomics() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+
+    unsafe {
+        c11_atomics(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_c11_atomics(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    for x in 0..BUFFER_SIZE {
+        assert_eq!(buffer[x], rust_buffer[x], "index {}", x);
+    }
+}
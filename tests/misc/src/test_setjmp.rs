@@ -0,0 +1,28 @@
+//! runtime_c2rust_setjmp
+
+extern crate libc;
+
+use setjmp::rust_setjmp_test;
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn setjmp_test(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 8;
+
+pub fn test_setjmp() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [0, 1, 2, -3, -4, 1, 2, -7];
+
+    unsafe {
+        setjmp_test(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_setjmp_test(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
@@ -0,0 +1,71 @@
+//! translate_restrict
+
+struct point {
+    int x;
+    int y;
+};
+
+// These parameters are only dereferenced, so they become references
+static void scale(int *restrict out, const int *restrict in, int factor) {
+    *out = *in * factor;
+}
+
+static void swap_point(struct point *restrict p) {
+    int tmp = p->x;
+    p->x = p->y;
+    p->y = tmp;
+}
+
+// Neither parameter is written through, so both become shared references, which may alias
+static int add_coords(struct point *restrict a, struct point *restrict b) {
+    return a->x + b->y;
+}
+
+// The argument may be NULL, so this parameter keeps a raw pointer
+static int read_value(int *restrict p) {
+    return *p;
+}
+
+// These parameters are passed on to a function taking references
+static void scale_twice(int *restrict out, const int *restrict in) {
+    int tmp;
+    scale(&tmp, in, 2);
+    scale(out, &tmp, 2);
+}
+
+// Offsetting a pointer keeps it a raw pointer
+static int sum(const int *restrict xs, int n) {
+    int total = 0;
+    for (int i = 0; i < n; i++)
+        total += xs[i];
+    return total;
+}
+
+// Other translation units may call this with any pointer, so it keeps raw pointers
+void negate(int *restrict x) {
+    *x = -*x;
+}
+
+void restrict_test(unsigned buffer_size, int buffer[]) {
+    int a = 3, b;
+    struct point p = { 1, 2 };
+    int xs[3] = { 4, 5, 6 };
+    int *q = &a;
+
+    if (buffer_size < 8)
+        return;
+
+    scale(&b, &a, 5);
+    swap_point(&p);
+    scale_twice(&a, &b);
+
+    buffer[0] = a;
+    buffer[1] = b;
+    buffer[2] = p.x;
+    buffer[3] = p.y;
+    buffer[4] = sum(xs, 3);
+    negate(&b);
+    buffer[5] = b;
+    buffer[6] = add_coords(&p, &p);
+    buffer[7] = read_value(q);
+}
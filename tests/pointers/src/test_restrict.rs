@@ -0,0 +1,37 @@
+extern crate libc;
+
+use restrict::{rust_negate, rust_restrict_test};
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn restrict_test(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 8;
+
+pub fn test_restrict() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [60, 15, 2, 1, 15, -15, 3, 60];
+
+    unsafe {
+        restrict_test(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_restrict_test(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
+
+pub fn test_exported_params() {
+    let mut x = 7;
+    let p: *mut c_int = &mut x;
+
+    unsafe {
+        rust_negate(p);
+    }
+
+    assert_eq!(x, -7);
+}
@@ -0,0 +1,42 @@
+//! target_arch_aarch64
+#include <arm_neon.h>
+#include <string.h>
+
+// Loads, stores and lane accesses have no counterpart in `core::arch::aarch64` yet, so vectors
+// are copied in and out with memcpy
+
+void neon_arith(const float *xs, const float *ys, float *out) {
+    float32x4_t x, y;
+    float lanes[4];
+
+    memcpy(&x, xs, sizeof x);
+    memcpy(&y, ys, sizeof y);
+    float32x4_t sum = vaddq_f32(x, y);
+
+    memcpy(out, &sum, sizeof sum);
+    memcpy(lanes, &sum, sizeof sum);
+    out[4] = lanes[2];
+}
+
+void neon_unsigned(const unsigned *xs, unsigned *out) {
+    uint32x4_t x;
+    uint32x4_t ones = {1, 1, 1, 1};
+    unsigned short lanes[4];
+
+    memcpy(&x, xs, sizeof x);
+    uint32x4_t doubled = vaddq_u32(x, x);
+    uint16x4_t narrow = vmovn_u32(vaddq_u32(doubled, ones));
+
+    memcpy(out, &doubled, sizeof doubled);
+    memcpy(lanes, &narrow, sizeof narrow);
+    out[4] = lanes[0];
+    out[5] = lanes[3];
+}
+
+void neon_init(float *out) {
+    float32x4_t zero = {0};
+    float32x4_t v = {1.5f, 2.5f, 3.5f, 4.5f};
+    float32x4_t sum = vaddq_f32(zero, v);
+
+    memcpy(out, &sum, sizeof sum);
+}
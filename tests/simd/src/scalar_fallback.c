@@ -0,0 +1,26 @@
+//! scalar_simd_fallback
+#include <emmintrin.h>
+
+// The intrinsics used here are macros around builtins, shuffles, and conversions that have no
+// Rust equivalent, so they are translated into scalar code on the lanes of their operands.
+void scalar_fallback(const int input[8], int buffer[16]) {
+    __m128i a = _mm_setr_epi32(input[0], input[1], input[2], input[3]);
+    __m128i b = _mm_setr_epi32(input[4], input[5], input[6], input[7]);
+
+    // Lane extraction and insertion
+    buffer[0] = _mm_extract_epi16(a, 1);
+    buffer[1] = _mm_extract_epi16(_mm_insert_epi16(a, -2, 3), 3);
+
+    // Byte shift
+    __m128i c = _mm_srli_si128(b, 5);
+    _mm_storeu_si128((__m128i *)&buffer[2], c);
+
+    // Generic shuffle and conversion
+    __m128i d = (__m128i)__builtin_shufflevector((__v4si)a, (__v4si)b, 1, 4, 3, 6);
+    _mm_storeu_si128((__m128i *)&buffer[6], d);
+    __m128 e = (__m128)__builtin_convertvector((__v4si)b, __v4sf);
+    _mm_storeu_si128((__m128i *)&buffer[10], _mm_cvttps_epi32(_mm_mul_ps(e, _mm_set1_ps(0.5f))));
+
+    buffer[14] = _mm_extract_epi16(c, 0);
+    buffer[15] = _mm_extract_epi16(d, 7);
+}
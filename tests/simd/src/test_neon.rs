@@ -0,0 +1,62 @@
+//! target_arch_aarch64
+extern crate libc;
+
+use neon::{rust_neon_arith, rust_neon_init, rust_neon_unsigned};
+use self::libc::{c_float, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn neon_arith(xs: *const c_float, ys: *const c_float, out: *mut c_float);
+
+    #[no_mangle]
+    fn neon_unsigned(xs: *const c_uint, out: *mut c_uint);
+
+    #[no_mangle]
+    fn neon_init(out: *mut c_float);
+}
+
+pub fn test_neon_arith() {
+    let xs = [1.0, 2.0, 3.0, 4.0];
+    let ys = [0.5, -1.0, 2.0, 8.0];
+    let mut buffer = [0.0; 5];
+    let mut rust_buffer = [0.0; 5];
+    let expected_buffer = [1.5, 1.0, 5.0, 12.0, 5.0];
+
+    unsafe {
+        neon_arith(xs.as_ptr(), ys.as_ptr(), buffer.as_mut_ptr());
+        rust_neon_arith(xs.as_ptr(), ys.as_ptr(), rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
+
+pub fn test_neon_unsigned() {
+    let xs = [1, 2, 3, 0xffff];
+    let mut buffer = [0; 6];
+    let mut rust_buffer = [0; 6];
+    let expected_buffer = [2, 4, 6, 0x1fffe, 3, 0xffff];
+
+    unsafe {
+        neon_unsigned(xs.as_ptr(), buffer.as_mut_ptr());
+        rust_neon_unsigned(xs.as_ptr(), rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
+
+pub fn test_neon_init() {
+    let mut buffer = [0.0; 4];
+    let mut rust_buffer = [0.0; 4];
+    let expected_buffer = [1.5, 2.5, 3.5, 4.5];
+
+    unsafe {
+        neon_init(buffer.as_mut_ptr());
+        rust_neon_init(rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
@@ -0,0 +1,30 @@
+extern crate libc;
+
+use scalar_fallback::rust_scalar_fallback;
+use self::libc::c_int;
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn scalar_fallback(input: *const c_int, buffer: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 16;
+
+pub fn test_scalar_fallback() {
+    let input = [1, -2, 0x12345678, -0x7654321, 42, -43, 1 << 20, -(1 << 20)];
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [
+        0, 65534, 16777215, 4096, 16773120, 0, -2, 42, -124076833, 1048576, 21, -21, 524288,
+        -524288, 65535, 16,
+    ];
+
+    unsafe {
+        scalar_fallback(input.as_ptr(), buffer.as_mut_ptr());
+        rust_scalar_fallback(input.as_ptr(), rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}
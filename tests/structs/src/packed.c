@@ -0,0 +1,58 @@
+#include <string.h>
+
+struct __attribute__((packed)) packed {
+    char tag;
+    int value;
+    short arr[3];
+};
+
+#pragma pack(push, 2)
+struct pack2 {
+    char tag;
+    long long wide;
+};
+#pragma pack(pop)
+
+// Fields of packed structs may be misaligned, so they are only accessed through memcpy
+static void write_int(int *p, int v) {
+    memcpy(p, &v, sizeof v);
+}
+
+static void write_short(short *p, short v) {
+    memcpy(p, &v, sizeof v);
+}
+
+static struct packed *get(struct packed *p, int *calls) {
+    (*calls)++;
+    return p;
+}
+
+void packed_test(unsigned buffer_size, int buffer[]) {
+    struct packed p = { 1, 2, { 3, 4, 5 } };
+    struct pack2 q = { 6, 7 };
+    long long wide = 70;
+    int calls = 0;
+    short *arr;
+
+    if (buffer_size < 10)
+        return;
+
+    write_int(&p.value, 20);
+    arr = p.arr;
+    write_short(arr + 1, 40);
+    memcpy(&q.wide, &wide, sizeof wide);
+
+    get(&p, &calls)->value += 1;
+    get(&p, &calls)->arr[2] *= 2;
+
+    buffer[0] = p.tag;
+    buffer[1] = p.value;
+    buffer[2] = p.arr[0];
+    buffer[3] = p.arr[1];
+    buffer[4] = p.arr[2];
+    buffer[5] = q.tag;
+    buffer[6] = (int)q.wide;
+    buffer[7] = calls;
+    buffer[8] = sizeof(struct packed);
+    buffer[9] = sizeof(struct pack2);
+}
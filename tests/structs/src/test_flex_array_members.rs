@@ -1,6 +1,6 @@
 extern crate libc;
 
-use flex_array_members::{rust_exercise_flex_arrays};
+use flex_array_members::{c89_flex, c99_flex, gnu_flex, rust_exercise_flex_arrays};
 use self::libc::{c_int, c_uint, size_t};
 
 #[link(name = "test")]
@@ -24,3 +24,26 @@ pub fn test_flex_array_members() {
     assert_eq!(buffer, rust_buffer);
     assert_eq!(buffer, expected_buffer);
 }
+
+pub fn test_flex_array_accessors() {
+    // The struct header (`x`) followed by room for 4 flexible array elements
+    let mut storage: [c_int; 5] = [1, 2, 3, 4, 5];
+
+    unsafe {
+        let s = &mut *(storage.as_mut_ptr() as *mut gnu_flex);
+        assert_eq!(s.x, 1);
+        assert_eq!(s.flex(4), &[2, 3, 4, 5]);
+        s.flex_mut(4)[3] = 10;
+
+        let t = &mut *(storage.as_mut_ptr() as *mut c99_flex);
+        assert_eq!(t.flex(4), &[2, 3, 4, 10]);
+        t.flex_mut(4)[0] = 20;
+
+        let u = &mut *(storage.as_mut_ptr() as *mut c89_flex);
+        assert_eq!(u.flex(4), &[20, 3, 4, 10]);
+        u.flex_mut(4).copy_from_slice(&[6, 7, 8, 9]);
+        assert!(u.flex(0).is_empty());
+    }
+
+    assert_eq!(storage, [1, 6, 7, 8, 9]);
+}
@@ -0,0 +1,26 @@
+extern crate libc;
+
+use packed::rust_packed_test;
+use self::libc::{c_int, c_uint};
+
+#[link(name = "test")]
+extern "C" {
+    #[no_mangle]
+    fn packed_test(_: c_uint, _: *mut c_int);
+}
+
+const BUFFER_SIZE: usize = 10;
+
+pub fn test_packed() {
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut rust_buffer = [0; BUFFER_SIZE];
+    let expected_buffer = [1, 21, 3, 40, 10, 6, 70, 2, 11, 10];
+
+    unsafe {
+        packed_test(BUFFER_SIZE as c_uint, buffer.as_mut_ptr());
+        rust_packed_test(BUFFER_SIZE as c_uint, rust_buffer.as_mut_ptr());
+    }
+
+    assert_eq!(buffer, rust_buffer);
+    assert_eq!(buffer, expected_buffer);
+}